@@ -1,4 +1,9 @@
+use crate::solver::{
+  get_orientation_from_machine_sequences, is_acyclic_orientation, IntermediateSolution,
+};
 use ndarray::Array1;
+use std::cmp::{self, Ordering};
+use std::error::Error;
 
 pub type Machine = usize;
 pub type Duration = u32;
@@ -17,6 +22,26 @@ pub struct Instance {
 
   pub durations: Array1<Duration>,
   pub machines: Array1<Machine>,
+  // Id of the first operation of each job followed by the total number of operations
+  // Jobs may consist of a differing number of operations
+  pub job_offsets: Array1<OpId>,
+  // Due date of each job, only required by tardiness-based objectives
+  pub due_dates: Option<Array1<Time>>,
+  // Per-job weights of the weighted earliness-tardiness objective, all weights are 1 if None
+  pub job_weights: Option<JobWeights>,
+  // Pairs (a, b) of operation indices within each job where a has to complete before b starts, replacing the linear
+  // chain of every job (see job_precedence_pairs); a < b is required, so the chain is always a valid order
+  // Only the labeling without an IntermediateSolution (see release_and_tail_times), verification, the lower bounds and
+  // the job completion objectives support general precedences so far, not the solvers
+  pub job_precedences: Option<Vec<Vec<(usize, usize)>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobWeights {
+  // Cost per time unit a job completes before its due date
+  pub earliness: Array1<u32>,
+  // Cost per time unit a job completes after its due date
+  pub tardiness: Array1<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -25,11 +50,42 @@ pub struct Solution {
 }
 
 impl Instance {
+  pub fn from_jobs(n_machines: usize, jobs: &[Vec<(Machine, Duration)>]) -> Self {
+    let n_jobs = jobs.len();
+    let n_ops: usize = jobs.iter().map(|job| job.len()).sum();
+
+    let mut durations = Array1::<Duration>::from_elem(n_ops, 0);
+    let mut machines = Array1::<Machine>::from_elem(n_ops, 0);
+    let mut job_offsets = Array1::<OpId>::from_elem(n_jobs + 1, 0);
+
+    let mut op = 0;
+    for (j, job) in jobs.iter().enumerate() {
+      job_offsets[j] = op;
+      for &(machine, duration) in job {
+        machines[op] = machine;
+        durations[op] = duration;
+        op += 1;
+      }
+    }
+    job_offsets[n_jobs] = op;
+
+    return Instance {
+      n_machines: n_machines,
+      n_jobs: n_jobs,
+      durations: durations,
+      machines: machines,
+      job_offsets: job_offsets,
+      due_dates: None,
+      job_weights: None,
+      job_precedences: None,
+    };
+  }
+
   pub fn ops(&self) -> Vec<Op> {
     let mut nodes = Vec::new();
 
     for j in 0..self.n_jobs {
-      for o in 0..self.n_machines {
+      for o in 0..self.job_len(j) {
         nodes.push([j, o]);
       }
     }
@@ -43,20 +99,376 @@ impl Instance {
 
   pub fn op_to_id(&self, op: Op) -> OpId {
     let [j, o] = op;
-    return j * self.n_machines + o;
+    return self.job_offsets[j] + o;
   }
 
   pub fn op_from_id(&self, id: OpId) -> Op {
-    let j = id / self.n_machines;
-    let o = id % self.n_machines;
+    // Last job starting at or before id (skips over empty jobs)
+    let j = self
+      .job_offsets
+      .as_slice()
+      .unwrap()
+      .binary_search_by(|&offset| {
+        if offset <= id {
+          Ordering::Less
+        } else {
+          Ordering::Greater
+        }
+      })
+      .unwrap_err()
+      - 1;
+    let o = id - self.job_offsets[j];
     return [j, o];
   }
 
+  pub fn job_len(&self, job: usize) -> usize {
+    return self.job_offsets[job + 1] - self.job_offsets[job];
+  }
+
+  pub fn is_rectangular(&self) -> bool {
+    return (0..self.n_jobs).all(|j| self.job_len(j) == self.n_machines);
+  }
+
   pub fn shape(&self) -> (usize, usize) {
     return (self.n_jobs, self.n_machines);
   }
 
   pub fn n_ops(&self) -> usize {
-    return self.n_jobs * self.n_machines;
+    return self.job_offsets[self.n_jobs];
+  }
+
+  // Explicit precedences of the job if any, otherwise the chain (0, 1), (1, 2), ...
+  pub fn job_precedence_pairs(&self, job: usize) -> Vec<(usize, usize)> {
+    return match &self.job_precedences {
+      Some(precedences) => precedences[job].clone(),
+      None => (1..self.job_len(job)).map(|o| (o - 1, o)).collect(),
+    };
+  }
+
+  // Multiplies every duration by factor
+  // Errors if a duration or the sum of all durations (an upper bound on the cmax of any semi-active schedule) overflows
+  pub fn scale_durations(&self, factor: u32) -> Result<Instance, String> {
+    let total: u64 = self.durations.iter().map(|&d| d as u64).sum();
+    if total
+      .checked_mul(factor as u64)
+      .map_or(true, |bound| bound > Time::MAX as u64)
+    {
+      return Err(format!(
+        "Scaling by {} overflows the cmax bound {} * {}",
+        factor, total, factor
+      ));
+    }
+
+    let mut durations = self.durations.clone();
+    for d in durations.iter_mut() {
+      *d = d
+        .checked_mul(factor)
+        .ok_or(format!("Scaling duration {} by {} overflows", d, factor))?;
+    }
+
+    // Due dates are in the same time unit as the durations
+    let due_dates = match &self.due_dates {
+      Some(due_dates) => {
+        let mut scaled = due_dates.clone();
+        for d in scaled.iter_mut() {
+          *d = d
+            .checked_mul(factor)
+            .ok_or(format!("Scaling due date {} by {} overflows", d, factor))?;
+        }
+        Some(scaled)
+      }
+      None => None,
+    };
+
+    return Ok(Instance {
+      durations: durations,
+      due_dates: due_dates,
+      ..self.clone()
+    });
+  }
+
+  // Jobs of self followed by those of other on the same machines, the op ids of other are shifted by self.n_ops()
+  // Due dates are kept if both instances have them
+  pub fn merge(&self, other: &Instance) -> Result<Instance, String> {
+    if self.n_machines != other.n_machines {
+      return Err(format!(
+        "Cannot merge instances with {} and {} machines",
+        self.n_machines, other.n_machines
+      ));
+    }
+    let due_dates = match (&self.due_dates, &other.due_dates) {
+      (Some(a), Some(b)) => Some(a.iter().chain(b.iter()).copied().collect()),
+      (None, None) => None,
+      _ => return Err("Cannot merge an instance with due dates and one without".to_string()),
+    };
+    let job_weights = match (&self.job_weights, &other.job_weights) {
+      (Some(a), Some(b)) => Some(JobWeights {
+        earliness: a
+          .earliness
+          .iter()
+          .chain(b.earliness.iter())
+          .copied()
+          .collect(),
+        tardiness: a
+          .tardiness
+          .iter()
+          .chain(b.tardiness.iter())
+          .copied()
+          .collect(),
+      }),
+      (None, None) => None,
+      _ => return Err("Cannot merge an instance with job weights and one without".to_string()),
+    };
+
+    // Jobs of an instance without explicit precedences keep their chains
+    let job_precedences = match (&self.job_precedences, &other.job_precedences) {
+      (None, None) => None,
+      _ => Some(
+        (0..self.n_jobs)
+          .map(|j| self.job_precedence_pairs(j))
+          .chain((0..other.n_jobs).map(|j| other.job_precedence_pairs(j)))
+          .collect(),
+      ),
+    };
+
+    let n_ops = self.n_ops();
+    let job_offsets = self
+      .job_offsets
+      .iter()
+      .take(self.n_jobs)
+      .copied()
+      .chain(other.job_offsets.iter().map(|&offset| offset + n_ops))
+      .collect();
+
+    return Ok(Instance {
+      n_machines: self.n_machines,
+      n_jobs: self.n_jobs + other.n_jobs,
+      durations: self
+        .durations
+        .iter()
+        .chain(other.durations.iter())
+        .copied()
+        .collect(),
+      machines: self
+        .machines
+        .iter()
+        .chain(other.machines.iter())
+        .copied()
+        .collect(),
+      job_offsets: job_offsets,
+      due_dates: due_dates,
+      job_weights: job_weights,
+      job_precedences: job_precedences,
+    });
+  }
+
+  // Durations divided by the largest duration of the instance (scheduling always uses the raw durations)
+  pub fn normalized_durations(&self) -> Array1<f64> {
+    let max_duration = self.durations.iter().copied().max().unwrap_or(0);
+    return self.durations.map(|&d| normalize(d, max_duration));
+  }
+
+  // Durations divided by the largest duration on the same machine
+  pub fn per_machine_normalized(&self) -> Array1<f64> {
+    let mut max_durations = Array1::<Duration>::from_elem(self.n_machines, 0);
+    for op in 0..self.n_ops() {
+      let m = self.machines[op];
+      max_durations[m] = cmp::max(max_durations[m], self.durations[op]);
+    }
+
+    let mut normalized = Array1::<f64>::from_elem(self.n_ops(), 0.0);
+    for op in 0..self.n_ops() {
+      normalized[op] = normalize(self.durations[op], max_durations[self.machines[op]]);
+    }
+    return normalized;
+  }
+}
+
+impl Solution {
+  // Earliest start schedule for the given job order of each machine (see get_orientation_from_machine_sequences)
+  pub fn from_machine_sequences(
+    inst: &Instance,
+    sequences: &[Vec<usize>],
+  ) -> Result<Solution, Box<dyn Error>> {
+    let edges = get_orientation_from_machine_sequences(inst, sequences)?;
+    if !is_acyclic_orientation(inst, &edges) {
+      Err("Machine sequences induce a cycle with the job precedences")?;
+    }
+
+    return Ok(IntermediateSolution::new(inst.clone(), edges).to_solution());
+  }
+
+  // Start time of operation op of job, panics if the job or operation is out of range
+  pub fn start_time(&self, inst: &Instance, job: usize, op: usize) -> Time {
+    assert!(
+      job < inst.n_jobs && op < inst.job_len(job),
+      "Operation {} of job {} out of range (n_jobs={})",
+      op,
+      job,
+      inst.n_jobs
+    );
+    return self.start_times[inst.op_to_id([job, op])];
+  }
+
+  // End time of operation op of job, panics if the job or operation is out of range
+  pub fn end_time(&self, inst: &Instance, job: usize, op: usize) -> Time {
+    return self.start_time(inst, job, op) + inst.durations[inst.op_to_id([job, op])];
+  }
+
+  // Start times with one row per job and one column per operation (so rows of ragged instances differ in length)
+  pub fn as_matrix(&self, inst: &Instance) -> Vec<Vec<Time>> {
+    assert_eq!(
+      self.start_times.len(),
+      inst.n_ops(),
+      "Solution does not match the instance"
+    );
+    return (0..inst.n_jobs)
+      .map(|j| {
+        (0..inst.job_len(j))
+          .map(|o| self.start_times[inst.op_to_id([j, o])])
+          .collect()
+      })
+      .collect();
+  }
+}
+
+// Zero-duration maxima map everything to zero instead of NaN
+fn normalize(duration: Duration, max_duration: Duration) -> f64 {
+  if max_duration == 0 {
+    return 0.0;
+  }
+  return duration as f64 / max_duration as f64;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{calculate_cmax, priority};
+  use crate::test_instances::ft06;
+
+  #[test]
+  fn normalized_durations() {
+    let inst = ft06();
+    let normalized = inst.normalized_durations();
+    assert!(normalized.iter().all(|&x| (0.0..=1.0).contains(&x)));
+    let max_duration = *inst.durations.iter().max().unwrap();
+    for op in inst.op_ids() {
+      assert_eq!(normalized[op] == 1.0, inst.durations[op] == max_duration);
+    }
+
+    let per_machine = inst.per_machine_normalized();
+    assert!(per_machine.iter().all(|&x| (0.0..=1.0).contains(&x)));
+    for m in 0..inst.n_machines {
+      let ops: Vec<OpId> = inst
+        .op_ids()
+        .into_iter()
+        .filter(|&op| inst.machines[op] == m)
+        .collect();
+      let max_duration = ops.iter().map(|&op| inst.durations[op]).max().unwrap();
+      for op in ops {
+        assert_eq!(per_machine[op] == 1.0, inst.durations[op] == max_duration);
+      }
+    }
+  }
+
+  #[test]
+  fn scale_durations() {
+    let inst = Instance::from_jobs(2, &[vec![(0, 3), (1, 2)], vec![(0, 2), (1, 4)]]);
+    let scaled = inst.scale_durations(10).unwrap();
+    assert_eq!(scaled.durations.to_vec(), vec![30, 20, 20, 40]);
+
+    // The scaled durations fit, but their sum 11 * 2^29 does not
+    let factor = 1 << 29;
+    assert!(inst
+      .durations
+      .iter()
+      .all(|&d| d.checked_mul(factor).is_some()));
+    assert!(inst.scale_durations(factor).is_err());
+
+    let inst = Instance::from_jobs(1, &[vec![(0, 1 << 16)]]);
+    assert!(inst.scale_durations(1 << 15).is_ok());
+    assert!(inst.scale_durations(1 << 16).is_err());
+  }
+
+  #[test]
+  fn from_machine_sequences() {
+    let inst = Instance::from_jobs(2, &[vec![(0, 3), (1, 2)], vec![(1, 4), (0, 1)]]);
+    // Job 0 first on machine 0, job 1 first on machine 1: both wait for the other, cmax max(3, 4) + 2
+    let solution = Solution::from_machine_sequences(&inst, &[vec![0, 1], vec![1, 0]]).unwrap();
+    assert_eq!(solution.start_times.to_vec(), vec![0, 4, 0, 4]);
+    assert_eq!(calculate_cmax(&inst, &solution), 6);
+
+    // Job 1 first on machine 0 and job 0 first on machine 1 needs both second operations before the first ones
+    let cyclic = Solution::from_machine_sequences(&inst, &[vec![1, 0], vec![0, 1]]);
+    assert!(cyclic.unwrap_err().to_string().contains("cycle"));
+    let incomplete = Solution::from_machine_sequences(&inst, &[vec![0], vec![1, 0]]);
+    assert!(incomplete.is_err());
+  }
+
+  #[test]
+  fn merge() {
+    let a = Instance::from_jobs(
+      3,
+      &[vec![(0, 3), (1, 2), (2, 2)], vec![(1, 4), (0, 1), (2, 3)]],
+    );
+    let b = Instance::from_jobs(
+      3,
+      &[vec![(2, 1), (0, 2), (1, 5)], vec![(0, 2), (2, 2), (1, 1)]],
+    );
+    let merged = a.merge(&b).unwrap();
+    assert_eq!(
+      (merged.n_jobs, merged.n_machines, merged.n_ops()),
+      (4, 3, 12)
+    );
+    assert_eq!(merged.job_offsets.to_vec(), vec![0, 3, 6, 9, 12]);
+    // The operations of b follow those of a with their jobs renumbered
+    for job in 0..2 {
+      for op in 0..3 {
+        let merged_op = merged.op_to_id([job + 2, op]);
+        assert_eq!(
+          merged.durations[merged_op],
+          b.durations[b.op_to_id([job, op])]
+        );
+        assert_eq!(
+          merged.machines[merged_op],
+          b.machines[b.op_to_id([job, op])]
+        );
+      }
+    }
+
+    let solution = crate::solver::priority::find_solution_sps(&merged, None);
+    crate::solver::verify_solution(&merged, &solution).unwrap();
+    // Machine 1 has a load of 2 + 4 from a and 5 + 1 from b
+    assert!(calculate_cmax(&merged, &solution) >= 12);
+
+    let other_machines = Instance::from_jobs(2, &[vec![(0, 1), (1, 1)]]);
+    assert!(a.merge(&other_machines).is_err());
+  }
+
+  #[test]
+  fn start_time_matrix() {
+    let inst = ft06();
+    let solution = priority::find_solution_mwrm(&inst, None);
+    let matrix = solution.as_matrix(&inst);
+    assert_eq!(matrix.len(), inst.n_jobs);
+    for (j, row) in matrix.iter().enumerate() {
+      assert_eq!(row.len(), inst.n_machines);
+      for (o, &start) in row.iter().enumerate() {
+        let op = inst.op_to_id([j, o]);
+        assert_eq!(start, solution.start_times[op]);
+        assert_eq!(solution.start_time(&inst, j, o), solution.start_times[op]);
+        assert_eq!(
+          solution.end_time(&inst, j, o),
+          solution.start_times[op] + inst.durations[op]
+        );
+      }
+    }
+  }
+
+  #[test]
+  #[should_panic(expected = "out of range")]
+  fn start_time_out_of_range() {
+    let inst = ft06();
+    priority::find_solution_sps(&inst, None).start_time(&inst, 0, inst.n_machines);
   }
 }
@@ -0,0 +1,53 @@
+use crate::data::Instance;
+use ndarray::Array1;
+
+// Heuristic difficulty ln(1 + n_jobs * n_machines) * (1 + cv) * (1 + max_share) for triaging which instances need
+// longer budgets, where cv is the coefficient of variation (population standard deviation over mean) of the machine
+// loads and max_share the load of the most loaded machine divided by the total load (both 0 without any load)
+// Only comparable between instances, larger is harder
+pub fn estimate_difficulty(inst: &Instance) -> f64 {
+  let mut machine_loads = Array1::<f64>::from_elem(inst.n_machines, 0.0);
+  for op in 0..inst.n_ops() {
+    machine_loads[inst.machines[op]] += inst.durations[op] as f64;
+  }
+
+  let size = (inst.n_jobs * inst.n_machines) as f64;
+  let total_load = machine_loads.sum();
+  let (cv, max_share) = if total_load > 0.0 {
+    let mean = total_load / inst.n_machines as f64;
+    let variance = machine_loads
+      .iter()
+      .map(|load| (load - mean).powi(2))
+      .sum::<f64>()
+      / inst.n_machines as f64;
+    let max_load = machine_loads.iter().copied().fold(0.0, f64::max);
+    (variance.sqrt() / mean, max_load / total_load)
+  } else {
+    (0.0, 0.0)
+  };
+
+  return (1.0 + size).ln() * (1.0 + cv) * (1.0 + max_share);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn imbalanced_large_instances_are_harder() {
+    // Both machines carry a load of 5
+    let balanced = Instance::from_jobs(2, &[vec![(0, 2), (1, 3)], vec![(1, 2), (0, 3)]]);
+    let expected = 5f64.ln() * 1.5;
+    assert!((estimate_difficulty(&balanced) - expected).abs() < 1e-9);
+
+    // 10 jobs spending most of their time on machine 0
+    let jobs: Vec<Vec<(usize, u32)>> = (0..10)
+      .map(|_| (0..5).map(|m| (m, if m == 0 { 20 } else { 1 })).collect())
+      .collect();
+    let imbalanced = Instance::from_jobs(5, &jobs);
+    assert!(estimate_difficulty(&balanced) < estimate_difficulty(&imbalanced));
+
+    let idle = Instance::from_jobs(2, &[vec![(0, 0), (1, 0)]]);
+    assert_eq!(estimate_difficulty(&idle), 3f64.ln());
+  }
+}
@@ -0,0 +1,256 @@
+use crate::data::{Instance, Solution};
+use crate::solver::calculate_cmax;
+use std::cmp;
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GanttLayout {
+  // One row per machine, operations colored by job
+  ByMachine,
+  // One row per job, operations colored by machine
+  ByJob,
+}
+
+impl FromStr for GanttLayout {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    return match s {
+      "machine" => Ok(GanttLayout::ByMachine),
+      "job" => Ok(GanttLayout::ByJob),
+      _ => Err(format!("Unknown Gantt layout {}", s)),
+    };
+  }
+}
+
+// Fill color of the operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GanttColoring {
+  ByJob,
+  ByMachine,
+  // One SVG color per job in the machine layout and per machine in the job layout
+  Custom(Vec<String>),
+}
+
+impl GanttColoring {
+  // Colors the operations by job in the machine layout and by machine in the job layout
+  pub fn default_for(layout: GanttLayout) -> Self {
+    return match layout {
+      GanttLayout::ByMachine => GanttColoring::ByJob,
+      GanttLayout::ByJob => GanttColoring::ByMachine,
+    };
+  }
+
+  // Fails if a custom palette does not have exactly one color per job (machine layout) or per machine (job layout)
+  pub fn validate(&self, inst: &Instance, layout: GanttLayout) -> Result<(), String> {
+    if let GanttColoring::Custom(colors) = self {
+      let (expected, entity) = match layout {
+        GanttLayout::ByMachine => (inst.n_jobs, "job"),
+        GanttLayout::ByJob => (inst.n_machines, "machine"),
+      };
+      if colors.len() != expected {
+        return Err(format!(
+          "Gantt palette has {} colors, expected one per {} ({})",
+          colors.len(),
+          entity,
+          expected
+        ));
+      }
+    }
+    return Ok(());
+  }
+
+  fn color(&self, inst: &Instance, layout: GanttLayout, job: usize, machine: usize) -> String {
+    return match self {
+      GanttColoring::ByJob => color_of(job, inst.n_jobs),
+      GanttColoring::ByMachine => color_of(machine, inst.n_machines),
+      GanttColoring::Custom(colors) => match layout {
+        GanttLayout::ByMachine => colors[job].clone(),
+        GanttLayout::ByJob => colors[machine].clone(),
+      },
+    };
+  }
+}
+
+impl FromStr for GanttColoring {
+  type Err = String;
+
+  // job, machine or a comma separated list of SVG colors, e.g. red,#00ff00,rgb(0,0,255)
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    return match s {
+      "job" => Ok(GanttColoring::ByJob),
+      "machine" => Ok(GanttColoring::ByMachine),
+      _ => {
+        let colors = split_colors(s);
+        for color in &colors {
+          if color.is_empty() || color.contains(&['"', '<', '>', '&'][..]) {
+            return Err(format!("Invalid Gantt color {:?} in {}", color, s));
+          }
+        }
+        Ok(GanttColoring::Custom(colors))
+      }
+    };
+  }
+}
+
+// Splits at the commas outside of parentheses, so functional colors like rgb(0,0,255) stay whole
+fn split_colors(s: &str) -> Vec<String> {
+  let mut colors = vec![String::new()];
+  let mut depth = 0usize;
+  for c in s.chars() {
+    match c {
+      ',' if depth == 0 => colors.push(String::new()),
+      _ => {
+        if c == '(' {
+          depth += 1;
+        } else if c == ')' {
+          depth = depth.saturating_sub(1);
+        }
+        colors.last_mut().unwrap().push(c);
+      }
+    }
+  }
+  return colors
+    .iter()
+    .map(|color| color.trim().to_string())
+    .collect();
+}
+
+const WIDTH: f64 = 1000.0;
+const LABEL_WIDTH: f64 = 40.0;
+const ROW_HEIGHT: f64 = 20.0;
+const ROW_GAP: f64 = 4.0;
+
+// Gantt chart with one rectangle per operation (also for zero durations), scaled so that cmax spans WIDTH
+// Fails if the coloring is invalid for the instance and layout, see GanttColoring::validate
+pub fn to_svg(
+  inst: &Instance,
+  solution: &Solution,
+  layout: GanttLayout,
+  coloring: &GanttColoring,
+) -> Result<String, String> {
+  coloring.validate(inst, layout)?;
+  let cmax = calculate_cmax(inst, solution);
+  let scale = WIDTH / cmp::max(cmax, 1) as f64;
+  let (n_rows, row_prefix) = match layout {
+    GanttLayout::ByMachine => (inst.n_machines, "M"),
+    GanttLayout::ByJob => (inst.n_jobs, "J"),
+  };
+  let height = n_rows as f64 * (ROW_HEIGHT + ROW_GAP) + ROW_GAP;
+
+  let mut svg = String::new();
+  writeln!(
+    svg,
+    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}">"#,
+    LABEL_WIDTH + WIDTH,
+    height
+  )
+  .unwrap();
+  for row in 0..n_rows {
+    writeln!(
+      svg,
+      r#"<text x="0" y="{:.2}" font-size="12">{}{}</text>"#,
+      row_y(row) + ROW_HEIGHT * 0.75,
+      row_prefix,
+      row
+    )
+    .unwrap();
+  }
+  for op in 0..inst.n_ops() {
+    let [j, o] = inst.op_from_id(op);
+    let m = inst.machines[op];
+    let row = match layout {
+      GanttLayout::ByMachine => m,
+      GanttLayout::ByJob => j,
+    };
+    let start = solution.start_times[op];
+    let duration = inst.durations[op];
+    writeln!(
+      svg,
+      r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" stroke="black" stroke-width="0.5"><title>job {} operation {} on machine {}: {}-{}</title></rect>"#,
+      LABEL_WIDTH + start as f64 * scale,
+      row_y(row),
+      duration as f64 * scale,
+      ROW_HEIGHT,
+      coloring.color(inst, layout, j, m),
+      j,
+      o,
+      m,
+      start,
+      start + duration
+    )
+    .unwrap();
+  }
+  svg.push_str("</svg>\n");
+
+  return Ok(svg);
+}
+
+fn row_y(row: usize) -> f64 {
+  return ROW_GAP + row as f64 * (ROW_HEIGHT + ROW_GAP);
+}
+
+// Evenly spaced hues, so the colors only depend on the index and the number of colors
+fn color_of(index: usize, n_colors: usize) -> String {
+  let hue = index as f64 * 360.0 / cmp::max(n_colors, 1) as f64;
+  return format!("hsl({:.0},70%,60%)", hue);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::priority;
+  use crate::test_instances::ft06;
+  use std::collections::HashSet;
+
+  #[test]
+  fn every_operation_is_rendered() {
+    // Includes a zero-duration operation, which is drawn with zero width
+    let ragged = Instance::from_jobs(3, &[vec![(0, 2), (2, 0), (1, 3)], vec![(1, 1)]]);
+    for inst in &[ft06(), ragged] {
+      let solution = priority::find_solution_sps(inst, None);
+      for &layout in &[GanttLayout::ByMachine, GanttLayout::ByJob] {
+        let svg = to_svg(inst, &solution, layout, &GanttColoring::default_for(layout)).unwrap();
+        assert_eq!(svg.matches("<rect ").count(), inst.n_ops());
+        let n_rows = match layout {
+          GanttLayout::ByMachine => inst.n_machines,
+          GanttLayout::ByJob => inst.n_jobs,
+        };
+        assert_eq!(svg.matches("<text ").count(), n_rows);
+      }
+    }
+  }
+
+  #[test]
+  fn one_color_per_machine() {
+    let inst = ft06();
+    let solution = priority::find_solution_sps(&inst, None);
+    let svg = to_svg(
+      &inst,
+      &solution,
+      GanttLayout::ByJob,
+      &GanttColoring::ByMachine,
+    )
+    .unwrap();
+    for op in 0..inst.n_ops() {
+      let m = inst.machines[op];
+      let [j, o] = inst.op_from_id(op);
+      let fill = format!(r#"fill="{}""#, color_of(m, inst.n_machines));
+      let title = format!("<title>job {} operation {} on machine {}:", j, o, m);
+      assert!(svg
+        .lines()
+        .any(|line| line.contains(&fill) && line.contains(&title)));
+    }
+    let fills: HashSet<&str> = svg
+      .lines()
+      .filter_map(|line| line.split(r#"fill=""#).nth(1))
+      .map(|rest| rest.split('"').next().unwrap())
+      .collect();
+    assert_eq!(fills.len(), inst.n_machines);
+
+    // An invalid palette is an error instead of a panic
+    let palette = GanttColoring::Custom(vec!["red".to_string()]);
+    assert!(to_svg(&inst, &solution, GanttLayout::ByJob, &palette).is_err());
+  }
+}
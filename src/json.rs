@@ -0,0 +1,223 @@
+use std::fmt::Write;
+
+// Minimal JSON values, numbers are kept as written so integers are parsed without loss
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+  Null,
+  Bool(bool),
+  Number(String),
+  String(String),
+  Array(Vec<JsonValue>),
+  // Members in the order of the input
+  Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+  // Value of the first member with the given key
+  pub fn get(&self, key: &str) -> Option<&JsonValue> {
+    return match self {
+      JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+      _ => None,
+    };
+  }
+}
+
+pub fn parse(s: &str) -> Result<JsonValue, String> {
+  let mut parser = Parser {
+    input: s.as_bytes(),
+    pos: 0,
+  };
+  let value = parser.value()?;
+  parser.skip_whitespace();
+  if parser.pos < parser.input.len() {
+    return Err(parser.error("trailing characters"));
+  }
+  return Ok(value);
+}
+
+// String literal including quotes, with all characters escaped that JSON requires to be
+pub fn quote(s: &str) -> String {
+  let mut quoted = String::with_capacity(s.len() + 2);
+  quoted.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+      c => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  return quoted;
+}
+
+// Line of --stream-jsonl for one instance (without newline): {"cmax":<cmax>,"ok":true} or {"ok":false,"error":<message>}
+pub fn stream_line(result: &Result<u32, String>) -> String {
+  return match result {
+    Ok(cmax) => format!("{{\"cmax\":{},\"ok\":true}}", cmax),
+    Err(e) => format!("{{\"ok\":false,\"error\":{}}}", quote(e)),
+  };
+}
+
+struct Parser<'a> {
+  input: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn error(&self, message: &str) -> String {
+    return format!("{} at byte {}", message, self.pos + 1);
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.pos < self.input.len() && b" \t\n\r".contains(&self.input[self.pos]) {
+      self.pos += 1;
+    }
+  }
+
+  fn peek(&mut self) -> Option<u8> {
+    self.skip_whitespace();
+    return self.input.get(self.pos).copied();
+  }
+
+  fn expect(&mut self, c: u8) -> Result<(), String> {
+    if self.peek() != Some(c) {
+      return Err(self.error(&format!("expected '{}'", c as char)));
+    }
+    self.pos += 1;
+    return Ok(());
+  }
+
+  fn literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, String> {
+    if !self.input[self.pos..].starts_with(literal.as_bytes()) {
+      return Err(self.error("invalid literal"));
+    }
+    self.pos += literal.len();
+    return Ok(value);
+  }
+
+  fn value(&mut self) -> Result<JsonValue, String> {
+    return match self.peek() {
+      Some(b'{') => self.object(),
+      Some(b'[') => self.array(),
+      Some(b'"') => Ok(JsonValue::String(self.string()?)),
+      Some(b'n') => self.literal("null", JsonValue::Null),
+      Some(b't') => self.literal("true", JsonValue::Bool(true)),
+      Some(b'f') => self.literal("false", JsonValue::Bool(false)),
+      Some(c) if c == b'-' || c.is_ascii_digit() => self.number(),
+      Some(_) => Err(self.error("unexpected character")),
+      None => Err(self.error("unexpected end of input")),
+    };
+  }
+
+  fn object(&mut self) -> Result<JsonValue, String> {
+    self.expect(b'{')?;
+    let mut members = Vec::new();
+    if self.peek() == Some(b'}') {
+      self.pos += 1;
+      return Ok(JsonValue::Object(members));
+    }
+    loop {
+      if self.peek() != Some(b'"') {
+        return Err(self.error("expected a key"));
+      }
+      let key = self.string()?;
+      self.expect(b':')?;
+      members.push((key, self.value()?));
+      match self.peek() {
+        Some(b',') => self.pos += 1,
+        Some(b'}') => {
+          self.pos += 1;
+          return Ok(JsonValue::Object(members));
+        }
+        _ => return Err(self.error("expected ',' or '}'")),
+      }
+    }
+  }
+
+  fn array(&mut self) -> Result<JsonValue, String> {
+    self.expect(b'[')?;
+    let mut elements = Vec::new();
+    if self.peek() == Some(b']') {
+      self.pos += 1;
+      return Ok(JsonValue::Array(elements));
+    }
+    loop {
+      elements.push(self.value()?);
+      match self.peek() {
+        Some(b',') => self.pos += 1,
+        Some(b']') => {
+          self.pos += 1;
+          return Ok(JsonValue::Array(elements));
+        }
+        _ => return Err(self.error("expected ',' or ']'")),
+      }
+    }
+  }
+
+  fn number(&mut self) -> Result<JsonValue, String> {
+    let start = self.pos;
+    while self.pos < self.input.len() && b"+-.eE0123456789".contains(&self.input[self.pos]) {
+      self.pos += 1;
+    }
+    // Only ASCII was consumed
+    let number = std::str::from_utf8(&self.input[start..self.pos]).unwrap();
+    if number.parse::<f64>().is_err() {
+      self.pos = start;
+      return Err(self.error("invalid number"));
+    }
+    return Ok(JsonValue::Number(number.to_string()));
+  }
+
+  fn string(&mut self) -> Result<String, String> {
+    self.expect(b'"')?;
+    let mut bytes = Vec::new();
+    loop {
+      let c = *self
+        .input
+        .get(self.pos)
+        .ok_or_else(|| self.error("unterminated string"))?;
+      self.pos += 1;
+      match c {
+        b'"' => break,
+        b'\\' => {
+          let escaped = *self
+            .input
+            .get(self.pos)
+            .ok_or_else(|| self.error("unterminated string"))?;
+          self.pos += 1;
+          let unescaped = match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            // Surrogate pairs are not combined, unpaired surrogates become the replacement character
+            b'u' => {
+              let hex = self
+                .input
+                .get(self.pos..self.pos + 4)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+              self.pos += 4;
+              std::char::from_u32(hex).unwrap_or('\u{fffd}')
+            }
+            _ => return Err(self.error("invalid escape")),
+          };
+          let mut buffer = [0; 4];
+          bytes.extend_from_slice(unescaped.encode_utf8(&mut buffer).as_bytes());
+        }
+        c => bytes.push(c),
+      }
+    }
+    // The input is a str and escapes are encoded as UTF-8, so the bytes are valid UTF-8
+    return Ok(String::from_utf8(bytes).unwrap());
+  }
+}
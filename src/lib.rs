@@ -1,3 +1,13 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 pub mod data;
+pub mod difficulty;
+pub mod gantt;
+pub mod json;
+pub mod lower_bounds;
 pub mod parser;
+pub mod preprocess;
+pub mod robustness;
 pub mod solver;
+#[cfg(test)]
+mod test_instances;
@@ -0,0 +1,170 @@
+use crate::data::Instance;
+use ndarray::Array1;
+use std::cmp;
+use std::collections::BinaryHeap;
+
+// Maximum of the longest job (ignoring machine conflicts) and the most loaded machine (ignoring precedences)
+// The length of a job is its longest path through the job precedences, the sum of its durations for a chain
+pub fn trivial_lower_bound(inst: &Instance) -> u32 {
+  let mut machine_loads = Array1::<u32>::from_elem(inst.n_machines, 0);
+  for op in 0..inst.n_ops() {
+    machine_loads[inst.machines[op]] += inst.durations[op];
+  }
+  let (heads, tails) = job_heads_and_tails(inst);
+  let max_job_length = (0..inst.n_ops())
+    .map(|op| heads[op] + inst.durations[op] + tails[op])
+    .max()
+    .unwrap_or(0);
+  let max_machine_load = machine_loads.iter().copied().max().unwrap_or(0);
+
+  return cmp::max(max_job_length, max_machine_load);
+}
+
+// Maximum over all machines of the preemptive one-machine bound (Jackson's preemptive schedule, see Carlier, The
+// one-machine sequencing problem) with the heads and tails of the precedence-only relaxation, i.e. the durations of
+// the longest paths of job predecessors and successors of every operation
+// Never below the trivial bound, since the head, duration and tail of an operation on the longest path of its job add up
+// to the length of the job
+pub fn single_machine_lower_bound(inst: &Instance) -> u32 {
+  let (heads, tails) = job_heads_and_tails(inst);
+  let mut machine_ops = vec![Vec::new(); inst.n_machines];
+  for op in 0..inst.n_ops() {
+    machine_ops[inst.machines[op]].push(op);
+  }
+
+  return machine_ops
+    .iter_mut()
+    .map(|ops| {
+      ops.sort_by_key(|&op| heads[op]);
+      preemptive_one_machine_bound(ops, &heads, &inst.durations, &tails)
+    })
+    .max()
+    .unwrap_or(0);
+}
+
+// Longest durations of the job predecessors before (heads) and of the job successors after (tails) every operation,
+// following the precedences of Instance::job_precedence_pairs, which never order a later before an earlier operation
+fn job_heads_and_tails(inst: &Instance) -> (Array1<u32>, Array1<u32>) {
+  let mut heads = Array1::<u32>::from_elem(inst.n_ops(), 0);
+  let mut tails = Array1::<u32>::from_elem(inst.n_ops(), 0);
+  for j in 0..inst.n_jobs {
+    let mut pairs = inst.job_precedence_pairs(j);
+    pairs.sort_unstable();
+    for &(a, b) in &pairs {
+      let (a, b) = (inst.op_to_id([j, a]), inst.op_to_id([j, b]));
+      heads[b] = cmp::max(heads[b], heads[a] + inst.durations[a]);
+    }
+    for &(a, b) in pairs.iter().rev() {
+      let (a, b) = (inst.op_to_id([j, a]), inst.op_to_id([j, b]));
+      tails[a] = cmp::max(tails[a], tails[b] + inst.durations[b]);
+    }
+  }
+
+  return (heads, tails);
+}
+
+// Max of completion plus tail in the preemptive schedule always running the released operation with the largest tail,
+// which is optimal for the preemptive one-machine problem; ops have to be sorted by head
+fn preemptive_one_machine_bound(
+  ops: &[usize],
+  heads: &Array1<u32>,
+  durations: &Array1<u32>,
+  tails: &Array1<u32>,
+) -> u32 {
+  let mut bound = 0;
+  let mut time = 0;
+  let mut next = 0;
+  // (tail, remaining duration) of the released but unfinished operations
+  let mut released = BinaryHeap::new();
+  while next < ops.len() || !released.is_empty() {
+    if released.is_empty() {
+      time = cmp::max(time, heads[ops[next]]);
+    }
+    while next < ops.len() && heads[ops[next]] <= time {
+      released.push((tails[ops[next]], durations[ops[next]]));
+      next += 1;
+    }
+
+    let (tail, remaining) = released.pop().unwrap();
+    // Runs until it finishes or the next operation is released, which may preempt it
+    let run = match ops.get(next) {
+      Some(&op) => cmp::min(remaining, heads[op] - time),
+      None => remaining,
+    };
+    time += run;
+    if run == remaining {
+      bound = cmp::max(bound, time + tail);
+    } else {
+      released.push((tail, remaining - run));
+    }
+  }
+
+  return bound;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{branch_and_bound, calculate_cmax, seeded_rng};
+  use crate::test_instances::ft06;
+  use rand::Rng;
+
+  #[test]
+  fn single_machine_bound_is_tighter() {
+    // Machine 1 can only start at 3 (after machine 0), the trivial bound ignores these heads
+    let inst = Instance::from_jobs(
+      3,
+      &[
+        vec![(0, 3), (1, 2)],
+        vec![(0, 3), (1, 2)],
+        vec![(2, 4), (1, 2)],
+      ],
+    );
+    assert_eq!(trivial_lower_bound(&inst), 6);
+    assert_eq!(single_machine_lower_bound(&inst), 9);
+    assert_eq!(
+      calculate_cmax(&inst, &branch_and_bound::find_optimal_solution(&inst)),
+      9
+    );
+
+    // The optimum of ft06 is 55
+    let inst = ft06();
+    assert_eq!(trivial_lower_bound(&inst), 47);
+    assert_eq!(single_machine_lower_bound(&inst), 52);
+
+    // Between the trivial bound and the optimum on random small instances
+    let mut rng = seeded_rng(0);
+    for _ in 0..50 {
+      let jobs: Vec<Vec<(usize, u32)>> = (0..3)
+        .map(|_| {
+          (0..3)
+            .map(|_| (rng.gen_range(0, 3), rng.gen_range(0, 6)))
+            .collect()
+        })
+        .collect();
+      let inst = Instance::from_jobs(3, &jobs);
+      let optimum = calculate_cmax(&inst, &branch_and_bound::find_optimal_solution(&inst));
+      let bound = single_machine_lower_bound(&inst);
+      assert!(
+        trivial_lower_bound(&inst) <= bound && bound <= optimum,
+        "{:?}",
+        jobs
+      );
+    }
+  }
+
+  #[test]
+  fn branching_job_precedences() {
+    // Operations 1 and 2 of job 0 both follow its operation 0, so the job is 3 + 5 long instead of 3 + 5 + 2
+    let mut inst = Instance::from_jobs(3, &[vec![(0, 3), (1, 5), (2, 2)], vec![(2, 4), (0, 1)]]);
+    inst.job_precedences = Some(vec![vec![(0, 1), (0, 2)], vec![(0, 1)]]);
+    let (heads, tails) = job_heads_and_tails(&inst);
+    assert_eq!(heads.to_vec(), vec![0, 3, 3, 0, 4]);
+    assert_eq!(tails.to_vec(), vec![5, 0, 0, 1, 0]);
+    assert_eq!(trivial_lower_bound(&inst), 8);
+    assert_eq!(single_machine_lower_bound(&inst), 8);
+
+    inst.job_precedences = None;
+    assert_eq!(trivial_lower_bound(&inst), 10);
+  }
+}
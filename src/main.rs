@@ -1,27 +1,493 @@
-#[macro_use]
-extern crate log;
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
 
-use clap::{App, Arg};
-use heuristics::parser::parse_instance;
+use clap::{App, Arg, ArgMatches};
+use heuristics::data::Instance;
+use heuristics::difficulty::estimate_difficulty;
+use heuristics::gantt::{to_svg, GanttColoring, GanttLayout};
+use heuristics::json::stream_line;
+use heuristics::lower_bounds::{single_machine_lower_bound, trivial_lower_bound};
+use heuristics::parser::{
+  drop_zero_duration_operations, parse_due_dates, parse_instance_from_reader, parse_instance_json,
+  parse_instance_split, parse_instance_taillard, parse_job_weights, validate_instance,
+};
+use heuristics::robustness::sample_cmax;
+use heuristics::solver::checkpoint::{load_checkpoint, CheckpointConfig};
+use heuristics::solver::n1::explain_moves;
+use heuristics::solver::neighborhood::{CandidateOrder, NeighborhoodKind, SearchMethod};
+use heuristics::solver::objective::{Constraint, Objective};
+use heuristics::solver::required_order::{required_edges, satisfies_required_edges, RequiredOrder};
+use heuristics::solver::run_options::RunOptions;
+use heuristics::solver::spec::{solve_with_budget, Budget, PriorityRule, SolverSpec};
 use heuristics::solver::{
-  calculate_cmax, hill_climber, print_solution, priority, random_restart_hill_climber, sequential,
-  simulated_annealing, tabu_search, verify_solution,
+  best_construct, branch_and_bound, calculate_cmax, format_per_operation, format_solution,
+  generate_random_solution_seeded, get_orientation_from_schedule, hill_climber,
+  orientation_distance, print_solution, print_solution_dispatch, print_solution_op_major, priority,
+  random_restart_hill_climber, resource_profile, seeded_rng, sequential, simulated_annealing,
+  steepest_descent, tabu_search, timeout_fraction, verify_solution, verify_solution_report,
+  DurationBias, IntermediateSolution, SolveStats,
 };
-use std::fs;
-use std::time::Duration;
+use std::any::Any;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 fn main() {
   env_logger::init();
 
+  // User errors are reported without a panic and backtrace
+  if let Err(e) = run() {
+    eprintln!("{}", e);
+    process::exit(1);
+  }
+}
+
+// Value of an optional argument, None if it is not given
+fn parse_arg<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<Option<T>, String>
+where
+  T::Err: Display,
+{
+  return matches
+    .value_of(name)
+    .map(|value| {
+      value
+        .parse()
+        .map_err(|e| format!("Invalid value {:?} for --{}: {}", value, name, e))
+    })
+    .transpose();
+}
+
+// Value of an argument that is required (or has a default value)
+fn require_arg<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<T, String>
+where
+  T::Err: Display,
+{
+  return parse_arg(matches, name)?.ok_or_else(|| format!("Missing --{}", name));
+}
+
+// The exact search enumerates all active schedules, so it is only feasible for small instances
+fn require_exact_search_size(instance: &Instance) -> Result<(), String> {
+  if instance.n_ops() > branch_and_bound::MAX_OPS {
+    return Err(format!(
+      "Instance too large for exact search: {} operations (at most {})",
+      instance.n_ops(),
+      branch_and_bound::MAX_OPS
+    ));
+  }
+  return Ok(());
+}
+
+// Comma separated elapsed times in seconds
+fn parse_time_checkpoints(checkpoints: &str) -> Result<Vec<Duration>, String> {
+  return checkpoints
+    .split(',')
+    .map(|checkpoint| {
+      let seconds: f64 = checkpoint
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid checkpoint {:?}", checkpoint))?;
+      // Duration::from_secs_f64 panics on negative, infinite or overflowing values
+      if !(seconds >= 0.0 && seconds < u64::MAX as f64) {
+        return Err(format!("Invalid checkpoint {:?}", checkpoint));
+      }
+      return Ok(Duration::from_secs_f64(seconds));
+    })
+    .collect();
+}
+
+// Verification defaults to debug builds outside of benchmarks
+fn verify_from_args(matches: &ArgMatches) -> bool {
+  if matches.is_present("verify") {
+    return true;
+  } else if matches.is_present("no-verify") {
+    return false;
+  }
+  return cfg!(debug_assertions) && !matches.is_present("benchmark");
+}
+
+fn constraint_from_args(matches: &ArgMatches) -> Result<Option<Constraint>, String> {
+  return match matches.values_of("constraint") {
+    Some(values) => {
+      let values: Vec<&str> = values.collect();
+      Constraint::parse(values[0], values[1])
+        .map(Some)
+        .map_err(|e| format!("Invalid constraint: {}", e))
+    }
+    None => Ok(None),
+  };
+}
+
+// Neighborhood of --neighborhood, or the default of the solver (n1 unless it defines a DEFAULT_NEIGHBORHOOD)
+fn neighborhood_from_args(matches: &ArgMatches, solver: &str) -> Result<NeighborhoodKind, String> {
+  return Ok(match parse_arg(matches, "neighborhood")? {
+    Some(neighborhood) => neighborhood,
+    None if solver == "simulated-annealing" => simulated_annealing::DEFAULT_NEIGHBORHOOD,
+    None if solver == "tabu-search" => tabu_search::DEFAULT_NEIGHBORHOOD,
+    None => NeighborhoodKind::N1,
+  });
+}
+
+fn hc_config_from_args(
+  matches: &ArgMatches,
+  secondary_objective: Option<Objective>,
+  constraint: Option<Constraint>,
+  verify: bool,
+) -> Result<hill_climber::HcConfig, String> {
+  let neighborhood = neighborhood_from_args(matches, "hill-climber")?;
+  let skip_symmetric_swaps = matches.is_present("skip-symmetric-swaps");
+  let random_walk_probability: f64 =
+    parse_arg(matches, "hc-random-walk-probability")?.unwrap_or(0.0);
+  if !(0.0..=1.0).contains(&random_walk_probability) {
+    Err(format!(
+      "Invalid value {} for --hc-random-walk-probability: has to be between 0 and 1",
+      random_walk_probability
+    ))?;
+  }
+  // Only the random walk needs a seed and stops at the timeout
+  let (seed, timeout) = if random_walk_probability > 0.0 {
+    let seed = parse_arg(matches, "seed")?.ok_or("--hc-random-walk-probability requires --seed")?;
+    (
+      seed,
+      Some(Duration::from_secs(require_arg(matches, "timeout")?)),
+    )
+  } else {
+    (0, None)
+  };
+  return Ok(match parse_arg(matches, "hc-max-plateau-steps")? {
+    Some(max_plateau_steps) => hill_climber::HcConfig {
+      allow_plateau: true,
+      max_plateau_steps: max_plateau_steps,
+      neighborhood: neighborhood,
+      skip_symmetric_swaps: skip_symmetric_swaps,
+      secondary_objective: secondary_objective,
+      constraint: constraint,
+      verify: verify,
+      random_walk_probability: random_walk_probability,
+      seed: seed,
+      timeout: timeout,
+      max_steps: None,
+    },
+    None => hill_climber::HcConfig {
+      neighborhood: neighborhood,
+      skip_symmetric_swaps: skip_symmetric_swaps,
+      secondary_objective: secondary_objective,
+      constraint: constraint,
+      verify: verify,
+      random_walk_probability: random_walk_probability,
+      seed: seed,
+      timeout: timeout,
+      ..hill_climber::HcConfig::default()
+    },
+  });
+}
+
+// Objectives like the tardiness are undefined without due dates
+fn require_due_dates(instance: &Instance, objective: Option<Objective>) -> Result<(), String> {
+  if objective.map_or(false, |objective| objective.requires_due_dates())
+    && instance.due_dates.is_none()
+  {
+    return Err("The secondary objective requires due dates".to_string());
+  }
+  return Ok(());
+}
+
+// Solver of the arguments as a SolverSpec, so only the parameters it covers are taken into account
+fn spec_from_args(matches: &ArgMatches) -> Result<SolverSpec, String> {
+  let solver = matches.value_of("solver").ok_or("Missing --solver")?;
+  return spec_for_solver(matches, solver);
+}
+
+// The named solver with the parameters of the arguments
+fn spec_for_solver(matches: &ArgMatches, solver: &str) -> Result<SolverSpec, String> {
+  let seed: Option<u64> = parse_arg(matches, "seed")?;
+  let require_seed = || seed.ok_or(format!("Solver {} requires --seed", solver));
+  let verify = verify_from_args(matches);
+  let neighborhood = neighborhood_from_args(matches, solver)?;
+  let skip_symmetric_swaps = matches.is_present("skip-symmetric-swaps");
+  let secondary_objective: Option<Objective> = parse_arg(matches, "secondary-objective")?;
+  let constraint = constraint_from_args(matches)?;
+  let hc_config = hc_config_from_args(matches, secondary_objective, constraint, verify)?;
+
+  return Ok(match solver {
+    "hill-climber" => SolverSpec::HillClimber(hc_config),
+    "steepest-descent" => SolverSpec::SteepestDescent { verify: verify },
+    "random-restart-hill-climber" => SolverSpec::RandomRestartHillClimber {
+      seed: require_seed()?,
+      hill_climber: hc_config,
+    },
+    "tabu-search" => SolverSpec::TabuSearch {
+      seed: require_seed()?,
+      neighborhood: neighborhood,
+      skip_symmetric_swaps: skip_symmetric_swaps,
+      secondary_objective: secondary_objective,
+      constraint: constraint,
+      verify: verify,
+    },
+    "simulated-annealing" => SolverSpec::SimulatedAnnealing {
+      seed: require_seed()?,
+      start_acceptance_ratio: parse_arg(matches, "sa-start-acceptance-ratio")?,
+      delta: parse_arg(matches, "sa-delta")?,
+      neighborhood: neighborhood,
+      skip_symmetric_swaps: skip_symmetric_swaps,
+      secondary_objective: secondary_objective,
+      constraint: constraint,
+      verify: verify,
+    },
+    "best-construct" => SolverSpec::BestConstruct {
+      seed: require_seed()?,
+      random_constructions: require_arg(matches, "best-construct-random")?,
+    },
+    "sequential" => SolverSpec::Sequential,
+    _ => match PriorityRule::ALL
+      .iter()
+      .find(|rule| format!("priority-{}", rule.name()) == solver)
+    {
+      Some(&rule) => SolverSpec::Priority(rule),
+      None => Err(format!("Solver {} not implemented", solver))?,
+    },
+  });
+}
+
+// Options that only some solvers support
+fn check_solver_options(matches: &ArgMatches, solver: &str) -> Result<(), String> {
+  if matches.is_present("hc-random-walk-probability") && solver != "hill-climber" {
+    Err(format!(
+      "--hc-random-walk-probability is only supported by hill-climber, not {}",
+      solver
+    ))?;
+  }
+  if matches.is_present("compact") && solver != "steepest-descent" && solver != "hill-climber" {
+    Err(format!(
+      "--compact is only supported by steepest-descent and hill-climber, not {}",
+      solver
+    ))?;
+  }
+  return Ok(());
+}
+
+// Reads stdin line by line, so instances are never all held in memory
+// Every non-empty line results in one json::stream_line
+fn stream_jsonl(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+  let spec = spec_from_args(matches)?;
+  let secondary_objective: Option<Objective> = parse_arg(matches, "secondary-objective")?;
+  let timeout = Duration::from_secs(require_arg(matches, "timeout")?);
+  let zero_durations_missing = matches.value_of("treat-zero-duration") == Some("as-missing");
+
+  let stdin = io::stdin();
+  let stdout = io::stdout();
+  let mut out = stdout.lock();
+  for line in stdin.lock().lines() {
+    let line = line.map_err(|e| format!("Error reading stdin: {}", e))?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let result = parse_instance_json(&line)
+      .and_then(|instance| validate_instance(&instance).map(|_| instance))
+      .map_err(|e| e.to_string())
+      .and_then(|instance| {
+        if zero_durations_missing {
+          return drop_zero_duration_operations(&instance);
+        }
+        Ok(instance)
+      })
+      .map_err(|e| format!("Invalid instance: {}", e))
+      .and_then(|instance| {
+        require_due_dates(&instance, secondary_objective)?;
+        let budget = Budget::Time {
+          timeout: timeout,
+          clock: None,
+        };
+        // A panic of the solver on one instance fails only its line
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+          solve_with_budget(&instance, &spec, budget)
+        }))
+        .map_err(|payload| format!("Solver panicked: {}", panic_message(&*payload)))?;
+        let (solution, _) = result?;
+        verify_solution(&instance, &solution).map_err(|e| format!("Verification failed: {}", e))?;
+        Ok(calculate_cmax(&instance, &solution))
+      });
+    writeln!(out, "{}", stream_line(&result))?;
+    out.flush()?;
+  }
+
+  return Ok(());
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+  if let Some(message) = payload.downcast_ref::<&str>() {
+    return message;
+  }
+  if let Some(message) = payload.downcast_ref::<String>() {
+    return message;
+  }
+  return "unknown cause";
+}
+
+// Runs every solver of --compare with the shared timeout and seed and prints a table of their verified makespans and
+// elapsed times, marking the best ones with a *, followed by the pairwise orientation distances of their solutions
+fn compare_solvers(matches: &ArgMatches, instance: &Instance) -> Result<(), Box<dyn Error>> {
+  let timeout = Duration::from_secs(require_arg(matches, "timeout")?);
+  let solvers: Vec<&str> = matches
+    .value_of("compare")
+    .ok_or("Missing --compare")?
+    .split(',')
+    .map(str::trim)
+    .collect();
+  let mut specs = Vec::new();
+  for &solver in &solvers {
+    if solver.is_empty() || solver == "compare" {
+      Err(format!("Invalid solver '{}' in --compare", solver))?;
+    }
+    specs.push(spec_for_solver(matches, solver)?);
+  }
+
+  let mut results = Vec::new();
+  for (solver, spec) in solvers.iter().zip(&specs) {
+    let start = Instant::now();
+    let budget = Budget::Time {
+      timeout: timeout,
+      clock: None,
+    };
+    let (solution, _) = solve_with_budget(instance, spec, budget)?;
+    let elapsed = start.elapsed();
+    verify_solution(instance, &solution)
+      .map_err(|e| format!("Verification of {} failed: {}", solver, e))?;
+    results.push((
+      solver,
+      calculate_cmax(instance, &solution),
+      elapsed,
+      IntermediateSolution::from_solution(instance, &solution)?,
+    ));
+  }
+
+  let best = results
+    .iter()
+    .map(|&(_, cmax, _, _)| cmax)
+    .min()
+    .unwrap_or(0);
+  let width = solvers.iter().map(|solver| solver.len()).max().unwrap_or(0);
+  println!(
+    "{:<width$} {:>8} {:>10}",
+    "solver",
+    "cmax",
+    "elapsed",
+    width = width
+  );
+  for (solver, cmax, elapsed, _) in &results {
+    println!(
+      "{:<width$} {:>8} {:>9.3}s{}",
+      solver,
+      cmax,
+      elapsed.as_secs_f64(),
+      if *cmax == best { " *" } else { "" },
+      width = width
+    );
+  }
+
+  println!("orientation_distance:");
+  let mut header = format!("{:<width$}", "", width = width);
+  for solver in &solvers {
+    header.push_str(&format!(" {:>width$}", solver, width = width));
+  }
+  println!("{}", header);
+  for (solver, _, _, a) in &results {
+    let mut row = format!("{:<width$}", solver, width = width);
+    for (_, _, _, b) in &results {
+      row.push_str(&format!(
+        " {:>width$}",
+        orientation_distance(a, b),
+        width = width
+      ));
+    }
+    println!("{}", row);
+  }
+  return Ok(());
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
   let matches = App::new("heuristics")
     .version("1.0")
     .about("Heuristic solvers for the job shop scheduling problem")
     .arg(
       Arg::with_name("instance")
         .long("instance")
-        .help("Instance file name")
+        .help("Instance file name, - reads the instance from stdin")
+        .takes_value(true)
+        .required_unless_one(&["times-file", "stream-jsonl"])
+        .conflicts_with_all(&["times-file", "machines-file", "stream-jsonl"]),
+    )
+    .arg(
+      Arg::with_name("format")
+        .long("format")
+        .help("Format of the instance file: orlib (machine duration pairs per job, default) or taillard (a durations block followed by a machines block)")
+        .possible_values(&["orlib", "taillard"])
+        .takes_value(true)
+        .requires("instance"),
+    )
+    .arg(
+      Arg::with_name("times-file")
+        .long("times-file")
+        .help("File containing the durations of all operations, one line per job (with --machines-file)")
+        .takes_value(true)
+        .requires("machines-file")
+        .conflicts_with("stream-jsonl"),
+    )
+    .arg(
+      Arg::with_name("stream-jsonl")
+        .long("stream-jsonl")
+        .help("Solve every JSON instance read from stdin (one per line) and print one JSON result line each")
+        // Options of a single solution (its output, analysis and checks) would be ignored for the streamed ones
+        .conflicts_with_all(&[
+          "compare",
+          "due-dates",
+          "job-weights",
+          "benchmark",
+          "checkpoint",
+          "resume",
+          "dump-improvements",
+          "trajectory-capacity",
+          "unique-solutions-cap",
+          "checkpoints",
+          "output",
+          "print-order",
+          "verify-report",
+          "analysis",
+          "slack",
+          "gantt",
+          "resource-profile",
+          "explain-moves",
+          "robustness",
+          "check-optimal",
+          "assert-cmax-leq",
+          "rule-optimality",
+          "dry-run",
+        ]),
+    )
+    .arg(
+      Arg::with_name("treat-zero-duration")
+        .long("treat-zero-duration")
+        .help("Whether zero-duration operations are genuine operations (as-real) or mark missing ones that are dropped from their jobs (as-missing)")
+        .possible_values(&["as-real", "as-missing"])
+        .takes_value(true)
+        .default_value("as-real"),
+    )
+    .arg(
+      Arg::with_name("machines-file")
+        .long("machines-file")
+        .help("File containing the machines of all operations, one line per job (with --times-file)")
         .takes_value(true)
-        .required(true),
+        .requires("times-file"),
+    )
+    .arg(
+      Arg::with_name("one-based")
+        .long("one-based")
+        .help("The machines of --machines-file are numbered from 1 instead of 0")
+        .requires("machines-file"),
     )
     .arg(
       Arg::with_name("solver")
@@ -29,6 +495,7 @@ fn main() {
         .help("Solver to use")
         .possible_values(&[
           "hill-climber",
+          "steepest-descent",
           "random-restart-hill-climber",
           "tabu-search",
           "simulated-annealing",
@@ -38,108 +505,1015 @@ fn main() {
           "priority-lpt",
           "priority-lwrm",
           "priority-mwrm",
+          "best-construct",
           "sequential",
+          "compare",
         ])
         .takes_value(true)
-        .required(true),
+        .required_unless_one(&["dry-run", "rule-optimality"]),
+    )
+    .arg(
+      Arg::with_name("compare")
+        .long("compare")
+        .help("Comma-separated solvers to run with the same timeout and seed for --solver compare")
+        .takes_value(true)
+        .required_if("solver", "compare"),
     )
     .arg(
       Arg::with_name("timeout")
         .long("timeout")
         .help("Timeout (in s) after which to abort the search")
         .takes_value(true)
-        .required(true),
+        .required_unless_one(&["dry-run", "rule-optimality"]),
     )
     .arg(
       Arg::with_name("seed")
         .long("seed")
-        .help("Seed for rng")
-        .takes_value(true)
-        .required(true),
+        .help("Seed for rng, only required by randomized solvers")
+        .takes_value(true),
     )
     .arg(
       Arg::with_name("sa-start-acceptance-ratio")
         .long("sa-start-acceptance-ratio")
-        .help("Start acceptance ratio parameter for simulated annealing")
-        .takes_value(true)
-        .required_if("solver", "simulated-annealing")
-        .requires_if("simulated-annealing", "solver"),
+        .help("Start acceptance ratio parameter for simulated annealing (default 0.9)")
+        .takes_value(true),
     )
     .arg(
       Arg::with_name("sa-delta")
         .long("sa-delta")
-        .help("Cooling parameter for simulated annealing")
+        .help("Cooling parameter for simulated annealing (default 10 / number of operations, at most 1)")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("compact")
+        .long("compact")
+        .help("Evaluate and apply the swaps of steepest-descent or hill-climber (n1-full only) in place on a compact solution (same result, less memory traffic)")
+        .conflicts_with("stream-jsonl"),
+    )
+    .arg(
+      Arg::with_name("sa-initial-temperature")
+        .long("sa-initial-temperature")
+        .help("Start every simulated annealing cycle at this temperature instead of estimating one")
+        .takes_value(true)
+        .conflicts_with_all(&["sa-start-acceptance-ratio", "sa-keep-initial-temperature"]),
+    )
+    .arg(
+      Arg::with_name("sa-min-temperature")
+        .long("sa-min-temperature")
+        .help("Minimum temperature after which simulated annealing restarts")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("sa-deterministic-acceptance")
+        .long("sa-deterministic-acceptance")
+        .help("Decide acceptance in simulated annealing by fixed-point instead of floating-point comparison"),
+    )
+    .arg(
+      Arg::with_name("sa-keep-initial-temperature")
+        .long("sa-keep-initial-temperature")
+        .help("Start every simulated annealing cycle at the initial temperature estimated for the first one"),
+    )
+    .arg(
+      Arg::with_name("rrhc-min-restart-distance")
+        .long("rrhc-min-restart-distance")
+        .help("Minimum number of differing machine arcs between a restart and the recent starts")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("rrhc-elite-pool-size")
+        .long("rrhc-elite-pool-size")
+        .help("Number of best distinct local optima kept for recombination restarts")
+        .takes_value(true)
+        .default_value("0"),
+    )
+    .arg(
+      Arg::with_name("rrhc-recombination-probability")
+        .long("rrhc-recombination-probability")
+        .help("Probability of restarting from a recombination of two elite local optima")
+        .takes_value(true)
+        .default_value("0.0"),
+    )
+    .arg(
+      Arg::with_name("best-construct-random")
+        .long("best-construct-random")
+        .help("Number of random constructions compared with the dispatching rules by best-construct")
         .takes_value(true)
-        .required_if("solver", "simulated-annealing")
-        .requires_if("simulated-annealing", "solver"),
+        .default_value("16"),
+    )
+    .arg(
+      Arg::with_name("tabu-max-stagnant-iterations")
+        .long("tabu-max-stagnant-iterations")
+        .help("Restart tabu search after this many iterations without improving the best solution")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("tabu-first-admissible")
+        .long("tabu-first-admissible")
+        .help("Let tabu search take the first admissible move instead of the best one"),
+    )
+    .arg(
+      Arg::with_name("tabu-presort-moves")
+        .long("tabu-presort-moves")
+        .help("Let tabu search try the moves in ascending order of their estimated makespan"),
+    )
+    .arg(
+      Arg::with_name("tabu-tie-break")
+        .long("tabu-tie-break")
+        .help("Move tabu search selects among moves of equal evaluation")
+        .possible_values(&["generated", "lexicographic", "random"])
+        .takes_value(true)
+        .default_value("generated"),
+    )
+    .arg(
+      Arg::with_name("tabu-solution-cache")
+        .long("tabu-solution-cache")
+        .help("Let tabu search keep the solutions of this many recently evaluated orientations instead of recomputing them")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("tabu-penalty-coeff")
+        .long("tabu-penalty-coeff")
+        .help("Coefficient of tabu search's frequency penalty coeff * max_delta * (n * m)^exponent, 0 disables it")
+        .takes_value(true)
+        .default_value("0.5"),
+    )
+    .arg(
+      Arg::with_name("tabu-penalty-exponent")
+        .long("tabu-penalty-exponent")
+        .help("Exponent of the instance size n * m in tabu search's frequency penalty")
+        .takes_value(true)
+        .default_value("0.5"),
+    )
+    .arg(
+      Arg::with_name("priority-random-ties")
+        .long("priority-random-ties")
+        .help("Let the priority-* solvers break ties between operations of equal priority randomly (seeded with --seed)"),
+    )
+    .arg(
+      Arg::with_name("zero-timeout-rule")
+        .long("zero-timeout-rule")
+        .help("Dispatching rule the metaheuristics return with --timeout 0, best takes the best of all rules")
+        .possible_values(&["best", "sps", "lps", "spt", "lpt", "lwrm", "mwrm"])
+        .takes_value(true)
+        .default_value("best"),
+    )
+    .arg(
+      Arg::with_name("stall-window")
+        .long("stall-window")
+        .help("Stop metaheuristics once the best makespan did not improve by more than stall-epsilon within this many iterations")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("stall-epsilon")
+        .long("stall-epsilon")
+        .help("Relative improvement of the best makespan within stall-window iterations below which the search stops")
+        .takes_value(true)
+        .default_value("0.0"),
+    )
+    .arg(
+      Arg::with_name("neighborhood")
+        .long("neighborhood")
+        .help("Neighborhood used by local search based solvers (default n1-full for simulated annealing, n1 otherwise)")
+        .possible_values(&["n1", "n1-full", "n1-bottleneck"])
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("require-order")
+        .long("require-order")
+        .help("Require that on machine m job a precedes job b, given as m,a,b (tabu-search only, may be repeated)")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1),
+    )
+    .arg(
+      Arg::with_name("skip-symmetric-swaps")
+        .long("skip-symmetric-swaps")
+        .help("Omit neighborhood swaps of interchangeable operations, which provably leave the makespan unchanged"),
+    )
+    .arg(
+      Arg::with_name("secondary-objective")
+        .long("secondary-objective")
+        .help("Objective breaking ties between solutions of equal makespan")
+        .takes_value(true)
+        .possible_values(&[
+          "total-flow-time",
+          "total-idle-time",
+          "machine-completion-spread",
+          "weighted-earliness-tardiness",
+        ]),
+    )
+    .arg(
+      Arg::with_name("init-bias")
+        .long("init-bias")
+        .help("Bias random start solutions towards short or long operations, e.g. spt:2.0 or lpt:1.0")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("fixed-start-seed")
+        .long("fixed-start-seed")
+        .help("Seed of the initial solution, independent of the search seed")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("due-dates")
+        .long("due-dates")
+        .help("File containing the whitespace separated due dates of all jobs")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("job-weights")
+        .long("job-weights")
+        .help("File containing the whitespace separated earliness and tardiness weight of every job, in job order")
+        .takes_value(true)
+        .requires("due-dates"),
+    )
+    .arg(
+      Arg::with_name("constraint")
+        .long("constraint")
+        .help("Constraint every solution of the search has to satisfy, e.g. tardiness-leq 100")
+        .takes_value(true)
+        .number_of_values(2)
+        .value_names(&["kind", "value"])
+        .requires("due-dates"),
+    )
+    .arg(
+      Arg::with_name("hc-max-plateau-steps")
+        .long("hc-max-plateau-steps")
+        .help("Allow hill climbing to move along plateaus for at most this many consecutive steps")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("hc-random-walk-probability")
+        .long("hc-random-walk-probability")
+        .help("Let hill-climber move to a random neighbor with this probability instead of the best improving one (a random walk baseline)")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("benchmark")
+        .long("benchmark")
+        .help("Print iteration statistics instead of the solution and skip verification of intermediate solutions"),
+    )
+    .arg(
+      Arg::with_name("verify")
+        .long("verify")
+        .help("Verify every intermediate solution, even in release builds")
+        .conflicts_with("no-verify"),
+    )
+    .arg(
+      Arg::with_name("no-verify")
+        .long("no-verify")
+        .help("Do not verify intermediate solutions, even in debug builds"),
+    )
+    .arg(
+      Arg::with_name("checkpoint")
+        .long("checkpoint")
+        .help("Periodically save the best solution and statistics of metaheuristics to this file")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("checkpoint-interval")
+        .long("checkpoint-interval")
+        .help("Time (in s) between two checkpoints")
+        .takes_value(true)
+        .default_value("60"),
+    )
+    .arg(
+      Arg::with_name("resume")
+        .long("resume")
+        .help("Continue a metaheuristic from a previously saved checkpoint")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("dump-improvements")
+        .long("dump-improvements")
+        .help("Write every new best solution of metaheuristics to a numbered file in this directory")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("trajectory-capacity")
+        .long("trajectory-capacity")
+        .help("Print a uniform sample of at most this many (iteration, cmax) pairs of the metaheuristic's trajectory")
+        .takes_value(true)
+        .requires("benchmark"),
+    )
+    .arg(
+      Arg::with_name("unique-solutions-cap")
+        .long("unique-solutions-cap")
+        .help("Print the number of distinct solutions visited by tabu-search, remembering at most this many")
+        .takes_value(true)
+        .requires("benchmark"),
+    )
+    .arg(
+      Arg::with_name("checkpoints")
+        .long("checkpoints")
+        .help("Print the metaheuristic's best cmax at these elapsed times in seconds, comma separated e.g. 1,5,10")
+        .takes_value(true)
+        .requires("benchmark"),
+    )
+    .arg(
+      Arg::with_name("output")
+        .long("output")
+        .help("Format of the solution: start times per job (matrix) or operations sorted by machine and start time (dispatch)")
+        .possible_values(&["matrix", "dispatch"])
+        .takes_value(true)
+        .default_value("matrix"),
+    )
+    .arg(
+      Arg::with_name("print-order")
+        .long("print-order")
+        .help("Orientation of the matrix output: one line per job (job) or per operation index (op)")
+        .possible_values(&["job", "op"])
+        .takes_value(true)
+        .default_value("job"),
+    )
+    .arg(
+      Arg::with_name("verify-report")
+        .long("verify-report")
+        .help("Print the number of precedence and machine checks of the verification after the solution"),
+    )
+    .arg(
+      Arg::with_name("analysis")
+        .long("analysis")
+        .help("Print an analysis of the solution's structure after the solution"),
+    )
+    .arg(
+      Arg::with_name("slack")
+        .long("slack")
+        .help("Print the total slack of every operation after the solution"),
+    )
+    .arg(
+      Arg::with_name("gantt")
+        .long("gantt")
+        .help("Write a Gantt chart of the solution as SVG to this file")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("gantt-layout")
+        .long("gantt-layout")
+        .help("Rows of the Gantt chart")
+        .possible_values(&["machine", "job"])
+        .takes_value(true)
+        .default_value("machine"),
+    )
+    .arg(
+      Arg::with_name("gantt-color")
+        .long("gantt-color")
+        .help(
+          "Coloring of the Gantt chart: job, machine or a comma separated palette with one color per job \
+           (machine layout) or per machine (job layout) [default: job for the machine layout, machine for the job \
+           layout]",
+        )
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("resource-profile")
+        .long("resource-profile")
+        .help("Print the number of busy machines over time and its peak"),
+    )
+    .arg(
+      Arg::with_name("explain-moves")
+        .long("explain-moves")
+        .help("Print every n1 move at the solution with its estimated and exact makespan"),
+    )
+    .arg(
+      Arg::with_name("robustness")
+        .long("robustness")
+        .help("Print the makespan distribution of the solution's machine sequences under durations perturbed by up to this fraction")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("samples")
+        .long("samples")
+        .help("Number of perturbed instances evaluated by --robustness")
+        .takes_value(true)
+        .default_value("100"),
+    )
+    .arg(
+      Arg::with_name("check-optimal")
+        .long("check-optimal")
+        .help("Also compute the optimum of a small instance and print whether the solution is optimal"),
+    )
+    .arg(
+      Arg::with_name("assert-cmax-leq")
+        .long("assert-cmax-leq")
+        .help("Exit with a nonzero code if the makespan of the solution exceeds the given value")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("rule-optimality")
+        .long("rule-optimality")
+        .help("Compute the optimum of a small instance, print which dispatching rules achieve it and exit")
+        .conflicts_with("dry-run"),
+    )
+    .arg(
+      Arg::with_name("dry-run")
+        .long("dry-run")
+        .visible_alias("describe")
+        .help("Only parse and validate the instance, print its dimensions, bounds and difficulty and exit"),
     )
     .get_matches();
 
-  let solver = matches.value_of("solver").expect("Missing solver");
-  let file = matches.value_of("instance").expect("Missing instance file");
-  let timeout = Duration::from_secs(
-    matches
-      .value_of("timeout")
-      .and_then(|m| m.parse().ok())
-      .expect("Invalid timeout"),
-  );
-  let seed: u64 = matches
-    .value_of("seed")
-    .and_then(|m| m.parse().ok())
-    .expect("Invalid seed");
+  // Checked before streaming, which would otherwise silently ignore them
+  if let Some(solver) = matches.value_of("solver") {
+    check_solver_options(&matches, solver)?;
+  }
+  if matches.is_present("stream-jsonl") {
+    return stream_jsonl(&matches);
+  }
+
+  let parsed = match matches.value_of("instance") {
+    Some(file) if matches.value_of("format") == Some("taillard") => {
+      let contents = match file {
+        "-" => {
+          let mut contents = String::new();
+          io::stdin().read_to_string(&mut contents).map(|_| contents)
+        }
+        _ => fs::read_to_string(file),
+      }
+      .map_err(|e| format!("Error reading instance file {}: {}", file, e))?;
+      parse_instance_taillard(&contents)
+    }
+    // The orlib format is parsed line by line, so the file is never held in memory as a whole
+    Some("-") => parse_instance_from_reader(io::stdin().lock()),
+    Some(file) => {
+      let file =
+        File::open(file).map_err(|e| format!("Error reading instance file {}: {}", file, e))?;
+      parse_instance_from_reader(BufReader::new(file))
+    }
+    None => {
+      let times_file = matches
+        .value_of("times-file")
+        .ok_or("Missing --times-file")?;
+      let machines_file = matches
+        .value_of("machines-file")
+        .ok_or("Missing --machines-file")?;
+      parse_instance_split(
+        Path::new(times_file),
+        Path::new(machines_file),
+        matches.is_present("one-based"),
+      )
+    }
+  }
+  .and_then(|instance| validate_instance(&instance).map(|_| instance));
+  let mut instance = parsed.map_err(|e| format!("Invalid instance: {}", e))?;
+  if matches.value_of("treat-zero-duration") == Some("as-missing") {
+    instance =
+      drop_zero_duration_operations(&instance).map_err(|e| format!("Invalid instance: {}", e))?;
+  }
+
+  if matches.is_present("dry-run") {
+    println!("n_jobs: {}", instance.n_jobs);
+    println!("n_machines: {}", instance.n_machines);
+    println!("n_ops: {}", instance.n_ops());
+    println!("lower_bound: {}", trivial_lower_bound(&instance));
+    println!(
+      "single_machine_lower_bound: {}",
+      single_machine_lower_bound(&instance)
+    );
+    println!("difficulty: {:.3}", estimate_difficulty(&instance));
+    return Ok(());
+  }
+
+  if matches.is_present("rule-optimality") {
+    require_exact_search_size(&instance)?;
+    let optimum = calculate_cmax(
+      &instance,
+      &branch_and_bound::find_optimal_solution(&instance),
+    );
+    println!("optimum: {}", optimum);
+    let mut matching = Vec::new();
+    for rule in PriorityRule::ALL.iter() {
+      let cmax = calculate_cmax(&instance, &rule.find_solution(&instance));
+      println!("{}: {}", rule.name(), cmax);
+      if cmax == optimum {
+        matching.push(rule.name());
+      }
+    }
+    if matching.is_empty() {
+      println!("matching: none");
+    } else {
+      println!("matching: {}", matching.join(" "));
+    }
+    return Ok(());
+  }
+  if let Some(path) = matches.value_of("due-dates") {
+    let due_dates = fs::read_to_string(path)
+      .map_err(|e| format!("Error reading due dates file {}: {}", path, e))?;
+    instance.due_dates = Some(
+      parse_due_dates(&due_dates, &instance).map_err(|e| format!("Invalid due dates: {}", e))?,
+    );
+  }
+  if let Some(path) = matches.value_of("job-weights") {
+    let job_weights = fs::read_to_string(path)
+      .map_err(|e| format!("Error reading job weights file {}: {}", path, e))?;
+    instance.job_weights = Some(
+      parse_job_weights(&job_weights, &instance)
+        .map_err(|e| format!("Invalid job weights: {}", e))?,
+    );
+  }
+  let solver = matches.value_of("solver").ok_or("Missing --solver")?;
+  if solver == "compare" {
+    return compare_solvers(&matches, &instance);
+  }
+  let timeout = Duration::from_secs(require_arg(&matches, "timeout")?);
+  let seed: Option<u64> = parse_arg(&matches, "seed")?;
+  let require_seed = || seed.ok_or(format!("Solver {} requires --seed", solver));
+  let benchmark = matches.is_present("benchmark");
+  let verify = verify_from_args(&matches);
+  let checkpoint = match matches.value_of("checkpoint") {
+    Some(path) => Some(CheckpointConfig {
+      path: PathBuf::from(path),
+      interval: Duration::from_secs(require_arg(&matches, "checkpoint-interval")?),
+      solver: solver.to_string(),
+    }),
+    None => None,
+  };
+  let resume = match matches.value_of("resume") {
+    Some(path) => Some(
+      load_checkpoint(Path::new(path), &instance, solver)
+        .map_err(|e| format!("Error loading checkpoint {}: {}", path, e))?,
+    ),
+    None => None,
+  };
+  let neighborhood = neighborhood_from_args(&matches, solver)?;
+  let skip_symmetric_swaps = matches.is_present("skip-symmetric-swaps");
+  let sa_start_acceptance_ratio = parse_arg(&matches, "sa-start-acceptance-ratio")?
+    .unwrap_or(simulated_annealing::DEFAULT_START_ACCEPTANCE_RATIO);
+  let sa_delta = parse_arg(&matches, "sa-delta")?
+    .unwrap_or_else(|| simulated_annealing::default_delta(&instance));
+  let dump_improvements = matches.value_of("dump-improvements").map(PathBuf::from);
+  let trajectory_capacity: Option<usize> = parse_arg(&matches, "trajectory-capacity")?;
+  let unique_solutions_cap: Option<usize> = parse_arg(&matches, "unique-solutions-cap")?;
+  if unique_solutions_cap.is_some() && solver != "tabu-search" {
+    Err(format!(
+      "--unique-solutions-cap is only supported by tabu-search, not {}",
+      solver
+    ))?;
+  }
+  let time_checkpoints = match matches.value_of("checkpoints") {
+    Some(checkpoints) => parse_time_checkpoints(checkpoints)?,
+    None => Vec::new(),
+  };
+  let assert_cmax_leq: Option<u32> = parse_arg(&matches, "assert-cmax-leq")?;
+  let gantt_layout: GanttLayout = require_arg(&matches, "gantt-layout")?;
+  let gantt_coloring = parse_arg::<GanttColoring>(&matches, "gantt-color")?
+    .unwrap_or_else(|| GanttColoring::default_for(gantt_layout));
+  if matches.is_present("gantt") {
+    gantt_coloring
+      .validate(&instance, gantt_layout)
+      .map_err(|e| format!("Invalid value for --gantt-color: {}", e))?;
+  }
+  let output = matches.value_of("output").unwrap_or("matrix");
+  let print_order = matches.value_of("print-order").unwrap_or("job");
+  let robustness: Option<f64> = parse_arg(&matches, "robustness")?;
+  let samples: usize = require_arg(&matches, "samples")?;
+  if let Some(magnitude) = robustness {
+    if !(0.0..=1.0).contains(&magnitude) {
+      Err(format!(
+        "Invalid value {} for --robustness: not between 0 and 1",
+        magnitude
+      ))?;
+    }
+    if samples == 0 {
+      Err("Invalid value 0 for --samples: at least one sample is required")?;
+    }
+  }
+  // Checked before solving, so a long run is not wasted on an instance the exact search cannot handle
+  let check_optimal = matches.is_present("check-optimal");
+  if check_optimal {
+    require_exact_search_size(&instance)?;
+  }
+  let secondary_objective: Option<Objective> = parse_arg(&matches, "secondary-objective")?;
+  require_due_dates(&instance, secondary_objective)?;
+  let init_bias: Option<DurationBias> = parse_arg(&matches, "init-bias")?;
+  let start_seed: Option<u64> = parse_arg(&matches, "fixed-start-seed")?;
+  let stall_window: Option<u64> = parse_arg(&matches, "stall-window")?;
+  let stall_epsilon: f64 = require_arg(&matches, "stall-epsilon")?;
+  if stall_epsilon.is_nan() || stall_epsilon < 0.0 {
+    Err(format!(
+      "Invalid value {} for --stall-epsilon: not a non-negative number",
+      stall_epsilon
+    ))?;
+  }
+  let constraint = constraint_from_args(&matches)?;
+  let required_orders: Vec<RequiredOrder> = match matches.values_of("require-order") {
+    Some(values) => values.map(str::parse).collect::<Result<_, _>>()?,
+    None => Vec::new(),
+  };
+  if !required_orders.is_empty() && solver != "tabu-search" {
+    Err(format!(
+      "--require-order is only supported by tabu-search, not {}",
+      solver
+    ))?;
+  }
+  if matches.is_present("priority-random-ties") && !solver.starts_with("priority-") {
+    Err(format!(
+      "--priority-random-ties is only supported by the priority-* solvers, not {}",
+      solver
+    ))?;
+  }
+  let required_edges = required_edges(&instance, &required_orders)?;
+  let solution_cache_capacity: Option<usize> = parse_arg(&matches, "tabu-solution-cache")?;
+  if solution_cache_capacity == Some(0) {
+    Err("Invalid value 0 for --tabu-solution-cache: the cache has to hold at least one solution")?;
+  }
+  let penalty_coefficient: f32 = require_arg(&matches, "tabu-penalty-coeff")?;
+  if !penalty_coefficient.is_finite() || penalty_coefficient < 0.0 {
+    Err(format!(
+      "Invalid value {} for --tabu-penalty-coeff: has to be finite and non-negative",
+      penalty_coefficient
+    ))?;
+  }
+  let penalty_exponent: f32 = require_arg(&matches, "tabu-penalty-exponent")?;
+  if !penalty_exponent.is_finite() {
+    Err(format!(
+      "Invalid value {} for --tabu-penalty-exponent: has to be finite",
+      penalty_exponent
+    ))?;
+  }
+  let hc_config = hc_config_from_args(&matches, secondary_objective, constraint, verify)?;
 
-  let contents = fs::read_to_string(file).expect("Error reading file");
-  let instance = parse_instance(&contents).expect("Error parsing file");
+  let zero_timeout_rule = match matches.value_of("zero-timeout-rule") {
+    Some("best") | None => None,
+    Some(name) => PriorityRule::ALL
+      .iter()
+      .find(|rule| rule.name() == name)
+      .copied(),
+  };
+
+  let run = RunOptions {
+    resume: resume,
+    checkpoint: checkpoint,
+    collect_improvements: dump_improvements.is_some(),
+    trajectory_capacity: trajectory_capacity,
+    time_checkpoints: time_checkpoints.clone(),
+    stall_window: stall_window,
+    stall_epsilon: stall_epsilon,
+    init_bias: init_bias,
+    start_seed: start_seed,
+    ..RunOptions::default()
+  };
 
-  let solution = match solver {
+  let start = Instant::now();
+  let (solution, stats) = match solver {
     "random-restart-hill-climber" => {
+      let recombination_probability: f64 = require_arg(&matches, "rrhc-recombination-probability")?;
+      if !(0.0..=1.0).contains(&recombination_probability) {
+        Err(format!(
+          "Invalid value {} for --rrhc-recombination-probability: not between 0 and 1",
+          recombination_probability
+        ))?;
+      }
       let config = random_restart_hill_climber::Config {
         timeout: timeout,
-        seed: seed,
+        seed: require_seed()?,
+        hill_climber: hc_config,
+        min_restart_distance: parse_arg(&matches, "rrhc-min-restart-distance")?,
+        elite_pool_size: require_arg(&matches, "rrhc-elite-pool-size")?,
+        recombination_probability: recombination_probability,
+        run: run,
+        zero_timeout_rule: zero_timeout_rule,
       };
-      random_restart_hill_climber::find_solution(&instance, &config).to_solution()
+      let (solution, stats) = random_restart_hill_climber::find_solution(&instance, &config);
+      (solution.to_solution(), Some(stats))
     }
     "tabu-search" => {
       let config = tabu_search::Config {
         timeout: timeout,
-        seed: seed,
+        seed: require_seed()?,
+        verify: verify,
+        run: run,
+        unique_solutions_cap: unique_solutions_cap,
+        termination: None,
+        max_stagnant_iterations: parse_arg(&matches, "tabu-max-stagnant-iterations")?,
+        neighborhood: neighborhood,
+        skip_symmetric_swaps: skip_symmetric_swaps,
+        search_method: if matches.is_present("tabu-first-admissible") {
+          SearchMethod::First
+        } else {
+          SearchMethod::Exhaustive
+        },
+        candidate_order: if matches.is_present("tabu-presort-moves") {
+          CandidateOrder::Estimate
+        } else {
+          CandidateOrder::Generated
+        },
+        tie_break: require_arg(&matches, "tabu-tie-break")?,
+        secondary_objective: secondary_objective,
+        constraint: constraint,
+        required_edges: required_edges.clone(),
+        solution_cache_capacity: solution_cache_capacity,
+        penalty_coefficient: penalty_coefficient,
+        penalty_exponent: penalty_exponent,
+        zero_timeout_rule: zero_timeout_rule,
       };
-      tabu_search::find_solution(&instance, &config).to_solution()
+      let (solution, stats) = tabu_search::find_solution(&instance, &config);
+      (solution.to_solution(), Some(stats))
     }
     "simulated-annealing" => {
-      let start_acceptance_ratio: f64 = matches
-        .value_of("sa-start-acceptance-ratio")
-        .and_then(|m| m.parse().ok())
-        .expect("Invalid start acceptance ratio");
-      let delta: f64 = matches
-        .value_of("sa-delta")
-        .and_then(|m| m.parse().ok())
-        .expect("Invalid delta");
+      let min_temperature: Option<f64> = parse_arg(&matches, "sa-min-temperature")?;
+      let sa_initial_temperature: Option<f64> = parse_arg(&matches, "sa-initial-temperature")?;
+      if let Some(temperature) = sa_initial_temperature {
+        if !(temperature.is_finite() && temperature > 0.0) {
+          Err(format!(
+            "Invalid value {} for --sa-initial-temperature: not positive",
+            temperature
+          ))?;
+        }
+      }
       let config = simulated_annealing::Config {
         timeout: timeout,
-        seed: seed,
-        start_acceptance_ratio: start_acceptance_ratio,
-        delta: delta,
+        seed: require_seed()?,
+        start_acceptance_ratio: sa_start_acceptance_ratio,
+        delta: sa_delta,
+        min_temperature: min_temperature,
+        deterministic_acceptance: matches.is_present("sa-deterministic-acceptance"),
+        reestimate_temperature_each_cycle: !matches.is_present("sa-keep-initial-temperature"),
+        initial_temperature: sa_initial_temperature,
+        verify: verify,
+        run: run,
+        warm_start: None,
+        neighborhood: neighborhood,
+        skip_symmetric_swaps: skip_symmetric_swaps,
+        secondary_objective: secondary_objective,
+        constraint: constraint,
+        zero_timeout_rule: zero_timeout_rule,
       };
-      simulated_annealing::find_solution(&instance, &config).to_solution()
+      let (solution, stats) = simulated_annealing::find_solution(&instance, &config);
+      (solution.to_solution(), Some(stats))
     }
     "hill-climber" => {
-      let solution = priority::find_solution_sps(&instance);
-      hill_climber::improve_solution(&instance, &solution).to_solution()
-    }
-    "priority-sps" => priority::find_solution_sps(&instance),
-    "priority-lps" => priority::find_solution_lps(&instance),
-    "priority-spt" => priority::find_solution_spt(&instance),
-    "priority-lpt" => priority::find_solution_lpt(&instance),
-    "priority-lwrm" => priority::find_solution_lwrm(&instance),
-    "priority-mwrm" => priority::find_solution_mwrm(&instance),
-    "sequential" => sequential::find_solution(&instance),
-    _ => panic!("Solver not implemented"),
+      let solution = match start_seed {
+        Some(start_seed) => generate_random_solution_seeded(&instance, start_seed),
+        None => priority::find_solution_sps(&instance, None),
+      };
+      let solution = if matches.is_present("compact") {
+        hill_climber::improve_solution_compact(&instance, &solution, &hc_config)?.to_solution()
+      } else {
+        hill_climber::improve_solution(&instance, &solution, &hc_config)?.to_solution()
+      };
+      (solution, None)
+    }
+    "steepest-descent" => {
+      let solution = match start_seed {
+        Some(start_seed) => generate_random_solution_seeded(&instance, start_seed),
+        None => priority::find_solution_sps(&instance, None),
+      };
+      let solution = if matches.is_present("compact") {
+        steepest_descent::improve_solution_compact(&instance, &solution, verify).to_solution()
+      } else {
+        steepest_descent::improve_solution(&instance, &solution, verify).to_solution()
+      };
+      (solution, None)
+    }
+    _ if matches.is_present("priority-random-ties") => {
+      let rule = PriorityRule::ALL
+        .iter()
+        .find(|rule| format!("priority-{}", rule.name()) == solver)
+        .ok_or(format!("Solver {} not implemented", solver))?;
+      let mut rng = seeded_rng(require_seed()?);
+      (rule.find_solution_random_ties(&instance, &mut rng), None)
+    }
+    "priority-sps" => (priority::find_solution_sps(&instance, None), None),
+    "priority-lps" => (priority::find_solution_lps(&instance, None), None),
+    "priority-spt" => (priority::find_solution_spt(&instance, None), None),
+    "priority-lpt" => (priority::find_solution_lpt(&instance, None), None),
+    "priority-lwrm" => (priority::find_solution_lwrm(&instance, None), None),
+    "priority-mwrm" => (priority::find_solution_mwrm(&instance, None), None),
+    "best-construct" => {
+      let config = best_construct::Config {
+        seed: require_seed()?,
+        random_constructions: require_arg(&matches, "best-construct-random")?,
+        init_bias: init_bias,
+      };
+      let (solution, constructor) = best_construct::find_solution(&instance, &config);
+      eprintln!("Best construction: {}", constructor);
+      (solution, None)
+    }
+    "sequential" => (sequential::find_solution(&instance), None),
+    _ => Err(format!("Solver {} not implemented", solver))?,
   };
+  // Simple heuristics do not iterate, so only the elapsed time is meaningful (and counted as construction)
+  let elapsed = start.elapsed();
+  let stats = stats.unwrap_or(SolveStats {
+    iterations: 0,
+    elapsed: elapsed,
+    timeout_fraction_used: timeout_fraction(elapsed, timeout),
+    construction: elapsed,
+    ..SolveStats::default()
+  });
+
+  if let Some(dir) = dump_improvements {
+    fs::create_dir_all(&dir)
+      .map_err(|e| format!("Error creating improvements directory {:?}: {}", dir, e))?;
+    for (i, (iteration, cmax, improvement)) in stats.improvements.iter().enumerate() {
+      let path = dir.join(format!("improvement-{:04}.txt", i));
+      let contents = format!("{}\n{}", cmax, format_solution(&instance, improvement));
+      fs::write(&path, contents)
+        .map_err(|e| format!("Error writing improvement {:?}: {}", path, e))?;
+      log::debug!(
+        "Wrote improvement {} at iteration {} to {:?}",
+        cmax,
+        iteration,
+        path
+      );
+    }
+  }
 
   let cmax = calculate_cmax(&instance, &solution);
-  verify_solution(&instance, &solution).expect("Verification failed");
+  let verification = verify_solution_report(&instance, &solution)
+    .map_err(|e| format!("Verification failed: {}", e))?;
+  if !satisfies_required_edges(
+    &instance,
+    &get_orientation_from_schedule(&instance, &solution),
+    &required_edges,
+  ) {
+    Err("Verification failed: solution violates a required order")?;
+  }
+
+  if benchmark {
+    println!("cmax: {}", cmax);
+    println!("iterations: {}", stats.iterations);
+    println!("elapsed: {:.3}s", stats.elapsed.as_secs_f64());
+    println!("timeout_used: {:.3}", stats.timeout_fraction_used);
+    println!("iterations/s: {:.1}", stats.iterations_per_second());
+    println!("construction: {:.3}s", stats.construction.as_secs_f64());
+    println!("search: {:.3}s", stats.search.as_secs_f64());
+    println!(
+      "orientation_distance: {}",
+      stats.orientation_distance_from_start
+    );
+    println!(
+      "tabu_blocked_improvements: {}",
+      stats.tabu_blocked_improvements
+    );
+    if solver == "simulated-annealing" {
+      println!("sa_start_acceptance_ratio: {}", sa_start_acceptance_ratio);
+      println!("sa_delta: {}", sa_delta);
+      println!(
+        "sa_temperature_estimations: {}",
+        stats.temperature_estimations
+      );
+    }
+    if unique_solutions_cap.is_some() {
+      println!(
+        "unique_solutions_visited: {}",
+        stats.unique_solutions_visited
+      );
+    }
+    if trajectory_capacity.is_some() {
+      let samples: Vec<String> = stats
+        .trajectory
+        .iter()
+        .map(|(iteration, cmax)| format!("{}:{}", iteration, cmax))
+        .collect();
+      println!("trajectory: {}", samples.join(" "));
+    }
+    if !time_checkpoints.is_empty() {
+      let checkpoints: Vec<String> = stats
+        .checkpoint_cmax
+        .iter()
+        .map(|(checkpoint, cmax)| format!("{}s:{}", checkpoint.as_secs_f64(), cmax))
+        .collect();
+      println!("checkpoints: {}", checkpoints.join(" "));
+    }
+  } else {
+    println!("{}", cmax);
+    match output {
+      "dispatch" => print_solution_dispatch(&instance, &solution),
+      _ if print_order == "op" => print_solution_op_major(&instance, &solution),
+      _ => print_solution(&instance, &solution),
+    }
+  }
+
+  if matches.is_present("verify-report") {
+    println!("verification: feasible");
+    println!("precedence_checks: {}", verification.precedence_checks);
+    println!("machine_checks: {}", verification.machine_checks);
+  }
+
+  if matches.is_present("analysis") {
+    let intermediate = IntermediateSolution::from_solution(&instance, &solution)?;
+    println!("critical_paths: {}", intermediate.critical_path_count());
+    let critical = intermediate.critical_operations();
+    println!(
+      "critical_operations: {}",
+      critical.iter().filter(|&&c| c).count()
+    );
+    println!("critical:");
+    print!(
+      "{}",
+      format_per_operation(&instance, &critical.mapv(|c| c as u32))
+    );
+    let windows = intermediate.start_windows();
+    println!("earliest_start:");
+    print!(
+      "{}",
+      format_per_operation(&instance, &windows.mapv(|(earliest, _)| earliest))
+    );
+    println!("latest_start:");
+    print!(
+      "{}",
+      format_per_operation(&instance, &windows.mapv(|(_, latest)| latest))
+    );
+  }
+
+  if matches.is_present("slack") {
+    let intermediate = IntermediateSolution::from_solution(&instance, &solution)?;
+    println!("slack:");
+    print!("{}", format_per_operation(&instance, &intermediate.slack()));
+  }
+
+  if matches.is_present("resource-profile") {
+    let profile = resource_profile(&instance, &solution);
+    println!("resource_profile:");
+    for (time, busy) in &profile {
+      println!("{} {}", time, busy);
+    }
+    println!(
+      "peak_busy_machines: {}",
+      profile.iter().map(|&(_, busy)| busy).max().unwrap_or(0)
+    );
+  }
+
+  if matches.is_present("explain-moves") {
+    let intermediate = IntermediateSolution::from_solution(&instance, &solution)?;
+    let explanations = explain_moves(&intermediate);
+    println!("moves:");
+    for explanation in &explanations {
+      let ((a_job, a_index), (b_job, b_index)) = (
+        (explanation.ops.0[0], explanation.ops.0[1]),
+        (explanation.ops.1[0], explanation.ops.1[1]),
+      );
+      println!(
+        "{} {} {}/{} {}/{} {} {} {} {}",
+        explanation.swap_move.0,
+        explanation.swap_move.1,
+        a_job,
+        a_index,
+        b_job,
+        b_index,
+        explanation.machine,
+        explanation.estimated_cmax,
+        explanation.cmax,
+        if explanation.improves { "yes" } else { "no" }
+      );
+    }
+    println!(
+      "improving_moves: {}",
+      explanations.iter().filter(|e| e.improves).count()
+    );
+  }
+
+  if let Some(path) = matches.value_of("gantt") {
+    fs::write(
+      path,
+      to_svg(&instance, &solution, gantt_layout, &gantt_coloring)?,
+    )
+    .map_err(|e| format!("Error writing Gantt chart {}: {}", path, e))?;
+  }
+
+  if let Some(magnitude) = robustness {
+    let seed = seed.ok_or("--robustness requires --seed")?;
+    let orientation = get_orientation_from_schedule(&instance, &solution);
+    let cmaxs = sample_cmax(&instance, &orientation, magnitude, samples, seed)?;
+    let mean = cmaxs.iter().map(|&c| c as f64).sum::<f64>() / cmaxs.len() as f64;
+    println!(
+      "robustness: min {} median {} p90 {} max {} mean {:.2}",
+      cmaxs[0],
+      cmaxs[cmaxs.len() / 2],
+      cmaxs[(cmaxs.len() * 9 / 10).min(cmaxs.len() - 1)],
+      cmaxs[cmaxs.len() - 1],
+      mean
+    );
+  }
+
+  if check_optimal {
+    let optimum = calculate_cmax(
+      &instance,
+      &branch_and_bound::find_optimal_solution(&instance),
+    );
+    println!("optimum: {}", optimum);
+    println!("optimal: {}", if cmax == optimum { "yes" } else { "no" });
+  }
+
+  if let Some(constraint) = constraint {
+    let intermediate = IntermediateSolution::from_solution(&instance, &solution)?;
+    let violation = constraint.violation(&intermediate);
+    if violation > 0 {
+      eprintln!("Warning: solution violates the constraint by {}", violation);
+    }
+  }
+
+  if let Some(threshold) = assert_cmax_leq {
+    if cmax > threshold {
+      eprintln!(
+        "Assertion failed: cmax {} exceeds threshold {}",
+        cmax, threshold
+      );
+      process::exit(1);
+    }
+  }
 
-  println!("{}", cmax);
-  print_solution(&instance, &solution);
+  return Ok(());
 }
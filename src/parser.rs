@@ -1,34 +1,732 @@
-use crate::data::Instance;
+use crate::data::{Instance, JobWeights, Time};
+use crate::json::{self, JsonValue};
 use ndarray::Array1;
 use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, Cursor};
+use std::num::ParseIntError;
+use std::path::Path;
+use std::str::FromStr;
 
-pub fn parse_instance(instance: &String) -> Result<Instance, Box<dyn Error>> {
-  let mut lines: Vec<&str> = instance.lines().collect();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+  // Name of the missing field
+  MissingField(String),
+  // Field that is not a nonnegative integer
+  NotAnInteger(String),
+  // Integer that does not fit the field's type
+  OutOfRange(String),
+}
+
+// Location of a parse error, line and column (in bytes) are 1-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  pub line: usize,
+  pub column: usize,
+  pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let description = match &self.kind {
+      ParseErrorKind::MissingField(field) => format!("{} missing", field),
+      ParseErrorKind::NotAnInteger(value) => format!("{:?} is not an integer", value),
+      ParseErrorKind::OutOfRange(value) => format!("{} is out of range", value),
+    };
+    return write!(
+      f,
+      "line {}, column {}: {}",
+      self.line, self.column, description
+    );
+  }
+}
+
+impl Error for ParseError {}
+
+// Whitespace separated fields of a line with their 1-based column
+fn fields(line: &str) -> Vec<(usize, &str)> {
+  let mut fields = Vec::new();
+  let mut field_start = None;
+  for (i, c) in line.char_indices() {
+    match (c.is_whitespace(), field_start) {
+      (false, None) => field_start = Some(i),
+      (true, Some(start)) => {
+        fields.push((start + 1, &line[start..i]));
+        field_start = None;
+      }
+      _ => {}
+    }
+  }
+  if let Some(start) = field_start {
+    fields.push((start + 1, &line[start..]));
+  }
+  return fields;
+}
+
+// Whether an unparsable unsigned integer failed only because it is too large
+fn is_overflow(value: &str) -> bool {
+  return !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit());
+}
+
+fn parse_field<T: FromStr<Err = ParseIntError>>(
+  line_fields: &[(usize, &str)],
+  index: usize,
+  name: &str,
+  line: usize,
+  line_len: usize,
+) -> Result<T, ParseError> {
+  let &(column, value) = line_fields.get(index).ok_or(ParseError {
+    line: line,
+    column: line_len + 1,
+    kind: ParseErrorKind::MissingField(name.to_string()),
+  })?;
 
-  let prelude = lines.drain(0..1).next().ok_or("Prelude missing")?;
-  let prelude_items: Vec<&str> = prelude.split_whitespace().collect();
-  let n_jobs = prelude_items.get(0).ok_or("n_jobs missing")?.parse()?;
-  let n_machines = prelude_items.get(1).ok_or("n_machines missing")?.parse()?;
+  return value.parse().map_err(|_: ParseIntError| ParseError {
+    line: line,
+    column: column,
+    kind: if is_overflow(value) {
+      ParseErrorKind::OutOfRange(value.to_string())
+    } else {
+      ParseErrorKind::NotAnInteger(value.to_string())
+    },
+  });
+}
+
+pub fn parse_instance(instance: &str) -> Result<Instance, Box<dyn Error>> {
+  return parse_instance_from_reader(Cursor::new(instance));
+}
+
+// Like parse_instance, but errors carry their location
+pub fn parse_instance_detailed(instance: &str) -> Result<Instance, ParseError> {
+  // Reading from a string only fails with parse errors
+  return parse_instance_from_reader(Cursor::new(instance))
+    .map_err(|e| *e.downcast::<ParseError>().expect("Reading a string failed"));
+}
+
+// Like parse_instance, but reads one line at a time, e.g. from stdin, so the file is never held in memory as a whole
+// Fails with a ParseError for invalid contents and with an io::Error if reading fails
+pub fn parse_instance_from_reader<R: BufRead>(reader: R) -> Result<Instance, Box<dyn Error>> {
+  let mut lines = reader.lines();
+
+  let prelude = lines.next().transpose()?.unwrap_or_default();
+  let prelude_fields = fields(&prelude);
+  let n_jobs: usize = parse_field(&prelude_fields, 0, "n_jobs", 1, prelude.len())?;
+  let n_machines: usize = parse_field(&prelude_fields, 1, "n_machines", 1, prelude.len())?;
+
+  // Jobs may have a differing number of operations, each job consists of all operations on its line
+  // Not preallocated, since n_jobs is not validated before its lines arrive
+  let mut jobs = Vec::new();
+  for job in 0..n_jobs {
+    let line_number = job + 2;
+    let line = lines.next().transpose()?.ok_or(ParseError {
+      line: line_number,
+      column: 1,
+      kind: ParseErrorKind::MissingField(format!("Job {}", job)),
+    })?;
+    let line_fields = fields(&line);
+    let mut ops = Vec::with_capacity(line_fields.len() / 2);
+    for i in (0..line_fields.len()).step_by(2) {
+      let machine: usize = parse_field(&line_fields, i, "Machine", line_number, line.len())?;
+      let duration: u32 = parse_field(&line_fields, i + 1, "Duration", line_number, line.len())?;
+      ops.push((machine, duration));
+    }
+    jobs.push(ops);
+  }
+
+  Ok(Instance::from_jobs(n_machines, &jobs))
+}
+
+// Alternative format with the durations and machines of the operations in two separate files without a prelude
+// Every (non-empty) line of both files is a job, the number of machines is derived from the machine indices
+// Machines are 0-based as in the combined format, unless one_based is set
+pub fn parse_instance_split(
+  times_path: &Path,
+  machines_path: &Path,
+  one_based: bool,
+) -> Result<Instance, Box<dyn Error>> {
+  let times: Vec<Vec<u32>> = read_matrix(times_path, "Duration")?;
+  let machines: Vec<Vec<usize>> = read_matrix(machines_path, "Machine")?;
+
+  if times.len() != machines.len() {
+    Err(format!(
+      "Times file has {} jobs, but machines file has {}",
+      times.len(),
+      machines.len()
+    ))?;
+  }
+  let jobs = jobs_from_matrices(&times, &machines, one_based)?;
+  let n_machines = jobs
+    .iter()
+    .flatten()
+    .map(|&(machine, _)| machine + 1)
+    .max()
+    .unwrap_or(0);
 
-  let mut instance = Instance {
-    n_jobs: n_jobs,
-    n_machines: n_machines,
-    durations: Array1::<u32>::from_elem(n_jobs * n_machines, 0),
-    machines: Array1::<usize>::from_elem(n_jobs * n_machines, 0),
+  Ok(Instance::from_jobs(n_machines, &jobs))
+}
+
+// Taillard format: the prelude "n_jobs n_machines" (further fields such as seeds and bounds are ignored), then one line
+// of durations per job followed by one line of machines per job, empty lines are skipped
+// Machines are 1-based (as in the published benchmarks) if the largest machine index is n_machines, otherwise 0-based
+pub fn parse_instance_taillard(instance: &str) -> Result<Instance, Box<dyn Error>> {
+  let lines: Vec<&str> = instance.lines().collect();
+
+  let prelude = lines.first().copied().unwrap_or("");
+  let prelude_fields = fields(prelude);
+  let n_jobs: usize = parse_field(&prelude_fields, 0, "n_jobs", 1, prelude.len())?;
+  let n_machines: usize = parse_field(&prelude_fields, 1, "n_machines", 1, prelude.len())?;
+
+  let rows: Vec<(usize, &str)> = lines
+    .iter()
+    .enumerate()
+    .skip(1)
+    .filter(|(_, line)| !line.trim().is_empty())
+    .map(|(i, &line)| (i + 1, line))
+    .collect();
+  if rows.len() != 2 * n_jobs {
+    Err(format!(
+      "Expected {} lines of durations followed by {} lines of machines, but found {} lines",
+      n_jobs,
+      n_jobs,
+      rows.len()
+    ))?;
+  }
+
+  let times = rows[..n_jobs]
+    .iter()
+    .map(|&(line_number, line)| parse_row(line, line_number, "Duration"))
+    .collect::<Result<Vec<Vec<u32>>, ParseError>>()?;
+  let machines = rows[n_jobs..]
+    .iter()
+    .map(|&(line_number, line)| parse_row(line, line_number, "Machine"))
+    .collect::<Result<Vec<Vec<usize>>, ParseError>>()?;
+
+  // A 1-based file that never uses its last machine is indistinguishable from a 0-based one and read as such
+  let one_based = machines.iter().flatten().max() == Some(&n_machines);
+  let jobs = jobs_from_matrices(&times, &machines, one_based)?;
+  if let Some(&(machine, _)) = jobs
+    .iter()
+    .flatten()
+    .find(|&&(machine, _)| machine >= n_machines)
+  {
+    Err(format!(
+      "Machine {} out of range (n_machines={})",
+      machine, n_machines
+    ))?;
+  }
+
+  Ok(Instance::from_jobs(n_machines, &jobs))
+}
+
+// (machine, duration) pairs of every job from a durations and a machines matrix with one row per job
+fn jobs_from_matrices(
+  times: &[Vec<u32>],
+  machines: &[Vec<usize>],
+  one_based: bool,
+) -> Result<Vec<Vec<(usize, u32)>>, String> {
+  for (job, (job_times, job_machines)) in times.iter().zip(machines).enumerate() {
+    if job_times.len() != job_machines.len() {
+      return Err(format!(
+        "Job {} has {} durations, but {} machines",
+        job,
+        job_times.len(),
+        job_machines.len()
+      ));
+    }
+  }
+
+  if one_based && machines.iter().flatten().any(|&machine| machine == 0) {
+    return Err("Machine 0 in 1-based machine indices".to_string());
+  }
+  let offset = if one_based { 1 } else { 0 };
+  return Ok(
+    times
+      .iter()
+      .zip(machines)
+      .map(|(job_times, job_machines)| {
+        job_machines
+          .iter()
+          .map(|&machine| machine - offset)
+          .zip(job_times.iter().copied())
+          .collect()
+      })
+      .collect(),
+  );
+}
+
+// JSON object {"n_machines": 2, "jobs": [[[0, 3], [1, 2]], [[1, 4]]]} with the (machine, duration) pairs of every job
+// and optionally "due_dates": [..] with one due date per job, other keys are ignored
+pub fn parse_instance_json(instance: &str) -> Result<Instance, Box<dyn Error>> {
+  let value = json::parse(instance)?;
+  let n_machines: usize = json_integer(
+    value.get("n_machines").ok_or("n_machines missing")?,
+    "n_machines",
+  )?;
+  let jobs = json_array(value.get("jobs").ok_or("jobs missing")?, "jobs")?
+    .iter()
+    .enumerate()
+    .map(|(j, job)| {
+      json_array(job, &format!("Job {}", j))?
+        .iter()
+        .enumerate()
+        .map(|(o, op)| match op {
+          JsonValue::Array(pair) if pair.len() == 2 => Ok((
+            json_integer(&pair[0], "Machine")?,
+            json_integer(&pair[1], "Duration")?,
+          )),
+          _ => Err(format!(
+            "Operation {:?} is not a [machine, duration] pair",
+            [j, o]
+          )),
+        })
+        .collect::<Result<Vec<(usize, u32)>, String>>()
+    })
+    .collect::<Result<Vec<_>, String>>()?;
+
+  let mut inst = Instance::from_jobs(n_machines, &jobs);
+  if let Some(due_dates) = value.get("due_dates") {
+    let due_dates = json_array(due_dates, "due_dates")?
+      .iter()
+      .map(|due_date| json_integer(due_date, "Due date"))
+      .collect::<Result<Vec<Time>, String>>()?;
+    if due_dates.len() != inst.n_jobs {
+      Err(format!(
+        "Expected {} due dates, found {}",
+        inst.n_jobs,
+        due_dates.len()
+      ))?;
+    }
+    inst.due_dates = Some(Array1::from(due_dates));
+  }
+
+  Ok(inst)
+}
+
+fn json_array<'a>(value: &'a JsonValue, name: &str) -> Result<&'a Vec<JsonValue>, String> {
+  return match value {
+    JsonValue::Array(elements) => Ok(elements),
+    _ => Err(format!("{} is not an array", name)),
+  };
+}
+
+fn json_integer<T: FromStr<Err = ParseIntError>>(
+  value: &JsonValue,
+  name: &str,
+) -> Result<T, String> {
+  return match value {
+    JsonValue::Number(number) => number.parse().map_err(|_: ParseIntError| {
+      if is_overflow(number) {
+        format!("{} {} is out of range", name, number)
+      } else {
+        format!("{} {} is not a nonnegative integer", name, number)
+      }
+    }),
+    _ => Err(format!("{} is not a number", name)),
   };
+}
+
+fn read_matrix<T: FromStr<Err = ParseIntError>>(
+  path: &Path,
+  name: &str,
+) -> Result<Vec<Vec<T>>, Box<dyn Error>> {
+  let contents = fs::read_to_string(path)?;
+  let matrix = parse_matrix(&contents, name).map_err(|e| format!("{}: {}", path.display(), e))?;
+  return Ok(matrix);
+}
 
-  for (job, line) in lines.iter().enumerate() {
-    let items: Vec<&str> = line.split_whitespace().collect();
-    for i in (0..items.len()).step_by(2) {
-      let machine: usize = items.get(i).ok_or("Machine missing")?.parse()?;
-      let duration: u32 = items.get(i + 1).ok_or("Duration missing")?.parse()?;
+// Whitespace separated integers, one row per non-empty line
+fn parse_matrix<T: FromStr<Err = ParseIntError>>(
+  contents: &str,
+  name: &str,
+) -> Result<Vec<Vec<T>>, ParseError> {
+  let mut rows = Vec::new();
+  for (i, line) in contents.lines().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    rows.push(parse_row(line, i + 1, name)?);
+  }
+  return Ok(rows);
+}
+
+// Whitespace separated integers of a line
+fn parse_row<T: FromStr<Err = ParseIntError>>(
+  line: &str,
+  line_number: usize,
+  name: &str,
+) -> Result<Vec<T>, ParseError> {
+  let line_fields = fields(line);
+  let mut row = Vec::with_capacity(line_fields.len());
+  for index in 0..line_fields.len() {
+    row.push(parse_field(
+      &line_fields,
+      index,
+      name,
+      line_number,
+      line.len(),
+    )?);
+  }
+  return Ok(row);
+}
+
+// Whitespace separated due dates, one per job
+pub fn parse_due_dates(due_dates: &str, inst: &Instance) -> Result<Array1<Time>, Box<dyn Error>> {
+  let mut parsed = Vec::with_capacity(inst.n_jobs);
+  for item in due_dates.split_whitespace() {
+    parsed.push(item.parse()?);
+  }
+  if parsed.len() != inst.n_jobs {
+    Err(format!(
+      "Expected {} due dates, found {}",
+      inst.n_jobs,
+      parsed.len()
+    ))?;
+  }
+
+  Ok(Array1::from(parsed))
+}
+
+// Whitespace separated earliness and tardiness weight of every job, e.g. one line "earliness tardiness" per job
+pub fn parse_job_weights(job_weights: &str, inst: &Instance) -> Result<JobWeights, Box<dyn Error>> {
+  let mut parsed = Vec::with_capacity(2 * inst.n_jobs);
+  for item in job_weights.split_whitespace() {
+    parsed.push(item.parse::<u32>()?);
+  }
+  if parsed.len() != 2 * inst.n_jobs {
+    Err(format!(
+      "Expected an earliness and a tardiness weight for each of the {} jobs, found {} weights",
+      inst.n_jobs,
+      parsed.len()
+    ))?;
+  }
+
+  Ok(JobWeights {
+    earliness: parsed.iter().step_by(2).copied().collect(),
+    tardiness: parsed.iter().skip(1).step_by(2).copied().collect(),
+  })
+}
 
-      let o = i / 2;
-      let op = instance.op_to_id([job, o]);
-      instance.durations[op] = duration;
-      instance.machines[op] = machine;
+// Instance without its zero-duration operations, for datasets where 0 marks a missing operation instead of a genuine
+// zero-duration one, the remaining operations of a job keep their order (so the instance may become ragged)
+// Fails for jobs without a positive duration and for explicit job precedences, whose indices would shift
+pub fn drop_zero_duration_operations(inst: &Instance) -> Result<Instance, String> {
+  if inst.job_precedences.is_some() {
+    return Err(
+      "Zero-duration operations cannot be dropped with explicit job precedences".to_string(),
+    );
+  }
+  let mut durations = Vec::new();
+  let mut machines = Vec::new();
+  let mut job_offsets = Vec::with_capacity(inst.n_jobs + 1);
+  for j in 0..inst.n_jobs {
+    job_offsets.push(durations.len());
+    for o in 0..inst.job_len(j) {
+      let op = inst.op_to_id([j, o]);
+      if inst.durations[op] > 0 {
+        durations.push(inst.durations[op]);
+        machines.push(inst.machines[op]);
+      }
     }
+    if durations.len() == job_offsets[j] {
+      return Err(format!("Job {} has only zero-duration operations", j));
+    }
+  }
+  job_offsets.push(durations.len());
+
+  return Ok(Instance {
+    n_machines: inst.n_machines,
+    n_jobs: inst.n_jobs,
+    durations: Array1::from(durations),
+    machines: Array1::from(machines),
+    job_offsets: Array1::from(job_offsets),
+    due_dates: inst.due_dates.clone(),
+    job_weights: inst.job_weights.clone(),
+    job_precedences: None,
+  });
+}
+
+pub fn validate_instance(inst: &Instance) -> Result<(), Box<dyn Error>> {
+  if inst.n_jobs == 0 || inst.n_machines == 0 {
+    Err(format!(
+      "Instance is empty (n_jobs={}, n_machines={})",
+      inst.n_jobs, inst.n_machines
+    ))?;
   }
 
-  Ok(instance)
+  for j in 0..inst.n_jobs {
+    if inst.job_len(j) == 0 {
+      Err(format!("Job {} has no operations", j))?;
+    }
+
+    for o in 0..inst.job_len(j) {
+      let machine = inst.machines[inst.op_to_id([j, o])];
+      if machine >= inst.n_machines {
+        Err(format!(
+          "Machine {} of operation {:?} out of range (n_machines={})",
+          machine,
+          [j, o],
+          inst.n_machines
+        ))?;
+      }
+    }
+  }
+
+  if let Some(precedences) = &inst.job_precedences {
+    if precedences.len() != inst.n_jobs {
+      Err(format!(
+        "Expected precedences for {} jobs, found {}",
+        inst.n_jobs,
+        precedences.len()
+      ))?;
+    }
+    for (j, pairs) in precedences.iter().enumerate() {
+      for &(a, b) in pairs {
+        if a >= b || b >= inst.job_len(j) {
+          Err(format!(
+            "Invalid precedence {:?} in job {}: requires a < b < {}",
+            (a, b),
+            j,
+            inst.job_len(j)
+          ))?;
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::neighborhood::NeighborhoodKind;
+  use crate::solver::spec::{solve_with_budget, Budget, PriorityRule, SolverSpec};
+  use crate::solver::{calculate_cmax, verify_solution};
+  use std::env;
+  use std::process;
+
+  #[test]
+  fn ragged_jobs() {
+    let inst = parse_instance("2 3\n0 2 1 3\n2 1 0 2 1 4\n").unwrap();
+    validate_instance(&inst).unwrap();
+    assert_eq!((inst.job_len(0), inst.job_len(1)), (2, 3));
+    assert_eq!(inst.n_ops(), 5);
+    assert!(!inst.is_rectangular());
+    assert_eq!(inst.op_to_id([1, 2]), 4);
+    assert_eq!(inst.op_from_id(2), [1, 0]);
+
+    let tabu_search = SolverSpec::TabuSearch {
+      seed: 0,
+      neighborhood: NeighborhoodKind::N1,
+      skip_symmetric_swaps: false,
+      secondary_objective: None,
+      constraint: None,
+      verify: true,
+    };
+    for spec in &[SolverSpec::Priority(PriorityRule::Sps), tabu_search] {
+      let (solution, _) = solve_with_budget(&inst, spec, Budget::Iterations(50)).unwrap();
+      verify_solution(&inst, &solution).unwrap();
+      // Job 0 first on machines 0 and 1 is optimal, job 1 then waits until 5 for machine 1 and ends at 9
+      assert_eq!(calculate_cmax(&inst, &solution), 9, "{:?}", spec);
+    }
+  }
+
+  #[test]
+  fn detailed_errors() {
+    let error = parse_instance_detailed("2 2\n0 3 1 x\n0 2 1 4\n").unwrap_err();
+    assert_eq!(
+      error,
+      ParseError {
+        line: 2,
+        column: 7,
+        kind: ParseErrorKind::NotAnInteger("x".to_string()),
+      }
+    );
+    assert_eq!(
+      error.to_string(),
+      "line 2, column 7: \"x\" is not an integer"
+    );
+
+    let error = parse_instance_detailed("2 2\n0 3 1 4\n0 2 1\n").unwrap_err();
+    assert_eq!(error.line, 3);
+    assert_eq!(error.column, 6);
+    assert_eq!(
+      error.kind,
+      ParseErrorKind::MissingField("Duration".to_string())
+    );
+
+    let error = parse_instance_detailed("2 2\n0 3 1 4\n0 4294967296 1 4\n").unwrap_err();
+    assert_eq!((error.line, error.column), (3, 3));
+    assert_eq!(
+      error.kind,
+      ParseErrorKind::OutOfRange("4294967296".to_string())
+    );
+
+    let error = parse_instance_detailed("2 2\n0 3 1 4\n").unwrap_err();
+    assert_eq!(error.line, 3);
+    assert_eq!(
+      error.kind,
+      ParseErrorKind::MissingField("Job 1".to_string())
+    );
+    // A huge job count fails at the first missing line instead of allocating for all jobs
+    let error = parse_instance_detailed("99999999999999999 6\n0 1\n").unwrap_err();
+    assert_eq!(error.line, 3);
+    assert_eq!(
+      error.kind,
+      ParseErrorKind::MissingField("Job 1".to_string())
+    );
+
+    // The wrapper keeps the message
+    let error = parse_instance("2 2\n0 3 1 x\n0 2 1 4\n").unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "line 2, column 7: \"x\" is not an integer"
+    );
+  }
+
+  fn machines_and_durations(inst: &Instance) -> (usize, Vec<usize>, Vec<u32>) {
+    return (
+      inst.n_machines,
+      inst.machines.to_vec(),
+      inst.durations.to_vec(),
+    );
+  }
+
+  #[test]
+  fn taillard_machine_indices() {
+    let zero_based = parse_instance_taillard("2 3\n1 2 3\n4 5 6\n0 1 2\n2 1 0\n").unwrap();
+    let one_based = parse_instance_taillard("2 3 1234 99\n1 2 3\n4 5 6\n\n1 2 3\n3 2 1\n").unwrap();
+    assert_eq!(
+      machines_and_durations(&zero_based),
+      (3, vec![0, 1, 2, 2, 1, 0], vec![1, 2, 3, 4, 5, 6])
+    );
+    assert_eq!(
+      machines_and_durations(&one_based),
+      machines_and_durations(&zero_based)
+    );
+    // 0-based although no operation uses machine 0
+    let unused_first = parse_instance_taillard("2 3\n1 2\n3 4\n1 2\n2 1\n").unwrap();
+    assert_eq!(unused_first.machines.to_vec(), vec![1, 2, 2, 1]);
+
+    let error = parse_instance_taillard("2 3\n1 2\n3 4\n1 4\n2 1\n").unwrap_err();
+    assert_eq!(error.to_string(), "Machine 4 out of range (n_machines=3)");
+    let error = parse_instance_taillard("2 3\n1 2 3\n4 5 6\n0 1 2\n").unwrap_err();
+    assert!(error.to_string().contains("found 3 lines"), "{}", error);
+    let error = parse_instance_taillard("2 3\n1 2 3\n4 5 6\n0 1 2\n2 1\n").unwrap_err();
+    assert_eq!(error.to_string(), "Job 1 has 3 durations, but 2 machines");
+  }
+
+  #[test]
+  fn split_files() {
+    let dir = env::temp_dir().join(format!("heuristics-split-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, contents: &str| {
+      let path = dir.join(name);
+      fs::write(&path, contents).unwrap();
+      return path;
+    };
+    let times = write("times.txt", "3 2\n1 4\n");
+    let zero_based = write("zero-based.txt", "1 0\n0 1\n");
+    let one_based = write("one-based.txt", "2 1\n1 2\n");
+    let missing_job = write("missing-job.txt", "1 0\n");
+    let missing_op = write("missing-op.txt", "1 0\n0\n");
+
+    let inst = parse_instance_split(&times, &zero_based, false).unwrap();
+    assert_eq!(
+      machines_and_durations(&inst),
+      (2, vec![1, 0, 0, 1], vec![3, 2, 1, 4])
+    );
+    let inst_one_based = parse_instance_split(&times, &one_based, true).unwrap();
+    assert_eq!(
+      machines_and_durations(&inst_one_based),
+      machines_and_durations(&inst)
+    );
+    // Without the flag the indices are 0-based, so machine 0 is unused
+    let inst = parse_instance_split(&times, &one_based, false).unwrap();
+    assert_eq!(inst.n_machines, 3);
+    assert!(parse_instance_split(&times, &zero_based, true).is_err());
+
+    let error = parse_instance_split(&times, &missing_job, false).unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "Times file has 2 jobs, but machines file has 1"
+    );
+    let error = parse_instance_split(&times, &missing_op, false).unwrap_err();
+    assert_eq!(error.to_string(), "Job 1 has 2 durations, but 1 machines");
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn malformed_json() {
+    let cases = [
+      ("", "unexpected end of input"),
+      ("{", "expected a key"),
+      (
+        "{\"n_machines\": 1, \"jobs\": [[[0, 3]]]",
+        "expected ',' or '}'",
+      ),
+      (
+        "{\"n_machines\": 1, \"jobs\": [[[0, 3]]]} x",
+        "trailing characters",
+      ),
+      ("\"abc", "unterminated string"),
+      ("[]", "n_machines missing"),
+      ("{\"n_machines\": 2}", "jobs missing"),
+      (
+        "{\"n_machines\": -1, \"jobs\": []}",
+        "n_machines -1 is not a nonnegative integer",
+      ),
+      (
+        "{\"n_machines\": 1.5, \"jobs\": []}",
+        "n_machines 1.5 is not a nonnegative integer",
+      ),
+      ("{\"n_machines\": 1, \"jobs\": {}}", "jobs is not an array"),
+      (
+        "{\"n_machines\": 1, \"jobs\": [[[0]]]}",
+        "Operation [0, 0] is not a [machine, duration] pair",
+      ),
+      (
+        "{\"n_machines\": 1, \"jobs\": [[[0, \"3\"]]]}",
+        "Duration is not a number",
+      ),
+      (
+        "{\"n_machines\": 1, \"jobs\": [[[0, 99999999999]]]}",
+        "Duration 99999999999 is out of range",
+      ),
+      (
+        "{\"n_machines\": 1, \"jobs\": [[[0, 3]]], \"due_dates\": [1, 2]}",
+        "Expected 1 due dates, found 2",
+      ),
+    ];
+    for &(instance, error) in &cases {
+      let message = parse_instance_json(instance).unwrap_err().to_string();
+      assert!(message.contains(error), "{:?}: {}", instance, message);
+    }
+
+    let inst = parse_instance_json(
+      "{\"n_machines\": 2, \"jobs\": [[[0, 3], [1, 2]], []], \"due_dates\": [4, 0]}",
+    )
+    .unwrap();
+    assert_eq!(inst.n_ops(), 2);
+    assert_eq!(inst.due_dates.unwrap().to_vec(), vec![4, 0]);
+  }
+
+  #[test]
+  fn interior_zero_durations() {
+    let inst = parse_instance("3 3\n0 2 1 0 2 3\n1 0 0 4 2 1\n2 3 0 1 1 0\n").unwrap();
+    validate_instance(&inst).unwrap();
+    assert_eq!(inst.n_ops(), 9);
+
+    let dropped = drop_zero_duration_operations(&inst).unwrap();
+    validate_instance(&dropped).unwrap();
+    assert_eq!(dropped.n_ops(), 6);
+    assert_eq!(
+      (0..3).map(|j| dropped.job_len(j)).collect::<Vec<_>>(),
+      vec![2, 2, 2]
+    );
+    assert!(dropped.durations.iter().all(|&d| d > 0));
+    assert_eq!(dropped.machines.to_vec(), vec![0, 2, 0, 2, 2, 0]);
+    assert_eq!(dropped.durations.to_vec(), vec![2, 3, 4, 1, 3, 1]);
+  }
 }
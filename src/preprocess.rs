@@ -0,0 +1,124 @@
+use crate::data::{Edge, Instance, Machine, OpId};
+use ndarray::Array1;
+use std::cmp;
+
+#[derive(Debug, Clone)]
+pub struct PreprocessInfo {
+  // Machines processing at most one operation, these have no conflict edges
+  pub singleton_machines: Vec<Machine>,
+  // Machines whose operations all belong to a single job and are totally ordered by its precedences (see
+  // is_forced_machine), their order is forced
+  pub forced_machines: Vec<Machine>,
+  // Pairs of operations on the same machine (from, to) whose order is forced by job precedences
+  pub forced_pairs: Vec<Edge>,
+}
+
+impl PreprocessInfo {
+  // Trivial machines never contribute a choice to the orientation
+  pub fn is_trivial_machine(&self, machine: Machine) -> bool {
+    return self.singleton_machines.contains(&machine) || self.forced_machines.contains(&machine);
+  }
+}
+
+// Whether the job precedences force the order of two operations, i.e. they belong to the same job and (with explicit
+// job precedences) one reaches the other, then swapping them on their machine always introduces a cycle
+pub fn is_forced_pair(inst: &Instance, a: OpId, b: OpId) -> bool {
+  let [job_a, o_a] = inst.op_from_id(a);
+  let [job_b, o_b] = inst.op_from_id(b);
+  if job_a != job_b {
+    return false;
+  }
+  if inst.job_precedences.is_none() {
+    return true;
+  }
+
+  // Explicit precedences only lead from lower to higher indices
+  let (from, to) = (cmp::min(o_a, o_b), cmp::max(o_a, o_b));
+  let pairs = inst.job_precedence_pairs(job_a);
+  let mut reached = vec![false; inst.job_len(job_a)];
+  reached[from] = true;
+  for o in from..to {
+    if reached[o] {
+      for &(_, succ) in pairs.iter().filter(|&&(pred, _)| pred == o) {
+        reached[succ] = true;
+      }
+    }
+  }
+  return reached[to];
+}
+
+// Whether the job precedences force the whole order of the operations of a machine (given ordered by op id), so the
+// machine contributes no choice to the orientation and its order is the op id order
+pub fn is_forced_machine(inst: &Instance, ops: &[OpId]) -> bool {
+  return ops
+    .windows(2)
+    .all(|pair| is_forced_pair(inst, pair[0], pair[1]));
+}
+
+// Operations processed on each machine, ordered by op id
+pub fn machine_operations(inst: &Instance) -> Array1<Vec<OpId>> {
+  let mut machine_to_operations = Array1::from_elem(inst.n_machines, Vec::new());
+  for op in 0..inst.n_ops() {
+    let m = inst.machines[op];
+    machine_to_operations[m].push(op);
+  }
+
+  return machine_to_operations;
+}
+
+pub fn preprocess(inst: &Instance) -> PreprocessInfo {
+  let machine_to_operations = machine_operations(inst);
+
+  let mut singleton_machines = Vec::new();
+  let mut forced_machines = Vec::new();
+  let mut forced_pairs = Vec::new();
+  for (m, ops) in machine_to_operations.iter().enumerate() {
+    if ops.len() <= 1 {
+      singleton_machines.push(m);
+      continue;
+    }
+
+    // Operations of the same job have ascending op ids in job order
+    for (i, &a) in ops.iter().enumerate() {
+      for &b in &ops[i + 1..] {
+        if is_forced_pair(inst, a, b) {
+          forced_pairs.push((a, b));
+        }
+      }
+    }
+
+    if is_forced_machine(inst, ops) {
+      forced_machines.push(m);
+    }
+  }
+
+  return PreprocessInfo {
+    singleton_machines: singleton_machines,
+    forced_machines: forced_machines,
+    forced_pairs: forced_pairs,
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn singleton_and_forced_machines() {
+    // Machine 2 only processes the last operation of job 1, job 0 visits machine 3 twice and no other job does
+    let inst = Instance::from_jobs(
+      4,
+      &[
+        vec![(0, 2), (3, 1), (1, 3), (3, 2)],
+        vec![(1, 1), (0, 2), (2, 4)],
+      ],
+    );
+    let info = preprocess(&inst);
+    assert_eq!(info.singleton_machines, vec![2]);
+    assert_eq!(info.forced_machines, vec![3]);
+    assert_eq!(info.forced_pairs, vec![(1, 3)]);
+    assert!(info.is_trivial_machine(2));
+    assert!(info.is_trivial_machine(3));
+    assert!(!info.is_trivial_machine(0));
+  }
+}
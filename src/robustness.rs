@@ -0,0 +1,75 @@
+use crate::data::{Edge, Instance};
+use crate::solver::{cmax_from_orientation, seeded_rng};
+use rand::Rng;
+use std::error::Error;
+
+// Multiplies every duration by an independent uniform factor in [1 - magnitude, 1 + magnitude] (rounded)
+// Zero durations stay zero, magnitudes above 1 are clamped so durations never become negative
+pub fn perturb_durations<R: Rng>(inst: &Instance, magnitude: f64, rng: &mut R) -> Instance {
+  let magnitude = magnitude.min(1.0);
+  let durations = inst.durations.map(|&d| {
+    if magnitude <= 0.0 {
+      return d;
+    }
+    let factor = 1.0 + rng.gen_range(-magnitude, magnitude);
+    (d as f64 * factor).round() as u32
+  });
+
+  return Instance {
+    durations: durations,
+    ..inst.clone()
+  };
+}
+
+// Cmax of the fixed orientation (i.e. the machine sequences of a solution) on samples perturbed instances, sorted
+pub fn sample_cmax(
+  inst: &Instance,
+  oriented_conflict_edges: &[Edge],
+  magnitude: f64,
+  samples: usize,
+  seed: u64,
+) -> Result<Vec<u32>, Box<dyn Error>> {
+  let mut rng = seeded_rng(seed);
+  let mut cmaxs = Vec::with_capacity(samples);
+  for _ in 0..samples {
+    let perturbed = perturb_durations(inst, magnitude, &mut rng);
+    cmaxs.push(cmax_from_orientation(&perturbed, oriented_conflict_edges)?);
+  }
+  cmaxs.sort_unstable();
+
+  return Ok(cmaxs);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{calculate_cmax, get_orientation_from_schedule, priority};
+  use crate::test_instances::ft06;
+
+  #[test]
+  fn zero_magnitude_keeps_the_cmax() {
+    let inst = ft06();
+    let solution = priority::find_solution_mwrm(&inst, None);
+    let orientation = get_orientation_from_schedule(&inst, &solution);
+    let nominal = calculate_cmax(&inst, &solution);
+    assert_eq!(
+      sample_cmax(&inst, &orientation, 0.0, 20, 1).unwrap(),
+      vec![nominal; 20]
+    );
+  }
+
+  #[test]
+  fn perturbed_durations_stay_in_range() {
+    let inst = ft06();
+    let mut rng = seeded_rng(2);
+    for _ in 0..20 {
+      let perturbed = perturb_durations(&inst, 0.5, &mut rng);
+      for (&d, &perturbed_d) in inst.durations.iter().zip(perturbed.durations.iter()) {
+        assert!(
+          perturbed_d as f64 >= (d as f64 * 0.5).floor()
+            && perturbed_d as f64 <= (d as f64 * 1.5).ceil()
+        );
+      }
+    }
+  }
+}
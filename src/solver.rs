@@ -1,18 +1,130 @@
+pub mod best_construct;
+pub mod branch_and_bound;
+pub mod checkpoint;
+pub mod clock;
+pub mod compact;
+pub mod crossover;
 pub mod hill_climber;
-mod n1;
+pub mod n1;
+pub mod neighborhood;
+pub mod objective;
 pub mod priority;
 pub mod random_restart_hill_climber;
+pub mod required_order;
+pub mod reservoir;
+pub mod run_options;
 pub mod sequential;
 pub mod simulated_annealing;
+pub mod solution_cache;
+pub mod spec;
+mod stall;
+pub mod steepest_descent;
 pub mod tabu_search;
-
-use crate::data::{Edge, Instance, OpId, Solution};
+pub mod termination;
+mod time_checkpoints;
+mod visited;
+
+use crate::data::{Edge, Instance, Machine, OpId, Solution, Time};
+use crate::preprocess::{is_forced_machine, machine_operations};
+use crate::solver::clock::Stopwatch;
+use crate::solver::objective::Objective;
 use itertools::Itertools;
 use log;
 use ndarray::Array1;
+use rand::SeedableRng;
+use rand_chacha;
 use std::cmp;
-use std::collections::VecDeque;
+use std::collections::{BinaryHeap, VecDeque};
 use std::error::Error;
+use std::mem;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct SolveStats {
+  // Number of search iterations (i.e. evaluated neighborhoods or proposed moves)
+  pub iterations: u64,
+  pub elapsed: Duration,
+  // Elapsed time of this run (excluding resumed runs) divided by the timeout, below 1 if the search stopped early
+  pub timeout_fraction_used: f64,
+  // Share of elapsed spent generating start solutions (including their evaluation)
+  pub construction: Duration,
+  // Share of elapsed spent evaluating and applying moves, the rest of elapsed not spent on construction went to e.g.
+  // verification and checkpoints
+  pub search: Duration,
+  // (iteration, cmax, solution) for every new global best, only collected if requested by the config
+  pub improvements: Vec<(u64, u32, Solution)>,
+  // Sampled (iteration, cmax of the current solution) sorted by iteration, ends with the final best cmax
+  // Only collected if requested by the config, bounded by its trajectory capacity
+  pub trajectory: Vec<(u64, u32)>,
+  // Machine arcs of the returned solution oriented differently than in the start solution of this run (the resumed one
+  // when resuming), i.e. how much the search restructured
+  pub orientation_distance_from_start: usize,
+  // (checkpoint, best cmax at that elapsed time of this run) sorted by checkpoint, only for the checkpoints requested by
+  // the config, those after the end of the search get the final best cmax
+  pub checkpoint_cmax: Vec<(Duration, u32)>,
+  // Iterations of this run of the tabu search where the selected move was worse than a tabu move (not satisfying the
+  // aspiration criterion) that would have improved the current solution, both by their exact cmax, a high count hints
+  // at a too long tenure
+  pub tabu_blocked_improvements: u64,
+  // Initial temperature estimations of this run of simulated annealing (none with a given initial temperature)
+  pub temperature_estimations: u64,
+  // Distinct orientations the iterations of this run moved to (deduplicated by hash), only counted by the tabu search
+  // if requested by the config, exact up to its cap and then the cap
+  pub unique_solutions_visited: usize,
+}
+
+impl SolveStats {
+  pub fn iterations_per_second(&self) -> f64 {
+    let secs = self.elapsed.as_secs_f64();
+    if secs == 0.0 {
+      return 0.0;
+    }
+    return self.iterations as f64 / secs;
+  }
+}
+
+// Share of the timeout taken by a run of the given duration, a zero timeout counts as fully used
+pub fn timeout_fraction(elapsed: Duration, timeout: Duration) -> f64 {
+  if timeout == Duration::from_secs(0) {
+    return 1.0;
+  }
+  return elapsed.as_secs_f64() / timeout.as_secs_f64();
+}
+
+// Runs f and adds its duration (measured on the clock of stopwatch) to total
+pub(crate) fn timed<T>(stopwatch: &Stopwatch, total: &mut Duration, f: impl FnOnce() -> T) -> T {
+  let before = stopwatch.elapsed();
+  let result = f();
+  *total += stopwatch.elapsed().checked_sub(before).unwrap_or_default();
+  return result;
+}
+
+// Whether a search has to stop due to its timeout or its iteration limit
+pub(crate) fn is_exhausted(
+  start: &Stopwatch,
+  timeout: Duration,
+  max_iterations: Option<u64>,
+  iterations: u64,
+) -> bool {
+  return start.elapsed() >= timeout || max_iterations.map_or(false, |max| iterations >= max);
+}
+
+// Lowers the shared best cmax (if any) to cmax, handles should be initialized with u32::MAX
+pub(crate) fn publish_best(shared_best: &Option<Arc<AtomicU32>>, cmax: u32) {
+  if let Some(shared_best) = shared_best {
+    // Compare-and-swap instead of fetch_min, which needs Rust 1.45
+    let mut current = shared_best.load(Ordering::SeqCst);
+    while cmax < current {
+      match shared_best.compare_exchange_weak(current, cmax, Ordering::SeqCst, Ordering::SeqCst) {
+        Ok(_) => break,
+        Err(actual) => current = actual,
+      }
+    }
+  }
+}
 
 #[derive(Debug, Clone)]
 pub struct IntermediateSolution {
@@ -34,7 +146,12 @@ pub struct IntermediateSolution {
 }
 
 impl IntermediateSolution {
+  // Requires linear job chains, i.e. no explicit job precedences (see release_and_tail_times for those)
   pub fn new(instance: Instance, oriented_conflict_edges: Vec<Edge>) -> Self {
+    assert!(
+      instance.job_precedences.is_none(),
+      "IntermediateSolution does not support explicit job precedences"
+    );
     let precedence_edges = get_precedence_edges(&instance);
     let (pre_job, succ_job) = get_pre_succ_relations(&instance, &precedence_edges);
     let (pre_machine, succ_machine) = get_pre_succ_relations(&instance, &oriented_conflict_edges);
@@ -71,10 +188,40 @@ impl IntermediateSolution {
     }
   }
 
+  // Disjunctive graph of the machine sequences of the schedule (see get_orientation_from_schedule), fails if they form
+  // a cycle with the job precedences, which only invalid schedules can cause
+  pub fn from_solution(inst: &Instance, solution: &Solution) -> Result<Self, Box<dyn Error>> {
+    if inst.job_precedences.is_some() {
+      Err("IntermediateSolution does not support explicit job precedences")?;
+    }
+    let orientation = get_orientation_from_schedule(inst, solution);
+    if !is_acyclic_orientation(inst, &orientation) {
+      Err("Machine sequences of the solution contain a cycle")?;
+    }
+    return Ok(IntermediateSolution::new(inst.clone(), orientation));
+  }
+
   pub fn cmax(&self) -> u32 {
     return self.cmax;
   }
 
+  // Number of machine arcs of this solution that are oriented differently in other (same instance)
+  pub(crate) fn arc_distance(&self, other_key: &[Edge]) -> usize {
+    return arc_distance(&self.orientation_key(), other_key);
+  }
+
+  // Orientation in canonical (sorted) order, two solutions of the same instance are equal iff their keys are equal
+  pub fn orientation_key(&self) -> Vec<Edge> {
+    let mut edges = self.oriented_conflict_edges.clone();
+    edges.sort_unstable();
+    return edges;
+  }
+
+  // Full recompute of objective for the schedule of this solution
+  pub fn evaluate(&self, objective: Objective) -> u64 {
+    return objective.evaluate(&self.instance, &self.to_solution());
+  }
+
   pub fn to_solution(&self) -> Solution {
     return Solution {
       start_times: self.release_times.clone(),
@@ -85,10 +232,168 @@ impl IntermediateSolution {
     return self.path_times[node] == self.cmax;
   }
 
-  fn apply_swap(&self, a: OpId, b: OpId) -> IntermediateSolution {
-    // Apply swap for neighborhood n1
-    log::trace!("apply_swap({}, {})", a, b);
-    log::trace!("oriented_conflict_edges={:?}", self.oriented_conflict_edges);
+  // Whether every operation lies on a longest path, equivalent to a slack of zero
+  pub fn critical_operations(&self) -> Array1<bool> {
+    return (0..self.instance.n_ops())
+      .map(|op| self.is_critical(op))
+      .collect();
+  }
+
+  // Total slack of every operation, i.e. by how much it can be delayed without increasing cmax
+  // Zero exactly for the critical operations
+  pub fn slack(&self) -> Array1<u32> {
+    return self.path_times.mapv(|path_time| self.cmax - path_time);
+  }
+
+  // (earliest, latest) start of every operation for this orientation without increasing cmax, i.e. its release time
+  // and cmax minus its tail time (which includes its duration), zero-width exactly for the critical operations
+  pub fn start_windows(&self) -> Array1<(Time, Time)> {
+    return (0..self.instance.n_ops())
+      .map(|op| (self.release_times[op], self.cmax - self.tail_times[op]))
+      .collect();
+  }
+
+  // Number of distinct longest paths from a source to a sink (saturates at usize::MAX)
+  pub fn critical_path_count(&self) -> usize {
+    let inst = &self.instance;
+    // An arc is part of a longest path iff both ends are critical and there is no slack between them
+    let is_tight = |u: OpId, v: OpId| {
+      self.is_critical(u)
+        && self.is_critical(v)
+        && self.release_times[u] + inst.durations[u] == self.release_times[v]
+    };
+
+    let mut in_degree = Array1::<usize>::from_elem(inst.n_ops(), 0);
+    for op in 0..inst.n_ops() {
+      in_degree[op] = self.pre_job[op].iter().chain(&self.pre_machine[op]).count();
+    }
+    let mut open: VecDeque<OpId> = (0..inst.n_ops()).filter(|&op| in_degree[op] == 0).collect();
+
+    // Number of longest paths from any source ending in each operation
+    let mut path_counts = Array1::<usize>::from_elem(inst.n_ops(), 0);
+    let mut count: usize = 0;
+    while let Some(node) = open.pop_front() {
+      let preds: Vec<OpId> = self.pre_job[node]
+        .iter()
+        .chain(&self.pre_machine[node])
+        .copied()
+        .collect();
+      if preds.is_empty() {
+        if self.is_critical(node) {
+          path_counts[node] = 1;
+        }
+      } else {
+        path_counts[node] = preds
+          .iter()
+          .filter(|&&pred| is_tight(pred, node))
+          .fold(0, |acc: usize, &pred| acc.saturating_add(path_counts[pred]));
+      }
+
+      let succs: Vec<OpId> = self.succ_job[node]
+        .iter()
+        .chain(&self.succ_machine[node])
+        .copied()
+        .collect();
+      if succs.is_empty() && self.is_critical(node) {
+        count = count.saturating_add(path_counts[node]);
+      }
+      for succ in succs {
+        in_degree[succ] -= 1;
+        if in_degree[succ] == 0 {
+          open.push_back(succ);
+        }
+      }
+    }
+
+    return count;
+  }
+
+  // Maximal sequences of consecutive operations of a machine on a critical path (each ending exactly when the next one
+  // starts) in machine order, over all critical paths, so every critical operation is in exactly one block, including
+  // blocks of a single operation whose neighbors on the path are job predecessors and successors
+  // Sorted by machine, blocks of the same machine in their order on it
+  pub fn critical_blocks(&self) -> Vec<(Machine, Vec<OpId>)> {
+    let inst = &self.instance;
+    let is_tight = |u: OpId, v: OpId| {
+      self.is_critical(v) && self.release_times[u] + inst.durations[u] == self.release_times[v]
+    };
+
+    let mut blocks = Vec::new();
+    // Every machine sequence starts at an operation without machine predecessor
+    for first in (0..inst.n_ops()).filter(|&op| self.pre_machine[op].is_none()) {
+      let machine = inst.machines[first];
+      let mut block: Vec<OpId> = Vec::new();
+      let mut next = Some(first);
+      while let Some(op) = next {
+        // A block ends before a non-critical operation or a gap
+        if let Some(&last) = block.last() {
+          if !is_tight(last, op) {
+            blocks.push((machine, mem::take(&mut block)));
+          }
+        }
+        if self.is_critical(op) {
+          block.push(op);
+        }
+        next = self.succ_machine[op];
+      }
+      if !block.is_empty() {
+        blocks.push((machine, block));
+      }
+    }
+
+    blocks.sort_by_key(|&(machine, _)| machine);
+    return blocks;
+  }
+
+  // Operations of the machine of op in their current order
+  pub fn machine_sequence(&self, op: OpId) -> Vec<OpId> {
+    let mut first = op;
+    while let Some(pre) = self.pre_machine[first] {
+      first = pre;
+    }
+    let mut sequence = vec![first];
+    while let Some(succ) = self.succ_machine[*sequence.last().unwrap()] {
+      sequence.push(succ);
+    }
+    return sequence;
+  }
+
+  // Moves op to the (0-based) target position of its machine sequence, equivalent to a chain of adjacent swaps
+  // Times are recomputed once for the new orientation
+  // Returns None if the position is out of range, the shift would introduce a cycle or the arcs of the machine do not
+  // form a single chain through op (e.g. for an operation without machine neighbours on a partially oriented machine)
+  pub fn apply_shift(&self, op: OpId, target_position: usize) -> Option<IntermediateSolution> {
+    let mut sequence = self.machine_sequence(op);
+    if target_position >= sequence.len() {
+      return None;
+    }
+    let position = sequence.iter().position(|&o| o == op)?;
+    sequence.remove(position);
+    sequence.insert(target_position, op);
+
+    // The arcs of the machine keep their slots, so shifting back restores the original edge order
+    let machine = self.instance.machines[op];
+    let mut new_arcs = sequence.iter().copied().tuple_windows();
+    let edges = self
+      .oriented_conflict_edges
+      .iter()
+      .map(|&(u, v)| {
+        if self.instance.machines[u] == machine {
+          new_arcs.next()
+        } else {
+          Some((u, v))
+        }
+      })
+      .collect::<Option<Vec<Edge>>>()?;
+    if new_arcs.next().is_some() || !is_acyclic_orientation(&self.instance, &edges) {
+      return None;
+    }
+
+    return Some(IntermediateSolution::new(self.instance.clone(), edges));
+  }
+
+  // Orientation after swapping the machine-adjacent a and b, without computing any times
+  pub(crate) fn swapped_orientation(&self, a: OpId, b: OpId) -> Vec<Edge> {
     let mut edges = self.oriented_conflict_edges.clone();
 
     // Reorient all affected edges
@@ -104,7 +409,14 @@ impl IntermediateSolution {
       }
     }
 
-    let new_oriented_conflict_edges = edges.into_iter().collect();
+    return edges;
+  }
+
+  fn apply_swap(&self, a: OpId, b: OpId) -> IntermediateSolution {
+    // Apply swap for neighborhood n1
+    log::trace!("apply_swap({}, {})", a, b);
+    log::trace!("oriented_conflict_edges={:?}", self.oriented_conflict_edges);
+    let new_oriented_conflict_edges = self.swapped_orientation(a, b);
     log::trace!(
       "new_oriented_conflict_edges: {:?}",
       new_oriented_conflict_edges
@@ -163,6 +475,107 @@ impl IntermediateSolution {
     }
   }
 
+  // Exact cmax after swapping the machine-adjacent operations a -> b without constructing the new solution
+  // Only release times of operations reachable from the swapped pair are recomputed
+  // Returns None if b is not the machine successor of a or if the swap would introduce a cycle
+  pub fn evaluate_swap(&self, a: OpId, b: OpId) -> Option<u32> {
+    if self.succ_machine[a] != Some(b) {
+      return None;
+    }
+
+    let pre_machine = |op: OpId| {
+      if op == a {
+        Some(b)
+      } else if op == b {
+        self.pre_machine[a]
+      } else if Some(op) == self.succ_machine[b] {
+        Some(a)
+      } else {
+        self.pre_machine[op]
+      }
+    };
+    let succ_machine = |op: OpId| {
+      if op == a {
+        self.succ_machine[b]
+      } else if op == b {
+        Some(a)
+      } else if Some(op) == self.pre_machine[a] {
+        Some(b)
+      } else {
+        self.succ_machine[op]
+      }
+    };
+
+    let n_ops = self.instance.n_ops();
+    let mut affected = vec![false; n_ops];
+    let mut stack = vec![b];
+    while let Some(op) = stack.pop() {
+      if affected[op] {
+        continue;
+      }
+      affected[op] = true;
+      stack.extend(self.succ_job[op]);
+      stack.extend(succ_machine(op));
+    }
+
+    let mut in_degree = vec![0; n_ops];
+    let mut open = VecDeque::new();
+    for op in (0..n_ops).filter(|&op| affected[op]) {
+      in_degree[op] = [self.pre_job[op], pre_machine(op)]
+        .iter()
+        .flatten()
+        .filter(|&&pre| affected[pre])
+        .count();
+      if in_degree[op] == 0 {
+        open.push_back(op);
+      }
+    }
+
+    // Release times of unaffected predecessors remain unchanged
+    let mut release_times = self.release_times.clone();
+    let mut processed = 0;
+    while let Some(op) = open.pop_front() {
+      let end =
+        |pre: Option<OpId>| pre.map_or(0, |p| release_times[p] + self.instance.durations[p]);
+      release_times[op] = cmp::max(end(self.pre_job[op]), end(pre_machine(op)));
+      processed += 1;
+
+      for succ in [self.succ_job[op], succ_machine(op)].iter().flatten() {
+        in_degree[*succ] -= 1;
+        if in_degree[*succ] == 0 {
+          open.push_back(*succ);
+        }
+      }
+    }
+
+    if processed < affected.iter().filter(|&&x| x).count() {
+      return None;
+    }
+
+    return Some(calculate_cmax_from_release_times(
+      &self.instance,
+      &release_times,
+    ));
+  }
+
+  // Whether swapping the machine-adjacent a and b provably leaves cmax unchanged because they are interchangeable:
+  // equal durations, equal earliest start max(end of job predecessor, end of machine predecessor of a) and equal tail
+  // times of their job successors (0 without), so every path through a or b keeps its length with the labels exchanged
+  // Only cmax is preserved, the schedule (and thus secondary objectives) may still differ
+  pub(crate) fn is_symmetric_swap(&self, a: OpId, b: OpId) -> bool {
+    if self.instance.durations[a] != self.instance.durations[b] {
+      return false;
+    }
+    let end =
+      |op: Option<OpId>| op.map_or(0, |op| self.release_times[op] + self.instance.durations[op]);
+    let tail = |op: Option<OpId>| op.map_or(0, |op| self.tail_times[op]);
+    let pre_machine_a_end = end(self.pre_machine[a]);
+
+    return cmp::max(end(self.pre_job[a]), pre_machine_a_end)
+      == cmp::max(end(self.pre_job[b]), pre_machine_a_end)
+      && tail(self.succ_job[a]) == tail(self.succ_job[b]);
+  }
+
   // Gives cmax if critical path passes through a or b but at least a lower bound on the new cmax
   fn cmax_after_swap(&self, a: OpId, b: OpId) -> u32 {
     let (a_new_release, a_new_tail, b_new_release, b_new_tail) = self.times_after_swap(a, b);
@@ -219,13 +632,40 @@ impl IntermediateSolution {
   }
 }
 
+// Number of machine arcs oriented differently in the two solutions, e.g. to tell whether solvers converge to the same
+// region, panics if the operations of their instances are not on the same machines
+pub fn orientation_distance(a: &IntermediateSolution, b: &IntermediateSolution) -> usize {
+  assert!(
+    a.instance.machines == b.instance.machines,
+    "Solutions of different instances"
+  );
+  return a.arc_distance(&b.orientation_key());
+}
+
+// Number of arcs in orientation key a that are not contained in orientation key b (both sorted)
+pub(crate) fn arc_distance(a: &[Edge], b: &[Edge]) -> usize {
+  let mut common = 0;
+  let (mut i, mut j) = (0, 0);
+  while i < a.len() && j < b.len() {
+    match a[i].cmp(&b[j]) {
+      cmp::Ordering::Less => i += 1,
+      cmp::Ordering::Greater => j += 1,
+      cmp::Ordering::Equal => {
+        common += 1;
+        i += 1;
+        j += 1;
+      }
+    }
+  }
+  return a.len() - common;
+}
+
+// Job chains, or the explicit precedences of the instance (see Instance::job_precedences)
 pub fn get_precedence_edges(inst: &Instance) -> Vec<Edge> {
   let mut edges = Vec::new();
   for j in 0..inst.n_jobs {
-    for o in 1..inst.n_machines {
-      let op = inst.op_to_id([j, o]);
-      let pre_op = inst.op_to_id([j, o - 1]);
-      edges.push((pre_op, op));
+    for (a, b) in inst.job_precedence_pairs(j) {
+      edges.push((inst.op_to_id([j, a]), inst.op_to_id([j, b])));
     }
   }
 
@@ -233,14 +673,14 @@ pub fn get_precedence_edges(inst: &Instance) -> Vec<Edge> {
 }
 
 pub fn get_orientation_from_schedule(inst: &Instance, solution: &Solution) -> Vec<Edge> {
-  let mut machine_to_operations = Array1::from_elem(inst.n_machines, Vec::new());
-  for op in 0..inst.n_ops() {
-    let m = inst.machines[op];
-    machine_to_operations[m].push(op);
-  }
+  let mut machine_to_operations = machine_operations(inst);
 
   for m in 0..machine_to_operations.len() {
     let ops = &mut machine_to_operations[m];
+    // Singleton machines have no conflict edges to orient, and the order of forced machines is already the op id order
+    if ops.len() <= 1 || is_forced_machine(inst, ops) {
+      continue;
+    }
     ops.sort_by(|&a, &b| {
       if is_before(a, b, &solution.start_times, &inst.durations) {
         cmp::Ordering::Less
@@ -263,6 +703,276 @@ pub fn get_orientation_from_schedule(inst: &Instance, solution: &Solution) -> Ve
   return edges;
 }
 
+// Orientation of dispatching the operations in the order of their start times (ties ordered as by op_ordering, then by
+// op id), delaying every operation until its job predecessors and the operations required before it by extra_edges are
+// dispatched, so it never contains a cycle even if the start times overlap or violate the job precedences
+// Operations on a cycle of the extra edges with the job precedences are left out
+pub fn dispatch_orientation(
+  inst: &Instance,
+  start_times: &Array1<Time>,
+  extra_edges: &[Edge],
+) -> Vec<Edge> {
+  let mut in_degree = Array1::<usize>::from_elem(inst.n_ops(), 0);
+  let mut succs = Array1::from_elem(inst.n_ops(), Vec::new());
+  for &(a, b) in get_precedence_edges(inst).iter().chain(extra_edges) {
+    in_degree[b] += 1;
+    succs[a].push(b);
+  }
+
+  let key = |op: OpId| cmp::Reverse((start_times[op], inst.durations[op] != 0, op));
+  let mut ready: BinaryHeap<_> = (0..inst.n_ops())
+    .filter(|&op| in_degree[op] == 0)
+    .map(key)
+    .collect();
+  let mut sequences = vec![Vec::new(); inst.n_machines];
+  while let Some(cmp::Reverse((_, _, op))) = ready.pop() {
+    sequences[inst.machines[op]].push(op);
+    for &succ in &succs[op] {
+      in_degree[succ] -= 1;
+      if in_degree[succ] == 0 {
+        ready.push(key(succ));
+      }
+    }
+  }
+
+  return sequences
+    .iter()
+    .flat_map(|ops| ops.iter().copied().tuple_windows())
+    .collect();
+}
+
+// Nearest feasible semi-active schedule of a possibly infeasible solution, e.g. with overlapping operations or start
+// times rounded from an LP: every operation starts as early as the precedences and the machine orders of
+// dispatch_orientation allow, a feasible solution keeps its machine orders
+// Never panics for start times of all operations
+pub fn repair(inst: &Instance, solution: &Solution) -> Solution {
+  let orientation = dispatch_orientation(inst, &solution.start_times, &[]);
+  let (release_times, _) =
+    release_and_tail_times(inst, &orientation).expect("Dispatch orientation contains a cycle");
+  return Solution {
+    start_times: release_times,
+  };
+}
+
+// Orientation from the order in which each machine processes the jobs
+// A job visiting a machine multiple times has to be listed once per visit, in the order of its operations
+pub fn get_orientation_from_machine_sequences(
+  inst: &Instance,
+  sequences: &[Vec<usize>],
+) -> Result<Vec<Edge>, Box<dyn Error>> {
+  if sequences.len() != inst.n_machines {
+    Err(format!(
+      "Expected {} machine sequences, found {}",
+      inst.n_machines,
+      sequences.len()
+    ))?;
+  }
+
+  let machine_to_operations = machine_operations(inst);
+  let mut edges = Vec::new();
+  for (m, sequence) in sequences.iter().enumerate() {
+    // Operations of each job on this machine in job order
+    let mut job_operations = vec![VecDeque::new(); inst.n_jobs];
+    for &op in &machine_to_operations[m] {
+      job_operations[inst.op_from_id(op)[0]].push_back(op);
+    }
+
+    let mut ops = Vec::with_capacity(sequence.len());
+    for &job in sequence {
+      let op = job_operations
+        .get_mut(job)
+        .and_then(|job_ops| job_ops.pop_front())
+        .ok_or(format!(
+          "Job {} has no further operation on machine {}",
+          job, m
+        ))?;
+      ops.push(op);
+    }
+    if let Some(job) = job_operations
+      .iter()
+      .position(|job_ops| !job_ops.is_empty())
+    {
+      Err(format!("Sequence of machine {} is missing job {}", m, job))?;
+    }
+
+    for (a, b) in ops.iter().tuple_windows() {
+      edges.push((*a, *b));
+    }
+  }
+
+  return Ok(edges);
+}
+
+// Checks that the orientation orders the operations of each machine in a single chain of machine-adjacent arcs, i.e.
+// that it is a complete orientation as used by IntermediateSolution (cycles through job precedences are not checked)
+pub fn check_machine_chains(
+  inst: &Instance,
+  oriented_conflict_edges: &[Edge],
+) -> Result<(), String> {
+  let n_ops = inst.n_ops();
+  let mut pre_machine = vec![None; n_ops];
+  let mut succ_machine = vec![None; n_ops];
+  for &(a, b) in oriented_conflict_edges {
+    if a >= n_ops || b >= n_ops || a == b || inst.machines[a] != inst.machines[b] {
+      return Err(format!(
+        "Edge ({}, {}) is not a conflict edge of the instance",
+        a, b
+      ));
+    }
+    if succ_machine[a].is_some() || pre_machine[b].is_some() {
+      return Err(format!(
+        "Edge ({}, {}) branches the chain of machine {}",
+        a, b, inst.machines[a]
+      ));
+    }
+    succ_machine[a] = Some(b);
+    pre_machine[b] = Some(a);
+  }
+
+  for (m, ops) in machine_operations(inst).iter().enumerate() {
+    // Without branches the arcs form disjoint paths and cycles, a single chain is one path from the only head
+    let mut heads = ops.iter().filter(|&&op| pre_machine[op].is_none());
+    let mut chain_len = 0;
+    let mut next = heads.next().copied();
+    while let Some(op) = next {
+      chain_len += 1;
+      next = succ_machine[op];
+    }
+    if chain_len != ops.len() {
+      return Err(format!(
+        "Operations of machine {} do not form a single chain",
+        m
+      ));
+    }
+  }
+
+  return Ok(());
+}
+
+// Whether the job precedences together with the (complete) orientation form a DAG
+pub fn is_acyclic_orientation(inst: &Instance, oriented_conflict_edges: &[Edge]) -> bool {
+  let precedence_edges = get_precedence_edges(inst);
+  let mut in_degree = Array1::<usize>::from_elem(inst.n_ops(), 0);
+  let mut succs = Array1::from_elem(inst.n_ops(), Vec::new());
+  for &(a, b) in precedence_edges.iter().chain(oriented_conflict_edges) {
+    in_degree[b] += 1;
+    succs[a].push(b);
+  }
+
+  let mut open: VecDeque<OpId> = (0..inst.n_ops()).filter(|&op| in_degree[op] == 0).collect();
+  let mut sorted = 0;
+  while let Some(node) = open.pop_front() {
+    sorted += 1;
+    for &succ in &succs[node] {
+      in_degree[succ] -= 1;
+      if in_degree[succ] == 0 {
+        open.push_back(succ);
+      }
+    }
+  }
+
+  return sorted == inst.n_ops();
+}
+
+// Machine arcs of a solution of the reference instance as an orientation of the target instance, e.g. as a warm start
+// for a similar instance with other durations; operation ids only identify the same operation if both instances have
+// the same jobs with the same machines per operation
+pub fn orientation_from_reference(
+  target: &Instance,
+  reference: &Instance,
+  reference_orientation: &[Edge],
+) -> Result<Vec<Edge>, String> {
+  if target.job_offsets != reference.job_offsets {
+    return Err(format!(
+      "Instances differ in shape: job offsets {} and {}",
+      reference.job_offsets, target.job_offsets
+    ));
+  }
+  if target.n_machines != reference.n_machines || target.machines != reference.machines {
+    return Err(
+      "Instances differ in shape: operations are processed on different machines".to_string(),
+    );
+  }
+  for &(a, b) in reference_orientation {
+    if a >= target.n_ops() || b >= target.n_ops() || target.machines[a] != target.machines[b] {
+      return Err(format!("Edge {:?} is not a machine arc", (a, b)));
+    }
+  }
+
+  return Ok(reference_orientation.to_vec());
+}
+
+// Cmax of the orientation without building an IntermediateSolution
+// Fails unless the orientation is complete (see check_machine_chains), since the cmax of a partial one would only be a
+// lower bound, or if it contains a cycle
+// Also supports explicit job precedences (see release_and_tail_times)
+pub fn cmax_from_orientation(
+  inst: &Instance,
+  oriented_conflict_edges: &[Edge],
+) -> Result<u32, Box<dyn Error>> {
+  check_machine_chains(inst, oriented_conflict_edges)?;
+  let (release_times, _) =
+    release_and_tail_times(inst, oriented_conflict_edges).ok_or("Orientation contains a cycle")?;
+
+  return Ok(calculate_cmax_from_release_times(inst, &release_times));
+}
+
+// Release times (earliest starts) and tail times (duration plus longest path to the end) of all operations for the
+// precedences of the instance and the orientation, None if they contain a cycle
+// Unlike IntermediateSolution every operation may have several job predecessors and successors, so this also labels
+// instances with explicit job precedences
+pub fn release_and_tail_times(
+  inst: &Instance,
+  oriented_conflict_edges: &[Edge],
+) -> Option<(Array1<u32>, Array1<u32>)> {
+  let mut in_degree = Array1::<usize>::from_elem(inst.n_ops(), 0);
+  let mut preds = Array1::from_elem(inst.n_ops(), Vec::new());
+  let mut succs = Array1::from_elem(inst.n_ops(), Vec::new());
+  for &(a, b) in get_precedence_edges(inst)
+    .iter()
+    .chain(oriented_conflict_edges)
+  {
+    in_degree[b] += 1;
+    preds[b].push(a);
+    succs[a].push(b);
+  }
+
+  let mut order = Vec::with_capacity(inst.n_ops());
+  let mut open: VecDeque<OpId> = (0..inst.n_ops()).filter(|&op| in_degree[op] == 0).collect();
+  while let Some(node) = open.pop_front() {
+    order.push(node);
+    for &succ in &succs[node] {
+      in_degree[succ] -= 1;
+      if in_degree[succ] == 0 {
+        open.push_back(succ);
+      }
+    }
+  }
+  if order.len() != inst.n_ops() {
+    return None;
+  }
+
+  let mut release_times = Array1::<u32>::from_elem(inst.n_ops(), 0);
+  for &op in &order {
+    release_times[op] = preds[op]
+      .iter()
+      .map(|&pred| release_times[pred] + inst.durations[pred])
+      .max()
+      .unwrap_or(0);
+  }
+  let mut tail_times = Array1::<u32>::from_elem(inst.n_ops(), 0);
+  for &op in order.iter().rev() {
+    tail_times[op] = succs[op]
+      .iter()
+      .map(|&succ| tail_times[succ])
+      .max()
+      .unwrap_or(0)
+      + inst.durations[op];
+  }
+
+  return Some((release_times, tail_times));
+}
+
 pub fn is_before(a: OpId, b: OpId, release_times: &Array1<u32>, durations: &Array1<u32>) -> bool {
   let ord = op_ordering(a, b, release_times, durations);
 
@@ -299,7 +1009,8 @@ pub fn op_ordering(
     } else if d_a != 0 && d_b == 0 {
       return cmp::Ordering::Greater;
     } else if d_a == 0 && d_b == 0 {
-      // Fall back to ordering by op id
+      // Fall back to ordering by op id, i.e. by job and position, which is consistent across machines
+      // (e.g. for jobs consisting only of zero-length operations) and therefore never introduces a cycle
       return a.cmp(&b);
     } else {
       return cmp::Ordering::Equal;
@@ -321,7 +1032,7 @@ fn get_release_times_from_pre_succ_relations(
 
   let mut open = VecDeque::new();
   for op in 0..inst.n_ops() {
-    if pre_job[op] == None && pre_machine[op] == None {
+    if pre_job[op].is_none() && pre_machine[op].is_none() {
       open.push_back(op);
       release_time[op] = Some(0);
     }
@@ -367,37 +1078,35 @@ fn get_release_times_from_pre_succ_relations(
     }
   }
 
-  if log::log_enabled!(log::Level::Trace) {
-    if release_time.iter().any(|x| x.is_none()) {
-      let op_rels = inst
-        .op_ids()
-        .iter()
-        .map(|&op| {
-          let default = "_".to_string();
-          format!(
-            "[{} -> {} | pm={}, pj={}, sm={}, sj={}]",
-            op,
-            &release_time[op]
-              .map(|x| x.to_string())
-              .unwrap_or(default.clone()),
-            &pre_machine[op]
-              .map(|x| x.to_string())
-              .unwrap_or(default.clone()),
-            &pre_job[op]
-              .map(|x| x.to_string())
-              .unwrap_or(default.clone()),
-            &succ_machine[op]
-              .map(|x| x.to_string())
-              .unwrap_or(default.clone()),
-            &succ_job[op]
-              .map(|x| x.to_string())
-              .unwrap_or(default.clone()),
-          )
-        })
-        .collect::<Vec<_>>()
-        .join(", ");
-      log::trace!("op_rels=[{}]", op_rels);
-    }
+  if log::log_enabled!(log::Level::Trace) && release_time.iter().any(|x| x.is_none()) {
+    let op_rels = inst
+      .op_ids()
+      .iter()
+      .map(|&op| {
+        let default = "_".to_string();
+        format!(
+          "[{} -> {} | pm={}, pj={}, sm={}, sj={}]",
+          op,
+          &release_time[op]
+            .map(|x| x.to_string())
+            .unwrap_or(default.clone()),
+          &pre_machine[op]
+            .map(|x| x.to_string())
+            .unwrap_or(default.clone()),
+          &pre_job[op]
+            .map(|x| x.to_string())
+            .unwrap_or(default.clone()),
+          &succ_machine[op]
+            .map(|x| x.to_string())
+            .unwrap_or(default.clone()),
+          &succ_job[op]
+            .map(|x| x.to_string())
+            .unwrap_or(default.clone()),
+        )
+      })
+      .collect::<Vec<_>>()
+      .join(", ");
+    log::trace!("op_rels=[{}]", op_rels);
   }
 
   return release_time.map(|r| r.unwrap());
@@ -413,7 +1122,7 @@ fn get_tail_times_from_pre_succ_relations(
 ) -> Array1<u32> {
   let mut open = VecDeque::new();
   for op in 0..inst.n_ops() {
-    if succ_job[op] == None && succ_machine[op] == None {
+    if succ_job[op].is_none() && succ_machine[op].is_none() {
       open.push_back(op);
     }
   }
@@ -484,84 +1193,342 @@ pub fn get_pre_succ_relations(
   return (pre, succ);
 }
 
+// Verification of every intermediate solution shared by the searches (the verify flag of their configs), panics if the
+// solution is infeasible and does not build it if verification is disabled
+pub(crate) fn verify_step(verify: bool, inst: &Instance, solution: impl FnOnce() -> Solution) {
+  if verify {
+    verify_solution(inst, &solution()).expect("Verification failed");
+  }
+}
+
 pub fn verify_solution(inst: &Instance, solution: &Solution) -> Result<(), Box<dyn Error>> {
+  return verify_solution_report(inst, solution).map(|_| ());
+}
+
+// Checks performed by a successful verification, e.g. for auditing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationReport {
+  // Job precedences checked, i.e. pairs (a, b) of operations where a has to end before b starts
+  pub precedence_checks: usize,
+  // Pairs of operations on the same machine checked for overlap
+  pub machine_checks: usize,
+}
+
+// As verify_solution, but reports what was checked if the solution is feasible
+pub fn verify_solution_report(
+  inst: &Instance,
+  solution: &Solution,
+) -> Result<VerificationReport, Box<dyn Error>> {
+  let mut report = VerificationReport {
+    precedence_checks: 0,
+    machine_checks: 0,
+  };
   // Check:
-  // 1. For every job: order
-  // 2. For every job: no overlap
-  // 3. For every machine: no overlap
+  // 1. For every job: order (the chain or the explicit precedences)
+  // 2. For every machine: no overlap
+  let interval = |op: OpId| {
+    let start = solution.start_times[op];
+    return (start, start + inst.durations[op]);
+  };
 
-  for job in 0..inst.n_jobs {
-    for op in 0..inst.n_machines {
-      let op_id = inst.op_to_id([job, op]);
-      let machine = inst.machines[op_id];
-      let duration = inst.durations[op_id];
-      let start = solution.start_times[op_id];
-      let end = start + duration;
-
-      // Who needs decent runtime complexity any way
-      for other_job in 0..inst.n_jobs {
-        for other_op in 0..inst.n_machines {
-          if other_job != job && other_op != op {
-            let other_op_id = inst.op_to_id([other_job, other_op]);
-            let other_machine = inst.machines[other_op_id];
-            let other_duration = inst.durations[other_op_id];
-            let other_start = solution.start_times[other_op_id];
-            let other_end = other_start + other_duration;
-
-            if other_job == job && other_op + 1 == op {
-              if other_end > start {
-                Err(format!(
-                  "Precedence violation in job {:?} - {:?}:[{:?}, {:?}] should be before {:?}:[{:?}, {:?}]",
-                  job,
-                  [job, op],
-                  start,
-                  end,
-                  [other_job, other_op],
-                  other_start,
-                  other_end
-                ))?;
-              }
-            }
+  for (pre_op, op) in get_precedence_edges(inst) {
+    report.precedence_checks += 1;
+    let (pre_start, pre_end) = interval(pre_op);
+    let (start, end) = interval(op);
+    if pre_end > start {
+      let [job, _] = inst.op_from_id(op);
+      Err(format!(
+        "Precedence violation in job {:?} - {:?}:[{:?}, {:?}] should be before {:?}:[{:?}, {:?}]",
+        job,
+        inst.op_from_id(pre_op),
+        pre_start,
+        pre_end,
+        inst.op_from_id(op),
+        start,
+        end
+      ))?;
+    }
+  }
 
-            if other_machine == machine {
-              if !((start <= other_start && end <= other_start)
-                || (other_start <= start && other_end <= start))
-              {
-                Err(format!(
-                  "Overlap in machine {:?} - {:?}:[{:?}, {:?}] overlaps with {:?}:[{:?}, {:?}]",
-                  machine,
-                  [job, op],
-                  start,
-                  end,
-                  [other_job, other_op],
-                  other_start,
-                  other_end
-                ))?;
-              }
-            }
-          }
-        }
+  // Who needs decent runtime complexity any way
+  for op in 0..inst.n_ops() {
+    let (start, end) = interval(op);
+    for other_op in op + 1..inst.n_ops() {
+      if inst.machines[other_op] != inst.machines[op] {
+        continue;
+      }
+      report.machine_checks += 1;
+      let (other_start, other_end) = interval(other_op);
+      if !((start <= other_start && end <= other_start)
+        || (other_start <= start && other_end <= start))
+      {
+        Err(format!(
+          "Overlap in machine {:?} - {:?}:[{:?}, {:?}] overlaps with {:?}:[{:?}, {:?}]",
+          inst.machines[op],
+          inst.op_from_id(op),
+          start,
+          end,
+          inst.op_from_id(other_op),
+          other_start,
+          other_end
+        ))?;
       }
     }
   }
 
-  Ok(())
+  Ok(report)
+}
+
+// Cmax of a valid solution computed directly from its start times and from the disjunctive graph of its orientation
+// The release times of the graph have to form a valid schedule with the graph's cmax, which can only be lower than the
+// solution's cmax if the solution is not semi-active for its orientation (it delays an operation, or it orders
+// zero-duration operations starting at the same time differently than op_ordering)
+pub fn cross_check_cmax(inst: &Instance, solution: &Solution) -> Result<u32, String> {
+  verify_solution(inst, solution).map_err(|e| format!("Invalid solution: {}", e))?;
+
+  let direct = calculate_cmax(inst, solution);
+  let orientation = get_orientation_from_schedule(inst, solution);
+  let intermediate = IntermediateSolution::new(inst.clone(), orientation.clone());
+  let labeled = intermediate.cmax();
+  let release_only = cmax_from_orientation(inst, &orientation).map_err(|e| e.to_string())?;
+  let earliest_start = intermediate.to_solution();
+  verify_solution(inst, &earliest_start)
+    .map_err(|e| format!("Release times of the orientation are invalid: {}", e))?;
+  let earliest_start_cmax = calculate_cmax(inst, &earliest_start);
+  if labeled != release_only || labeled != earliest_start_cmax || labeled > direct {
+    return Err(format!(
+      "Cmax mismatch: {} from the start times, {} from the disjunctive graph ({} from its release times only, {} from its schedule)",
+      direct, labeled, release_only, earliest_start_cmax
+    ));
+  }
+  if labeled < direct {
+    return Err(format!(
+      "Cmax mismatch: {} from the start times, but the earliest start schedule of the orientation reaches {} (the solution is not semi-active)",
+      direct, labeled
+    ));
+  }
+
+  return Ok(direct);
 }
 
 pub fn print_solution(inst: &Instance, solution: &Solution) {
+  print!("{}", format_solution(inst, solution));
+}
+
+// One line per job containing the start times of its operations
+pub fn format_solution(inst: &Instance, solution: &Solution) -> String {
+  return format_per_operation(inst, &solution.start_times);
+}
+
+pub fn print_solution_op_major(inst: &Instance, solution: &Solution) {
+  print!("{}", format_solution_op_major(inst, solution));
+}
+
+// Transpose of format_solution: one line per operation index containing the start times of that operation of every
+// job, jobs with fewer operations (of ragged instances) get a - instead
+pub fn format_solution_op_major(inst: &Instance, solution: &Solution) -> String {
+  let max_job_len = (0..inst.n_jobs)
+    .map(|job| inst.job_len(job))
+    .max()
+    .unwrap_or(0);
+  let mut lines = String::new();
+  for op in 0..max_job_len {
+    let row: Vec<String> = (0..inst.n_jobs)
+      .map(|job| {
+        if op < inst.job_len(job) {
+          solution.start_times[inst.op_to_id([job, op])].to_string()
+        } else {
+          "-".to_string()
+        }
+      })
+      .collect();
+    lines.push_str(&row.join(" "));
+    lines.push('\n');
+  }
+  return lines;
+}
+
+pub fn print_solution_dispatch(inst: &Instance, solution: &Solution) {
+  print!("{}", format_solution_dispatch(inst, solution));
+}
+
+// One line per operation (machine job operation start end), sorted by machine and then by start time
+// Zero-duration operations come first among operations starting at the same time (as in op_ordering)
+pub fn format_solution_dispatch(inst: &Instance, solution: &Solution) -> String {
+  let mut lines = String::new();
+  for (m, ops) in machine_operations(inst).iter().enumerate() {
+    let mut ops = ops.clone();
+    ops.sort_by_key(|&op| {
+      let start = solution.start_times[op];
+      (start, start + inst.durations[op], op)
+    });
+    for op in ops {
+      let [j, o] = inst.op_from_id(op);
+      let start = solution.start_times[op];
+      lines.push_str(&format!(
+        "{} {} {} {} {}\n",
+        m,
+        j,
+        o,
+        start,
+        start + inst.durations[op]
+      ));
+    }
+  }
+  return lines;
+}
+
+// One line per job containing the values of its operations
+pub fn format_per_operation(inst: &Instance, values: &Array1<u32>) -> String {
+  let mut lines = String::new();
   for job in 0..inst.n_jobs {
-    let mut starts = Vec::new();
-    for op in 0..inst.n_machines {
-      let start = solution.start_times[inst.op_to_id([job, op])];
-      starts.push(start.to_string());
+    let mut row = Vec::new();
+    for op in 0..inst.job_len(job) {
+      row.push(values[inst.op_to_id([job, op])].to_string());
     }
-    let line = starts.join(" ");
-    println!("{}", line);
+    lines.push_str(&row.join(" "));
+    lines.push('\n');
   }
+  return lines;
 }
 
 pub fn calculate_cmax(inst: &Instance, solution: &Solution) -> u32 {
-  return calculate_cmax_from_release_times(&inst, &solution.start_times);
+  return calculate_cmax_from_release_times(inst, &solution.start_times);
+}
+
+// Latest completion of the operations of the job without job successors (the last operation of a chain, the sinks of
+// explicit precedences), None for a job without operations
+fn job_completion(inst: &Instance, solution: &Solution, job: usize) -> Option<Time> {
+  let pairs = inst.job_precedence_pairs(job);
+  return (0..inst.job_len(job))
+    .filter(|&o| pairs.iter().all(|&(a, _)| a != o))
+    .map(|o| {
+      let op = inst.op_to_id([job, o]);
+      solution.start_times[op] + inst.durations[op]
+    })
+    .max();
+}
+
+// Sum of job completion times (all jobs are released at 0)
+pub fn calculate_total_flow_time(inst: &Instance, solution: &Solution) -> u64 {
+  let mut total = 0;
+  for job in 0..inst.n_jobs {
+    if let Some(completion) = job_completion(inst, solution, job) {
+      total += completion as u64;
+    }
+  }
+
+  return total;
+}
+
+// Sum over all machines of the gaps between consecutive operations
+// Idle time before the first and after the last operation of a machine is not included, as including the trailing idle
+// time up to cmax would make the total idle time n_machines * cmax - sum of durations, i.e. equivalent to the makespan
+pub fn calculate_total_idle(inst: &Instance, solution: &Solution) -> u64 {
+  let mut total = 0;
+  for ops in machine_operations(inst).iter_mut() {
+    ops.sort_by_key(|&op| (solution.start_times[op], inst.durations[op]));
+    for (&a, &b) in ops.iter().tuple_windows() {
+      let end_a = solution.start_times[a] + inst.durations[a];
+      total += solution.start_times[b].saturating_sub(end_a) as u64;
+    }
+  }
+
+  return total;
+}
+
+// Number of busy machines as a step function: every change point with the count until the next one, starting at 0 and
+// ending with 0 at cmax; an operation occupies its machine during [start, end), so zero durations are never counted
+pub fn resource_profile(inst: &Instance, solution: &Solution) -> Vec<(Time, usize)> {
+  let mut events = Vec::with_capacity(2 * inst.n_ops());
+  for op in 0..inst.n_ops() {
+    if inst.durations[op] > 0 {
+      let start = solution.start_times[op];
+      events.push((start, 1));
+      events.push((start + inst.durations[op], -1));
+    }
+  }
+  events.sort_unstable();
+
+  let mut profile = vec![(0, 0)];
+  let mut busy: i64 = 0;
+  for (time, delta) in events {
+    busy += delta;
+    match profile.last_mut() {
+      Some(last) if last.0 == time => last.1 = busy as usize,
+      _ => profile.push((time, busy as usize)),
+    }
+  }
+  // Merge steps that did not change the count, e.g. an operation starting when another one ends
+  profile.dedup_by(|later, earlier| later.1 == earlier.1);
+
+  return profile;
+}
+
+// Difference between the latest and the earliest completion of a machine's last operation
+// Machines without operations are ignored
+// The maximum machine completion alone is not used, since it always equals the makespan
+pub fn calculate_machine_completion_spread(inst: &Instance, solution: &Solution) -> u64 {
+  let completions: Vec<u32> = machine_operations(inst)
+    .iter()
+    .filter_map(|ops| {
+      ops
+        .iter()
+        .map(|&op| solution.start_times[op] + inst.durations[op])
+        .max()
+    })
+    .collect();
+
+  return match (completions.iter().max(), completions.iter().min()) {
+    (Some(latest), Some(earliest)) => (latest - earliest) as u64,
+    _ => 0,
+  };
+}
+
+// Sum over all jobs of the time by which they complete after their due date
+// Panics if the instance has no due dates (the spec entry points reject such constraints, see SolverSpec::validate)
+pub fn calculate_total_tardiness(inst: &Instance, solution: &Solution) -> u64 {
+  let due_dates = inst
+    .due_dates
+    .as_ref()
+    .expect("Tardiness requires due dates");
+  let mut total = 0;
+  for job in 0..inst.n_jobs {
+    let completion = match job_completion(inst, solution, job) {
+      Some(completion) => completion,
+      None => continue,
+    };
+    total += completion.saturating_sub(due_dates[job]) as u64;
+  }
+
+  return total;
+}
+
+// Sum over all jobs of their earliness weight times the time they complete before their due date plus their tardiness
+// weight times the time they complete after it
+// Only evaluates the schedule as given, the solvers only construct schedules with every operation as early as its
+// machine sequence allows, so inserting idle time to avoid earliness is never considered
+// Panics if the instance has no due dates, like calculate_total_tardiness
+pub fn calculate_weighted_earliness_tardiness(inst: &Instance, solution: &Solution) -> u64 {
+  let due_dates = inst
+    .due_dates
+    .as_ref()
+    .expect("Earliness-tardiness requires due dates");
+  let mut total = 0;
+  for job in 0..inst.n_jobs {
+    let completion = match job_completion(inst, solution, job) {
+      Some(completion) => completion,
+      None => continue,
+    };
+    let (earliness_weight, tardiness_weight) = match &inst.job_weights {
+      Some(weights) => (weights.earliness[job], weights.tardiness[job]),
+      None => (1, 1),
+    };
+    total += due_dates[job].saturating_sub(completion) as u64 * earliness_weight as u64;
+    total += completion.saturating_sub(due_dates[job]) as u64 * tardiness_weight as u64;
+  }
+
+  return total;
 }
 
 fn calculate_cmax_from_release_times(inst: &Instance, release_times: &Array1<u32>) -> u32 {
@@ -578,18 +1545,121 @@ fn calculate_cmax_from_release_times(inst: &Instance, release_times: &Array1<u32
   return cmax;
 }
 
+// Bias of the random construction towards operations with short (SPT) or long (LPT) durations
+// Ready operations are sampled with a weight of exp(-strength * normalized duration) (SPT) or exp(strength * normalized duration) (LPT),
+// so a strength of 0 is uniform and large strengths approach the deterministic rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DurationBias {
+  Spt(f64),
+  Lpt(f64),
+}
+
+impl FromStr for DurationBias {
+  type Err = String;
+
+  // <spt|lpt>:<strength> e.g. spt:2.5
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = s.splitn(2, ':');
+    let rule = parts.next().unwrap();
+    let strength = parts
+      .next()
+      .ok_or(format!("Expected <spt|lpt>:<strength>, found {}", s))?;
+    let strength: f64 = strength
+      .parse()
+      .map_err(|_| format!("Invalid bias strength {}", strength))?;
+    if strength.is_nan() || strength < 0.0 {
+      return Err(format!("Bias strength {} must be nonnegative", strength));
+    }
+
+    return match rule {
+      "spt" => Ok(DurationBias::Spt(strength)),
+      "lpt" => Ok(DurationBias::Lpt(strength)),
+      _ => Err(format!("Unknown bias {}", rule)),
+    };
+  }
+}
+
+// Uniformly random solution unless a bias is given
+pub fn generate_initial_solution<R: rand::Rng>(
+  inst: &Instance,
+  rng: &mut R,
+  bias: Option<DurationBias>,
+) -> Solution {
+  return match bias {
+    Some(bias) => generate_biased_solution(inst, rng, bias),
+    None => generate_random_solution(inst, rng),
+  };
+}
+
 pub fn generate_random_solution<R: rand::Rng>(inst: &Instance, rng: &mut R) -> Solution {
+  return generate_solution_by(inst, &mut |ready| rng.gen_range(0, ready.len()));
+}
+
+// Random number generator of a seed, every random decision of the solvers (and of the robustness sampling) is drawn
+// from one of these, so equal seeds give equal results on every platform and the crate has no unseeded randomness
+pub fn seeded_rng(seed: u64) -> rand_chacha::ChaChaRng {
+  return rand_chacha::ChaChaRng::seed_from_u64(seed);
+}
+
+// Reproducible start independent of any solver's rng stream
+pub fn generate_random_solution_seeded(inst: &Instance, seed: u64) -> Solution {
+  let mut rng = seeded_rng(seed);
+  return generate_random_solution(inst, &mut rng);
+}
+
+pub fn generate_biased_solution<R: rand::Rng>(
+  inst: &Instance,
+  rng: &mut R,
+  bias: DurationBias,
+) -> Solution {
+  let max_duration = cmp::max(inst.durations.iter().copied().max().unwrap_or(0), 1) as f64;
+  let exponent = |op: OpId| {
+    let normalized = inst.durations[op] as f64 / max_duration;
+    match bias {
+      DurationBias::Spt(strength) => -strength * normalized,
+      DurationBias::Lpt(strength) => strength * normalized,
+    }
+  };
+
+  return generate_solution_by(inst, &mut |ready| {
+    // Relative to the largest exponent, so the most preferred operation has weight 1 and large strengths do not underflow
+    let max_exponent = ready
+      .iter()
+      .map(|&op| exponent(op))
+      .fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<f64> = ready
+      .iter()
+      .map(|&op| (exponent(op) - max_exponent).exp())
+      .collect();
+    let total: f64 = weights.iter().sum();
+
+    let mut remaining = rng.gen_range(0.0, total);
+    for (i, weight) in weights.iter().enumerate() {
+      if remaining < *weight {
+        return i;
+      }
+      remaining -= weight;
+    }
+    // Only reachable due to rounding
+    return ready.len() - 1;
+  });
+}
+
+// Repeatedly schedules the ready operation at the index returned by choose as early as possible
+fn generate_solution_by(inst: &Instance, choose: &mut dyn FnMut(&[OpId]) -> usize) -> Solution {
   let mut op_start_times = Array1::<u32>::from_elem(inst.n_ops(), 0);
   let mut machine_next_release = Array1::<u32>::from_elem(inst.n_machines, 0);
   let mut job_next_release = Array1::<u32>::from_elem(inst.n_jobs, 0);
 
   let mut ready = Vec::new();
   for j in 0..inst.n_jobs {
-    ready.push(inst.op_to_id([j, 0]));
+    if inst.job_len(j) > 0 {
+      ready.push(inst.op_to_id([j, 0]));
+    }
   }
 
   while !ready.is_empty() {
-    let chosen_idx = rng.gen_range(0, ready.len());
+    let chosen_idx = choose(&ready);
     let chosen_op = ready.remove(chosen_idx);
 
     let [j, o] = inst.op_from_id(chosen_op);
@@ -601,7 +1671,7 @@ pub fn generate_random_solution<R: rand::Rng>(inst: &Instance, rng: &mut R) -> S
     machine_next_release[m] = finish_time;
     job_next_release[j] = finish_time;
 
-    if o < inst.n_machines - 1 {
+    if o + 1 < inst.job_len(j) {
       ready.push(inst.op_to_id([j, o + 1]));
     }
   }
@@ -610,3 +1680,654 @@ pub fn generate_random_solution<R: rand::Rng>(inst: &Instance, rng: &mut R) -> S
     start_times: op_start_times,
   };
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::data::JobWeights;
+  use crate::solver::neighborhood::NeighborhoodKind;
+  use crate::solver::spec::{solve_with_budget, Budget, PriorityRule, SolverSpec};
+  use crate::test_instances::ft06;
+  use rand::Rng;
+
+  fn sps(inst: &Instance) -> IntermediateSolution {
+    return IntermediateSolution::from_solution(inst, &priority::find_solution_sps(inst, None))
+      .unwrap();
+  }
+
+  #[test]
+  fn evaluate_swap_matches_apply_swap() {
+    let inst = ft06();
+    let solution = sps(&inst);
+    for a in inst.op_ids() {
+      assert_eq!(solution.evaluate_swap(a, a), None);
+      let b = match solution.succ_machine[a] {
+        Some(b) => b,
+        None => continue,
+      };
+      assert_eq!(solution.evaluate_swap(b, a), None);
+      if let Some(c) = solution.succ_machine[b] {
+        assert_eq!(solution.evaluate_swap(a, c), None);
+      }
+      let expected = if is_acyclic_orientation(&inst, &solution.swapped_orientation(a, b)) {
+        Some(solution.apply_swap(a, b).cmax())
+      } else {
+        None
+      };
+      assert_eq!(solution.evaluate_swap(a, b), expected, "{:?}", (a, b));
+    }
+  }
+
+  // Two jobs on machine 0 then machine 1, priority-sps schedules job 0 first on both
+  fn two_critical_paths() -> Instance {
+    return Instance::from_jobs(2, &[vec![(0, 3), (1, 2)], vec![(0, 2), (1, 4)]]);
+  }
+
+  #[test]
+  fn critical_path_count() {
+    // Job 0 on machine 0 (3), then either job 1 on machine 0 (2) and machine 1 (4) or job 0 (2) and job 1 (4) on
+    // machine 1, both of length 9
+    let inst = two_critical_paths();
+    let solution = sps(&inst);
+    assert_eq!(solution.cmax(), 9);
+    assert_eq!(solution.critical_path_count(), 2);
+  }
+
+  #[test]
+  fn duration_biased_solutions() {
+    // Both operations are ready at the start, the first chosen one starts at 0
+    let inst = Instance::from_jobs(1, &[vec![(0, 1)], vec![(0, 10)]]);
+    let shortest_first_count = |generate: &mut dyn FnMut() -> Solution| {
+      return (0..1000)
+        .filter(|_| generate().start_time(&inst, 0, 0) == 0)
+        .count();
+    };
+    let mut rng = seeded_rng(0);
+    let extreme = shortest_first_count(&mut || {
+      generate_biased_solution(&inst, &mut rng, DurationBias::Spt(50.0))
+    });
+    assert!(extreme >= 999, "{}", extreme);
+    let unbiased = shortest_first_count(&mut || {
+      generate_biased_solution(&inst, &mut rng, DurationBias::Spt(0.0))
+    });
+    let uniform = shortest_first_count(&mut || generate_random_solution(&inst, &mut rng));
+    assert!((450..=550).contains(&unbiased), "{}", unbiased);
+    assert!((450..=550).contains(&uniform), "{}", uniform);
+  }
+
+  #[test]
+  fn total_idle() {
+    let inst = Instance::from_jobs(2, &[vec![(0, 2), (1, 3)], vec![(0, 1), (1, 1)]]);
+    // Job 1 first on both machines: machine 0 runs 0-1 and 1-3, machine 1 runs 1-2 and 3-6
+    let solution = Solution::from_machine_sequences(&inst, &[vec![1, 0], vec![1, 0]]).unwrap();
+    assert_eq!(solution.start_times.to_vec(), vec![1, 3, 0, 1]);
+    // Only the gap 2-3 on machine 1, neither the idle time before 1 on machine 1 nor after 3 on machine 0
+    assert_eq!(calculate_total_idle(&inst, &solution), 1);
+    let solution = Solution::from_machine_sequences(&inst, &[vec![0, 1], vec![0, 1]]).unwrap();
+    assert_eq!(calculate_total_idle(&inst, &solution), 0);
+  }
+
+  #[test]
+  fn zero_slack_operations_are_critical() {
+    let inst = ft06();
+    for seed in 0..5 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      let slack = solution.slack();
+      assert!(slack.iter().any(|&slack| slack > 0));
+      for op in inst.op_ids() {
+        assert_eq!(slack[op] == 0, solution.is_critical(op));
+        let path_time = solution.release_times[op] + solution.tail_times[op];
+        assert_eq!(path_time + slack[op], solution.cmax());
+      }
+    }
+  }
+
+  #[test]
+  fn shift_and_shift_back() {
+    let inst = ft06();
+    let solution = sps(&inst);
+    for op in inst.op_ids() {
+      let sequence = solution.machine_sequence(op);
+      let position = sequence.iter().position(|&o| o == op).unwrap();
+      for target in 0..sequence.len() {
+        let shifted = match solution.apply_shift(op, target) {
+          Some(shifted) => shifted,
+          None => continue,
+        };
+        assert_eq!(shifted.machine_sequence(op)[target], op);
+        let restored = shifted.apply_shift(op, position).unwrap();
+        assert_eq!(
+          restored.oriented_conflict_edges,
+          solution.oriented_conflict_edges
+        );
+        assert_eq!(restored.cmax(), solution.cmax());
+      }
+      assert!(solution.apply_shift(op, sequence.len()).is_none());
+    }
+
+    // Operation 2 has no arc on the partially oriented machine
+    let inst = Instance::from_jobs(1, &[vec![(0, 1)], vec![(0, 2)], vec![(0, 3)]]);
+    let partial = IntermediateSolution::new(inst, vec![(0, 1)]);
+    assert!(partial.apply_shift(2, 0).is_none());
+    assert!(partial.apply_shift(0, 1).is_some());
+  }
+
+  #[test]
+  fn machine_completion_spread() {
+    // Machine 2 has no operations and is ignored
+    let inst = Instance::from_jobs(3, &[vec![(0, 2), (1, 3)], vec![(1, 1), (0, 1)]]);
+    // Machine 0 runs 0-2 and 2-3, machine 1 runs 0-1 and 2-5
+    let solution =
+      Solution::from_machine_sequences(&inst, &[vec![0, 1], vec![1, 0], vec![]]).unwrap();
+    assert_eq!(solution.start_times.to_vec(), vec![0, 2, 0, 2]);
+    assert_eq!(calculate_machine_completion_spread(&inst, &solution), 5 - 3);
+    // Machine 0 runs 1-2 and 2-4, machine 1 runs 0-1 and 4-7
+    let solution =
+      Solution::from_machine_sequences(&inst, &[vec![1, 0], vec![1, 0], vec![]]).unwrap();
+    assert_eq!(solution.start_times.to_vec(), vec![2, 4, 0, 1]);
+    assert_eq!(calculate_machine_completion_spread(&inst, &solution), 7 - 4);
+  }
+
+  #[test]
+  fn critical_operations() {
+    let inst = ft06();
+    for seed in 0..5 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      let critical = solution.critical_operations();
+      let slack = solution.slack();
+      assert!(inst
+        .op_ids()
+        .iter()
+        .all(|&op| !critical[op] || slack[op] == 0));
+
+      // Follow one critical path from its start, every step to a critical successor starting when op ends
+      let mut op = inst
+        .op_ids()
+        .into_iter()
+        .find(|&op| critical[op] && solution.release_times[op] == 0)
+        .unwrap();
+      let mut path_len = 1;
+      while let Some(next) = [solution.succ_job[op], solution.succ_machine[op]]
+        .iter()
+        .flatten()
+        .copied()
+        .find(|&next| {
+          critical[next]
+            && solution.release_times[op] + inst.durations[op] == solution.release_times[next]
+        })
+      {
+        op = next;
+        path_len += 1;
+      }
+      assert_eq!(
+        solution.release_times[op] + inst.durations[op],
+        solution.cmax()
+      );
+      let count = critical.iter().filter(|&&critical| critical).count();
+      assert!(count >= path_len, "{} < {}", count, path_len);
+    }
+  }
+
+  #[test]
+  fn cmax_from_orientations() {
+    let inst = ft06();
+    for seed in 0..10 {
+      let solution = generate_random_solution_seeded(&inst, seed);
+      let orientation = get_orientation_from_schedule(&inst, &solution);
+      assert_eq!(
+        cmax_from_orientation(&inst, &orientation).unwrap(),
+        IntermediateSolution::new(inst.clone(), orientation).cmax()
+      );
+    }
+
+    let inst = Instance::from_jobs(2, &[vec![(0, 3), (1, 2)], vec![(1, 4), (0, 1)]]);
+    // Job 1 first on machine 0 and job 0 first on machine 1
+    let cyclic = [(3, 0), (1, 2)];
+    assert!(cmax_from_orientation(&inst, &cyclic).is_err());
+    // Machine 1 is not ordered, so its operations could overlap
+    let partial = [(0, 3)];
+    assert!(cmax_from_orientation(&inst, &partial).is_err());
+    assert_eq!(cmax_from_orientation(&inst, &[(0, 3), (2, 1)]).unwrap(), 6);
+  }
+
+  #[test]
+  fn dispatch_listing() {
+    let inst = ft06();
+    let solution = priority::find_solution_mwrm(&inst, None);
+    let rows: Vec<Vec<u32>> = format_solution_dispatch(&inst, &solution)
+      .lines()
+      .map(|line| {
+        line
+          .split(' ')
+          .map(|value| value.parse().unwrap())
+          .collect()
+      })
+      .collect();
+    assert_eq!(rows.len(), inst.n_ops());
+    for row in &rows {
+      let op = inst.op_to_id([row[1] as usize, row[2] as usize]);
+      assert_eq!(row[0] as usize, inst.machines[op]);
+      assert_eq!(row[3], solution.start_times[op]);
+      assert_eq!(row[4], row[3] + inst.durations[op]);
+    }
+    // Sorted by machine ascending, then by start ascending
+    for pair in rows.windows(2) {
+      assert!((pair[0][0], pair[0][3]) <= (pair[1][0], pair[1][3]));
+    }
+  }
+
+  #[test]
+  fn cross_check_random_solutions() {
+    let mut rng = seeded_rng(0);
+    for _ in 0..200 {
+      let n_machines = rng.gen_range(1, 5);
+      // Half of the instances have zero durations
+      let min_duration = rng.gen_range(0, 2);
+      let jobs: Vec<Vec<(usize, u32)>> = (0..rng.gen_range(1, 6))
+        .map(|_| {
+          (0..rng.gen_range(1, 2 * n_machines))
+            .map(|_| {
+              (
+                rng.gen_range(0, n_machines),
+                rng.gen_range(min_duration, 10),
+              )
+            })
+            .collect()
+        })
+        .collect();
+      let inst = Instance::from_jobs(n_machines, &jobs);
+      for schedule in &[
+        priority::find_solution_sps(&inst, None),
+        priority::find_solution_mwrm(&inst, None),
+      ] {
+        assert_eq!(
+          cross_check_cmax(&inst, schedule),
+          Ok(calculate_cmax(&inst, schedule))
+        );
+      }
+      for _ in 0..5 {
+        let schedule = generate_random_solution(&inst, &mut rng);
+        assert_eq!(
+          cross_check_cmax(&inst, &schedule),
+          Ok(calculate_cmax(&inst, &schedule))
+        );
+
+        // Random walk over feasible adjacent swaps to also cover orientations no constructor produces, only without
+        // zero durations, as a swap can order zero-duration operations starting at the same time against op_ordering
+        if inst.durations.iter().any(|&duration| duration == 0) {
+          continue;
+        }
+        let mut solution = IntermediateSolution::from_solution(&inst, &schedule).unwrap();
+        for _ in 0..10 {
+          let a = rng.gen_range(0, inst.n_ops());
+          if let Some(b) = solution.succ_machine[a] {
+            if solution.evaluate_swap(a, b).is_some() {
+              solution = solution.apply_swap(a, b);
+            }
+          }
+        }
+        let schedule = solution.to_solution();
+        assert_eq!(
+          cross_check_cmax(&inst, &schedule),
+          Ok(calculate_cmax(&inst, &schedule))
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn cross_check_delayed_solution() {
+    let inst = two_critical_paths();
+    let mut solution = priority::find_solution_sps(&inst, None);
+    assert_eq!(cross_check_cmax(&inst, &solution), Ok(9));
+    // Delaying the last operation keeps the solution valid but not semi-active
+    solution.start_times[inst.op_to_id([1, 1])] += 1;
+    assert!(cross_check_cmax(&inst, &solution)
+      .unwrap_err()
+      .contains("not semi-active"));
+    // Starting it before its job predecessor completes is invalid
+    solution.start_times[inst.op_to_id([1, 1])] = 0;
+    assert!(cross_check_cmax(&inst, &solution)
+      .unwrap_err()
+      .starts_with("Invalid solution"));
+  }
+
+  #[test]
+  fn orientation_of_a_reference() {
+    let inst = ft06();
+    let solution = priority::find_solution_mwrm(&inst, None);
+    let orientation = get_orientation_from_schedule(&inst, &solution);
+    let seeded = orientation_from_reference(&inst, &inst, &orientation).unwrap();
+    let warm_start = IntermediateSolution::new(inst.clone(), seeded);
+    assert_eq!(warm_start.cmax(), calculate_cmax(&inst, &solution));
+    assert_eq!(warm_start.to_solution().start_times, solution.start_times);
+
+    // Other durations keep the shape
+    let mut target = inst.clone();
+    target.durations.mapv_inplace(|duration| duration + 1);
+    let seeded = orientation_from_reference(&target, &inst, &orientation).unwrap();
+    verify_solution(
+      &target,
+      &IntermediateSolution::new(target.clone(), seeded).to_solution(),
+    )
+    .unwrap();
+
+    let mut other_machines = inst.clone();
+    other_machines.machines.swap(0, 1);
+    assert!(orientation_from_reference(&other_machines, &inst, &orientation).is_err());
+    let fewer_jobs = Instance::from_jobs(6, &[vec![(0, 1)]]);
+    assert!(orientation_from_reference(&fewer_jobs, &inst, &orientation).is_err());
+  }
+
+  #[test]
+  fn resource_profile_peak() {
+    let inst = two_critical_paths();
+    let solution = priority::find_solution_sps(&inst, None);
+    // Job 0 on machine 1 overlaps job 1 on machine 0 during [3, 5)
+    assert_eq!(
+      resource_profile(&inst, &solution),
+      vec![(0, 1), (3, 2), (5, 1), (9, 0)]
+    );
+
+    let inst = ft06();
+    let solution = priority::find_solution_mwrm(&inst, None);
+    let profile = resource_profile(&inst, &solution);
+    let busy_at = |time: u32| {
+      inst
+        .op_ids()
+        .into_iter()
+        .filter(|&op| {
+          solution.start_times[op] <= time && time < solution.start_times[op] + inst.durations[op]
+        })
+        .count()
+    };
+    let cmax = calculate_cmax(&inst, &solution);
+    assert_eq!(profile.last(), Some(&(cmax, 0)));
+    for window in profile.windows(2) {
+      for time in window[0].0..window[1].0 {
+        assert_eq!(busy_at(time), window[0].1);
+      }
+    }
+    let peak = profile.iter().map(|&(_, busy)| busy).max().unwrap();
+    assert_eq!(peak, (0..cmax).map(busy_at).max().unwrap());
+  }
+
+  #[test]
+  fn weighted_earliness_tardiness() {
+    let mut inst = Instance::from_jobs(2, &[vec![(0, 3)], vec![(0, 2), (1, 1)]]);
+    inst.due_dates = Some(Array1::from(vec![5, 4]));
+    // Job 0 first: job 0 completes at 3 (2 early), job 1 at 6 (2 late)
+    let job_0_first = Solution::from_machine_sequences(&inst, &[vec![0, 1], vec![1]]).unwrap();
+    // Job 1 first: job 1 completes at 3 (1 early), job 0 at 5 (on time)
+    let job_1_first = Solution::from_machine_sequences(&inst, &[vec![1, 0], vec![1]]).unwrap();
+    assert_eq!(
+      calculate_weighted_earliness_tardiness(&inst, &job_0_first),
+      2 + 2
+    );
+    assert_eq!(
+      calculate_weighted_earliness_tardiness(&inst, &job_1_first),
+      1
+    );
+
+    inst.job_weights = Some(JobWeights {
+      earliness: Array1::from(vec![3, 5]),
+      tardiness: Array1::from(vec![7, 11]),
+    });
+    assert_eq!(
+      calculate_weighted_earliness_tardiness(&inst, &job_0_first),
+      2 * 3 + 2 * 11
+    );
+    assert_eq!(
+      calculate_weighted_earliness_tardiness(&inst, &job_1_first),
+      5
+    );
+    assert_eq!(
+      Objective::WeightedEarlinessTardiness.evaluate(&inst, &job_0_first),
+      28
+    );
+  }
+
+  #[test]
+  fn from_solution_matches_two_steps() {
+    let inst = ft06();
+    let solutions = (0..10)
+      .map(|seed| generate_random_solution_seeded(&inst, seed))
+      .chain(vec![
+        priority::find_solution_sps(&inst, None),
+        priority::find_solution_mwrm(&inst, None),
+      ]);
+    for solution in solutions {
+      let orientation = get_orientation_from_schedule(&inst, &solution);
+      assert_eq!(
+        IntermediateSolution::from_solution(&inst, &solution)
+          .unwrap()
+          .cmax(),
+        IntermediateSolution::new(inst.clone(), orientation).cmax()
+      );
+    }
+
+    // Job 1 first on machine 0 and job 0 first on machine 1, against the job orders
+    let inst = Instance::from_jobs(2, &[vec![(0, 3), (1, 2)], vec![(1, 4), (0, 1)]]);
+    let cyclic = Solution {
+      start_times: Array1::from(vec![5, 0, 2, 1]),
+    };
+    assert!(IntermediateSolution::from_solution(&inst, &cyclic).is_err());
+  }
+
+  // Job 0 branches after its first operation, job 1 is a chain
+  fn branching_instance() -> Instance {
+    let mut inst = Instance::from_jobs(3, &[vec![(0, 3), (1, 5), (2, 2)], vec![(2, 4), (0, 1)]]);
+    inst.job_precedences = Some(vec![vec![(0, 1), (0, 2)], vec![(0, 1)]]);
+    return inst;
+  }
+
+  #[test]
+  fn branching_job_precedences() {
+    let inst = branching_instance();
+    assert_eq!(get_precedence_edges(&inst), vec![(0, 1), (0, 2), (3, 4)]);
+    // Operation 4 after 0 on machine 0, 3 before 2 on machine 2
+    let (release_times, tail_times) = release_and_tail_times(&inst, &[(0, 4), (3, 2)]).unwrap();
+    assert_eq!(release_times.to_vec(), vec![0, 3, 4, 0, 4]);
+    assert_eq!(tail_times.to_vec(), vec![8, 5, 2, 6, 1]);
+
+    // Operation 2 completes before operation 1, which a chain would not allow
+    let solution = Solution {
+      start_times: release_times,
+    };
+    verify_solution(&inst, &solution).unwrap();
+    assert_eq!(calculate_cmax(&inst, &solution), 8);
+    // Job 0 completes with operation 1, job 1 with operation 4
+    assert_eq!(calculate_total_flow_time(&inst, &solution), 8 + 5);
+
+    let early = Solution {
+      start_times: Array1::from(vec![0, 2, 4, 0, 4]),
+    };
+    assert!(verify_solution(&inst, &early).is_err());
+
+    // Only the labeling and the verification support them, not the solvers
+    assert!(IntermediateSolution::from_solution(&inst, &solution).is_err());
+    let spec = SolverSpec::Priority(PriorityRule::Sps);
+    let error = solve_with_budget(&inst, &spec, Budget::Iterations(10)).unwrap_err();
+    assert_eq!(error, "The solvers do not support explicit job precedences");
+  }
+
+  #[test]
+  fn repair_overlapping_solutions() {
+    let inst = ft06();
+    let overlapping = Solution {
+      start_times: Array1::from_elem(inst.n_ops(), 0),
+    };
+    assert!(verify_solution(&inst, &overlapping).is_err());
+    verify_solution(&inst, &repair(&inst, &overlapping)).unwrap();
+
+    let mut rng = seeded_rng(0);
+    for seed in 0..10 {
+      let solution = generate_random_solution_seeded(&inst, seed);
+      let repaired = repair(&inst, &solution);
+      assert_eq!(repaired.start_times, solution.start_times, "{}", seed);
+      // Shifted start times as if rounded from a relaxation
+      let shifted = Solution {
+        start_times: solution
+          .start_times
+          .mapv(|start| (start + rng.gen_range(0, 3)).saturating_sub(1)),
+      };
+      verify_solution(&inst, &repair(&inst, &shifted)).unwrap();
+    }
+
+    // Both operations of machine 0 start at 0, the tie is broken by op id
+    let inst = Instance::from_jobs(1, &[vec![(0, 3)], vec![(0, 2)]]);
+    let repaired = repair(
+      &inst,
+      &Solution {
+        start_times: Array1::from(vec![0, 0]),
+      },
+    );
+    assert_eq!(repaired.start_times.to_vec(), vec![0, 3]);
+  }
+
+  #[test]
+  fn singleton_machine() {
+    // Machine 2 only processes the last operation of job 1
+    let inst = Instance::from_jobs(
+      3,
+      &[
+        vec![(0, 3), (1, 2)],
+        vec![(1, 2), (0, 4), (2, 3)],
+        vec![(0, 1), (1, 4)],
+      ],
+    );
+    let singleton = inst.op_to_id([1, 2]);
+    let optimum = calculate_cmax(&inst, &branch_and_bound::find_optimal_solution(&inst));
+    for seed in 0..10 {
+      let solution = generate_random_solution_seeded(&inst, seed);
+      let orientation = get_orientation_from_schedule(&inst, &solution);
+      assert!(orientation
+        .iter()
+        .all(|&(a, b)| a != singleton && b != singleton));
+      let intermediate = IntermediateSolution::new(inst.clone(), orientation);
+      assert!(n1::generate_moves(&intermediate, false)
+        .iter()
+        .all(|m| m.swap_move.0 != singleton && m.swap_move.1 != singleton));
+      assert_eq!(intermediate.cmax(), calculate_cmax(&inst, &solution));
+    }
+    let spec = SolverSpec::TabuSearch {
+      seed: 0,
+      neighborhood: NeighborhoodKind::N1,
+      skip_symmetric_swaps: false,
+      secondary_objective: None,
+      constraint: None,
+      verify: true,
+    };
+    let (solution, _) = solve_with_budget(&inst, &spec, Budget::Iterations(200)).unwrap();
+    verify_solution(&inst, &solution).unwrap();
+    assert_eq!(calculate_cmax(&inst, &solution), optimum);
+  }
+
+  #[test]
+  fn op_major_is_the_transpose() {
+    let inst = ft06();
+    let solution = priority::find_solution_mwrm(&inst, None);
+    let parse = |text: String| -> Vec<Vec<String>> {
+      text
+        .lines()
+        .map(|line| line.split(' ').map(str::to_string).collect())
+        .collect()
+    };
+    let job_major = parse(format_solution(&inst, &solution));
+    let op_major = parse(format_solution_op_major(&inst, &solution));
+    assert_eq!(op_major.len(), inst.n_machines);
+    for (o, row) in op_major.iter().enumerate() {
+      assert_eq!(row.len(), inst.n_jobs);
+      for (j, value) in row.iter().enumerate() {
+        assert_eq!(value, &job_major[j][o]);
+      }
+    }
+
+    // Missing operations of shorter jobs are printed as -
+    let ragged = Instance::from_jobs(2, &[vec![(0, 2), (1, 3)], vec![(1, 1)]]);
+    let solution = priority::find_solution_sps(&ragged, None);
+    assert_eq!(format_solution(&ragged, &solution), "0 2\n0\n");
+    assert_eq!(format_solution_op_major(&ragged, &solution), "0 0\n2 -\n");
+  }
+
+  #[test]
+  fn verification_report_counts() {
+    // 6 jobs of 6 operations: 5 precedences per job, 15 pairs of jobs on each machine
+    let inst = ft06();
+    let report = verify_solution_report(&inst, &priority::find_solution_sps(&inst, None)).unwrap();
+    assert_eq!(report.precedence_checks, 6 * 5);
+    assert_eq!(report.machine_checks, 6 * 15);
+
+    // Explicit precedences are checked instead of the chains, machine 1 has a single operation
+    let inst = branching_instance();
+    let (release_times, _) = release_and_tail_times(&inst, &[(0, 4), (3, 2)]).unwrap();
+    let solution = Solution {
+      start_times: release_times,
+    };
+    let report = verify_solution_report(&inst, &solution).unwrap();
+    assert_eq!(report.precedence_checks, 3);
+    assert_eq!(report.machine_checks, 2);
+  }
+
+  #[test]
+  fn start_windows_of_critical_operations() {
+    let inst = ft06();
+    for seed in 0..10 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      let windows = solution.start_windows();
+      for op in 0..inst.n_ops() {
+        let (earliest, latest) = windows[op];
+        assert!(latest >= earliest);
+        assert_eq!(earliest, solution.release_times[op]);
+        assert_eq!(earliest == latest, solution.is_critical(op), "{}", op);
+        assert!(latest + inst.durations[op] <= solution.cmax());
+      }
+    }
+  }
+
+  #[test]
+  fn orientation_distance_of_a_swap() {
+    let inst = ft06();
+    let solution =
+      IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, 0))
+        .unwrap();
+    assert_eq!(orientation_distance(&solution, &solution), 0);
+    let moves = n1::generate_moves(&solution, false);
+    assert!(!moves.is_empty());
+    for candidate in moves {
+      let (a, b) = candidate.swap_move;
+      let swapped = solution.apply_swap(a, b);
+      // The arc between a and b is reversed, and the arcs to their machine neighbors now start or end at the other one
+      let expected = 1
+        + solution.pre_machine[a].is_some() as usize
+        + solution.succ_machine[b].is_some() as usize;
+      assert_eq!(orientation_distance(&solution, &swapped), expected);
+      assert_eq!(orientation_distance(&swapped, &solution), expected);
+    }
+  }
+
+  #[test]
+  fn known_critical_blocks() {
+    let inst = Instance::from_jobs(2, &[vec![(0, 3), (1, 2)], vec![(0, 2), (1, 4)]]);
+    let solution = sps(&inst);
+    assert_eq!(
+      solution.critical_blocks(),
+      vec![(0, vec![0, 2]), (1, vec![1, 3])]
+    );
+
+    // The first operation on machine 0 is not critical, so the block only holds the second one
+    let inst = Instance::from_jobs(2, &[vec![(0, 3), (1, 1)], vec![(1, 5), (0, 1)]]);
+    let solution = sps(&inst);
+    assert_eq!(solution.cmax(), 6);
+    assert_eq!(
+      solution.critical_blocks(),
+      vec![(0, vec![3]), (1, vec![2, 1])]
+    );
+  }
+}
@@ -0,0 +1,121 @@
+use crate::data::{Instance, Solution};
+use crate::solver::spec::PriorityRule;
+use crate::solver::{calculate_cmax, generate_initial_solution, seeded_rng, DurationBias};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::fmt;
+
+pub struct Config {
+  pub seed: u64,
+  // Number of random constructions in addition to the dispatching rules
+  pub random_constructions: usize,
+  // Bias of the random constructions, uniform if None
+  pub init_bias: Option<DurationBias>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constructor {
+  Rule(PriorityRule),
+  // Seed of the construction's own rng
+  Random(u64, Option<DurationBias>),
+}
+
+impl Constructor {
+  pub fn construct(&self, inst: &Instance) -> Solution {
+    return match self {
+      Constructor::Rule(rule) => rule.find_solution(inst),
+      Constructor::Random(seed, bias) => {
+        let mut rng = seeded_rng(*seed);
+        generate_initial_solution(inst, &mut rng, *bias)
+      }
+    };
+  }
+}
+
+impl fmt::Display for Constructor {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    return match self {
+      Constructor::Rule(rule) => write!(f, "priority-{}", rule.name()),
+      Constructor::Random(seed, None) => write!(f, "random (seed {})", seed),
+      Constructor::Random(seed, Some(bias)) => write!(f, "random {:?} (seed {})", bias, seed),
+    };
+  }
+}
+
+// Best of all dispatching rules and random constructions without any local search
+// Ties are broken by the order of the constructors (rules first), so the result does not depend on the parallelization
+pub fn find_solution(inst: &Instance, config: &Config) -> (Solution, Constructor) {
+  let mut constructors: Vec<Constructor> = PriorityRule::ALL
+    .iter()
+    .map(|&rule| Constructor::Rule(rule))
+    .collect();
+  for i in 0..config.random_constructions {
+    constructors.push(Constructor::Random(
+      config.seed.wrapping_add(i as u64),
+      config.init_bias,
+    ));
+  }
+
+  let solutions = construct_all(inst, &constructors);
+  let (best_index, _) = solutions
+    .iter()
+    .enumerate()
+    .min_by_key(|&(i, solution)| (calculate_cmax(inst, solution), i))
+    .unwrap();
+
+  return (solutions[best_index].clone(), constructors[best_index]);
+}
+
+#[cfg(not(feature = "parallel"))]
+fn construct_all(inst: &Instance, constructors: &[Constructor]) -> Vec<Solution> {
+  return constructors
+    .iter()
+    .map(|constructor| constructor.construct(inst))
+    .collect();
+}
+
+// Distributed over the global rayon thread pool, collecting keeps the order of the constructors
+#[cfg(feature = "parallel")]
+fn construct_all(inst: &Instance, constructors: &[Constructor]) -> Vec<Solution> {
+  return constructors
+    .par_iter()
+    .map(|constructor| constructor.construct(inst))
+    .collect();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_instances::ft06;
+
+  #[test]
+  fn best_of_all_constructors() {
+    let inst = ft06();
+    let config = Config {
+      seed: 3,
+      random_constructions: 16,
+      init_bias: Some(DurationBias::Spt(2.0)),
+    };
+    let (solution, winner) = find_solution(&inst, &config);
+    let cmax = calculate_cmax(&inst, &solution);
+    assert_eq!(calculate_cmax(&inst, &winner.construct(&inst)), cmax);
+
+    let mut constructors: Vec<Constructor> = PriorityRule::ALL
+      .iter()
+      .map(|&rule| Constructor::Rule(rule))
+      .collect();
+    constructors.extend((3..19).map(|seed| Constructor::Random(seed, config.init_bias)));
+    let best = constructors
+      .iter()
+      .map(|constructor| calculate_cmax(&inst, &constructor.construct(&inst)))
+      .min()
+      .unwrap();
+    assert_eq!(cmax, best);
+    // The first constructor reaching the best cmax wins
+    let first = constructors
+      .iter()
+      .find(|constructor| calculate_cmax(&inst, &constructor.construct(&inst)) == best)
+      .unwrap();
+    assert_eq!(winner, *first);
+  }
+}
@@ -0,0 +1,141 @@
+use crate::data::{Instance, OpId, Solution};
+use crate::solver::calculate_cmax;
+use crate::solver::spec::PriorityRule;
+use ndarray::Array1;
+use std::cmp;
+
+// Exact search is exponential, larger instances are rejected by callers
+pub const MAX_OPS: usize = 40;
+
+// Exact solution by enumerating all active schedules (Giffler and Thompson), which contain an optimal one
+// The best dispatching rule solution is the initial upper bound
+pub fn find_optimal_solution(inst: &Instance) -> Solution {
+  let mut best = PriorityRule::ALL
+    .iter()
+    .map(|rule| rule.find_solution(inst))
+    .min_by_key(|solution| calculate_cmax(inst, solution))
+    .unwrap();
+
+  let mut remaining_job_work = Array1::<u32>::from_elem(inst.n_jobs, 0);
+  let mut remaining_machine_load = Array1::<u32>::from_elem(inst.n_machines, 0);
+  for op in 0..inst.n_ops() {
+    let [j, _o] = inst.op_from_id(op);
+    remaining_job_work[j] += inst.durations[op];
+    remaining_machine_load[inst.machines[op]] += inst.durations[op];
+  }
+
+  let mut state = State {
+    start_times: Array1::<u32>::from_elem(inst.n_ops(), 0),
+    next_op: Array1::<usize>::from_elem(inst.n_jobs, 0),
+    job_next_release: Array1::<u32>::from_elem(inst.n_jobs, 0),
+    machine_next_release: Array1::<u32>::from_elem(inst.n_machines, 0),
+    remaining_job_work: remaining_job_work,
+    remaining_machine_load: remaining_machine_load,
+    scheduled: 0,
+  };
+  let mut best_cmax = calculate_cmax(inst, &best);
+  branch(inst, &mut state, &mut best, &mut best_cmax);
+
+  return best;
+}
+
+struct State {
+  start_times: Array1<u32>,
+  next_op: Array1<usize>,
+  job_next_release: Array1<u32>,
+  machine_next_release: Array1<u32>,
+  remaining_job_work: Array1<u32>,
+  remaining_machine_load: Array1<u32>,
+  scheduled: usize,
+}
+
+impl State {
+  // No completion of the partial schedule can finish before this
+  fn lower_bound(&self) -> u32 {
+    let job_bound = (&self.job_next_release + &self.remaining_job_work)
+      .iter()
+      .copied()
+      .max()
+      .unwrap_or(0);
+    let machine_bound = (&self.machine_next_release + &self.remaining_machine_load)
+      .iter()
+      .copied()
+      .max()
+      .unwrap_or(0);
+    return cmp::max(job_bound, machine_bound);
+  }
+
+  fn earliest_start(&self, inst: &Instance, op: OpId) -> u32 {
+    let [j, _o] = inst.op_from_id(op);
+    return cmp::max(
+      self.job_next_release[j],
+      self.machine_next_release[inst.machines[op]],
+    );
+  }
+}
+
+fn branch(inst: &Instance, state: &mut State, best: &mut Solution, best_cmax: &mut u32) {
+  if state.scheduled == inst.n_ops() {
+    let cmax = state.lower_bound();
+    if cmax < *best_cmax {
+      *best_cmax = cmax;
+      *best = Solution {
+        start_times: state.start_times.clone(),
+      };
+    }
+    return;
+  }
+  if state.lower_bound() >= *best_cmax {
+    return;
+  }
+
+  let ready: Vec<OpId> = (0..inst.n_jobs)
+    .filter(|&j| state.next_op[j] < inst.job_len(j))
+    .map(|j| inst.op_to_id([j, state.next_op[j]]))
+    .collect();
+  // Operation completing earliest determines the conflicting machine
+  let (earliest_op, earliest_completion) = ready
+    .iter()
+    .map(|&op| (op, state.earliest_start(inst, op) + inst.durations[op]))
+    .min_by_key(|&(op, completion)| (completion, op))
+    .unwrap();
+  let machine = inst.machines[earliest_op];
+  let mut candidates: Vec<OpId> = ready
+    .into_iter()
+    .filter(|&op| {
+      inst.machines[op] == machine && state.earliest_start(inst, op) < earliest_completion
+    })
+    .collect();
+  // The earliest completing operation itself always qualifies (even with zero duration)
+  if !candidates.contains(&earliest_op) {
+    candidates.push(earliest_op);
+  }
+  candidates.sort_by_key(|&op| (state.earliest_start(inst, op), op));
+
+  for op in candidates {
+    let [j, _o] = inst.op_from_id(op);
+    let duration = inst.durations[op];
+    let start = state.earliest_start(inst, op);
+    let previous = (
+      state.job_next_release[j],
+      state.machine_next_release[machine],
+    );
+
+    state.start_times[op] = start;
+    state.next_op[j] += 1;
+    state.job_next_release[j] = start + duration;
+    state.machine_next_release[machine] = start + duration;
+    state.remaining_job_work[j] -= duration;
+    state.remaining_machine_load[machine] -= duration;
+    state.scheduled += 1;
+
+    branch(inst, state, best, best_cmax);
+
+    state.scheduled -= 1;
+    state.remaining_machine_load[machine] += duration;
+    state.remaining_job_work[j] += duration;
+    state.machine_next_release[machine] = previous.1;
+    state.job_next_release[j] = previous.0;
+    state.next_op[j] -= 1;
+  }
+}
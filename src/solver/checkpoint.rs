@@ -0,0 +1,325 @@
+use crate::data::{Edge, Instance};
+use crate::preprocess::machine_operations;
+use crate::solver::clock::Stopwatch;
+use crate::solver::{
+  check_machine_chains, is_acyclic_orientation, IntermediateSolution, SolveStats,
+};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct CheckpointConfig {
+  pub path: PathBuf,
+  // Minimum time between two successive checkpoints
+  pub interval: Duration,
+  // Name of the solver writing the checkpoints (e.g. tabu-search), only the same solver may resume them
+  pub solver: String,
+}
+
+// Format:
+// solver <name of the solver>
+// iterations <iterations>
+// elapsed_ms <elapsed milliseconds>
+// construction_ms <milliseconds spent generating start solutions>
+// search_ms <milliseconds spent evaluating and applying moves>
+// edges <n_edges>
+// followed by one oriented conflict edge <from> <to> per line
+pub fn save_checkpoint(
+  path: &Path,
+  solver: &str,
+  solution: &IntermediateSolution,
+  stats: &SolveStats,
+) -> Result<(), Box<dyn Error>> {
+  let mut lines = vec![
+    format!("solver {}", solver),
+    format!("iterations {}", stats.iterations),
+    format!("elapsed_ms {}", stats.elapsed.as_millis()),
+    format!("construction_ms {}", stats.construction.as_millis()),
+    format!("search_ms {}", stats.search.as_millis()),
+    format!("edges {}", solution.oriented_conflict_edges.len()),
+  ];
+  for &(a, b) in &solution.oriented_conflict_edges {
+    lines.push(format!("{} {}", a, b));
+  }
+
+  // Write to a temporary file first so that an interrupted write never corrupts the previous checkpoint
+  let tmp_path = path.with_extension("tmp");
+  fs::write(&tmp_path, lines.join("\n") + "\n")?;
+  fs::rename(&tmp_path, path)?;
+
+  return Ok(());
+}
+
+// Fails if the checkpoint was saved by another solver than the given one or if its orientation is not a complete acyclic
+// orientation of the instance
+// Checkpoints of older versions may lack the solver (then every solver accepts them) and the construction and search
+// times (then all of elapsed counts as search)
+pub fn load_checkpoint(
+  path: &Path,
+  inst: &Instance,
+  solver: &str,
+) -> Result<(IntermediateSolution, SolveStats), Box<dyn Error>> {
+  let contents = fs::read_to_string(path)?;
+  let mut lines = contents.lines();
+
+  // <key> <value> lines up to and including the number of edges
+  let mut header = HashMap::new();
+  loop {
+    let line = lines.next().ok_or("edges missing")?;
+    let items: Vec<&str> = line.split_whitespace().collect();
+    if items.len() != 2 {
+      Err(format!("Expected <key> <value>, found {:?}", line))?;
+    }
+    header.insert(items[0], items[1]);
+    if items[0] == "edges" {
+      break;
+    }
+  }
+  if let Some(&saved_solver) = header.get("solver") {
+    if saved_solver != solver {
+      Err(format!(
+        "Checkpoint was saved by {}, it cannot be resumed by {}",
+        saved_solver, solver
+      ))?;
+    }
+  }
+  // Value of a header line, the default if it is missing (an error without default)
+  let header_value = |key: &str, default: Option<u64>| -> Result<u64, Box<dyn Error>> {
+    return match (header.get(key), default) {
+      (Some(value), _) => Ok(value.parse()?),
+      (None, Some(default)) => Ok(default),
+      (None, None) => Err(format!("{} missing", key))?,
+    };
+  };
+  let iterations = header_value("iterations", None)?;
+  let elapsed_ms = header_value("elapsed_ms", None)?;
+  let construction_ms = header_value("construction_ms", Some(0))?;
+  let search_ms = header_value(
+    "search_ms",
+    Some(elapsed_ms.saturating_sub(construction_ms)),
+  )?;
+  let n_edges = header_value("edges", None)?;
+  // Checked before reading the edges, so a corrupt count cannot cause a huge allocation
+  let n_machine_arcs: usize = machine_operations(inst)
+    .iter()
+    .map(|ops| ops.len().saturating_sub(1))
+    .sum();
+  if n_edges != n_machine_arcs as u64 {
+    Err(format!(
+      "Expected {} edges for the instance, header has {}",
+      n_machine_arcs, n_edges
+    ))?;
+  }
+  let n_edges = n_machine_arcs;
+
+  let mut edges: Vec<Edge> = Vec::with_capacity(n_edges);
+  for line in lines.take(n_edges) {
+    let items: Vec<&str> = line.split_whitespace().collect();
+    let a: usize = items.first().ok_or("Edge source missing")?.parse()?;
+    let b: usize = items.get(1).ok_or("Edge target missing")?.parse()?;
+    edges.push((a, b));
+  }
+  if edges.len() != n_edges {
+    Err(format!("Expected {} edges, found {}", n_edges, edges.len()))?;
+  }
+  if inst.job_precedences.is_some() {
+    Err("Checkpoints of instances with job precedences are not supported")?;
+  }
+  check_machine_chains(inst, &edges)?;
+  if !is_acyclic_orientation(inst, &edges) {
+    Err("Edges contain a cycle with the job precedences")?;
+  }
+
+  let solution = IntermediateSolution::new(inst.clone(), edges);
+  let stats = SolveStats {
+    iterations: iterations,
+    elapsed: Duration::from_millis(elapsed_ms),
+    construction: Duration::from_millis(construction_ms),
+    search: Duration::from_millis(search_ms),
+    ..SolveStats::default()
+  };
+
+  return Ok((solution, stats));
+}
+
+// Periodically saves the best solution during a search
+pub struct Checkpointer<'a> {
+  config: Option<&'a CheckpointConfig>,
+  stopwatch: Stopwatch,
+  // Stopwatch time of the last save
+  last_save: Duration,
+}
+
+impl<'a> Checkpointer<'a> {
+  pub fn new(config: Option<&'a CheckpointConfig>, stopwatch: &Stopwatch) -> Self {
+    return Checkpointer {
+      config: config,
+      stopwatch: stopwatch.clone(),
+      last_save: stopwatch.elapsed(),
+    };
+  }
+
+  pub fn maybe_save(&mut self, best: &IntermediateSolution, stats: impl FnOnce() -> SolveStats) {
+    if let Some(config) = self.config {
+      if self
+        .stopwatch
+        .elapsed()
+        .checked_sub(self.last_save)
+        .unwrap_or_default()
+        >= config.interval
+      {
+        self.save(best, &stats());
+      }
+    }
+  }
+
+  pub fn save(&mut self, best: &IntermediateSolution, stats: &SolveStats) {
+    if let Some(config) = self.config {
+      // A failed checkpoint should not abort the search
+      match save_checkpoint(&config.path, &config.solver, best, stats) {
+        Ok(()) => debug!("Saved checkpoint at {} to {:?}", best.cmax(), config.path),
+        Err(e) => warn!("Failed to save checkpoint to {:?}: {}", config.path, e),
+      }
+      self.last_save = self.stopwatch.elapsed();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::priority;
+  use crate::test_instances::ft06;
+  use std::env;
+  use std::process;
+
+  fn checkpoint_path(name: &str) -> PathBuf {
+    return env::temp_dir().join(format!("heuristics-{}-{}.checkpoint", process::id(), name));
+  }
+
+  fn sps(inst: &Instance) -> IntermediateSolution {
+    return IntermediateSolution::from_solution(inst, &priority::find_solution_sps(inst, None))
+      .unwrap();
+  }
+
+  #[test]
+  fn save_and_load() {
+    let inst = ft06();
+    let solution = sps(&inst);
+    let stats = SolveStats {
+      iterations: 42,
+      elapsed: Duration::from_millis(1500),
+      construction: Duration::from_millis(500),
+      search: Duration::from_millis(900),
+      ..SolveStats::default()
+    };
+    let path = checkpoint_path("save-and-load");
+    save_checkpoint(&path, "tabu-search", &solution, &stats).unwrap();
+
+    let (loaded, loaded_stats) = load_checkpoint(&path, &inst, "tabu-search").unwrap();
+    assert_eq!(loaded.cmax(), solution.cmax());
+    assert_eq!(loaded.orientation_key(), solution.orientation_key());
+    assert_eq!(loaded_stats.iterations, 42);
+    assert_eq!(loaded_stats.elapsed, stats.elapsed);
+    assert_eq!(loaded_stats.construction, stats.construction);
+    assert_eq!(loaded_stats.search, stats.search);
+
+    assert!(load_checkpoint(&path, &inst, "simulated-annealing").is_err());
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn invalid_orientations_are_rejected() {
+    let inst = ft06();
+    let solution = sps(&inst);
+    let edges = solution.oriented_conflict_edges.clone();
+    let (a, b) = edges[0];
+    let c = edges[1].1;
+    let mut missing = edges.clone();
+    missing.remove(0);
+    let mut reversed = edges.clone();
+    reversed[0] = (b, a);
+    let mut branching = edges.clone();
+    branching.push((a, c));
+    let mut duplicate = edges.clone();
+    duplicate.push((a, b));
+
+    let path = checkpoint_path("invalid");
+    for invalid in &[missing, reversed, branching, duplicate] {
+      let mut lines = vec![
+        "solver tabu-search".to_string(),
+        "iterations 0".to_string(),
+        "elapsed_ms 0".to_string(),
+        "construction_ms 0".to_string(),
+        format!("edges {}", invalid.len()),
+      ];
+      lines.extend(invalid.iter().map(|(a, b)| format!("{} {}", a, b)));
+      fs::write(&path, lines.join("\n")).unwrap();
+      assert!(
+        load_checkpoint(&path, &inst, "tabu-search").is_err(),
+        "{:?}",
+        invalid
+      );
+    }
+
+    let edges = &solution.oriented_conflict_edges;
+    let mut lines = vec![
+      "solver tabu-search".to_string(),
+      "iterations 0".to_string(),
+      "elapsed_ms 0".to_string(),
+      "edges 99999999999999999".to_string(),
+    ];
+    lines.extend(edges.iter().map(|(a, b)| format!("{} {}", a, b)));
+    fs::write(&path, lines.join("\n")).unwrap();
+    let error = load_checkpoint(&path, &inst, "tabu-search").unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "Expected 30 edges for the instance, header has 99999999999999999"
+    );
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn cyclic_orientations_are_rejected() {
+    // Job 0 visits machine 0 first and job 1 machine 1 first, so each machine ordering the other job first is a cycle
+    let inst = Instance::from_jobs(2, &[vec![(0, 3), (1, 2)], vec![(1, 2), (0, 4)]]);
+    let path = checkpoint_path("cyclic");
+    fs::write(
+      &path,
+      "iterations 0\nelapsed_ms 0\nconstruction_ms 0\nedges 2\n3 0\n1 2\n",
+    )
+    .unwrap();
+    let error = load_checkpoint(&path, &inst, "tabu-search").err().unwrap();
+    assert!(error.to_string().contains("cycle"), "{}", error);
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn checkpoints_without_times() {
+    let inst = ft06();
+    let solution = sps(&inst);
+    let mut lines = vec![
+      "iterations 7".to_string(),
+      "elapsed_ms 1200".to_string(),
+      format!("edges {}", solution.oriented_conflict_edges.len()),
+    ];
+    lines.extend(
+      solution
+        .oriented_conflict_edges
+        .iter()
+        .map(|(a, b)| format!("{} {}", a, b)),
+    );
+    let path = checkpoint_path("without-times");
+    fs::write(&path, lines.join("\n")).unwrap();
+    let (loaded, stats) = load_checkpoint(&path, &inst, "simulated-annealing").unwrap();
+    assert_eq!(loaded.cmax(), solution.cmax());
+    assert_eq!(stats.iterations, 7);
+    assert_eq!(stats.construction, Duration::from_millis(0));
+    assert_eq!(stats.search, Duration::from_millis(1200));
+    fs::remove_file(&path).unwrap();
+  }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+// Source of time for timeouts and statistics, allows running without std::time (e.g. on wasm)
+pub trait Clock: Send + Sync {
+  // Time since an arbitrary but fixed epoch
+  fn now(&self) -> Duration;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct SystemClock {
+  epoch: Instant,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SystemClock {
+  pub fn new() -> Self {
+    return SystemClock {
+      epoch: Instant::now(),
+    };
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for SystemClock {
+  fn default() -> Self {
+    return SystemClock::new();
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Clock for SystemClock {
+  fn now(&self) -> Duration {
+    return self.epoch.elapsed();
+  }
+}
+
+// Never advances, only useful together with an iteration limit
+pub struct FrozenClock;
+
+impl Clock for FrozenClock {
+  fn now(&self) -> Duration {
+    return Duration::from_secs(0);
+  }
+}
+
+// Clock used if none is injected
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_clock() -> Arc<dyn Clock> {
+  return Arc::new(SystemClock::new());
+}
+
+// Instant is not available on wasm, so without an injected clock only iteration limits stop a search
+#[cfg(target_arch = "wasm32")]
+pub fn default_clock() -> Arc<dyn Clock> {
+  return Arc::new(FrozenClock);
+}
+
+// Measures the time since its creation on a clock
+#[derive(Clone)]
+pub struct Stopwatch {
+  clock: Arc<dyn Clock>,
+  start: Duration,
+}
+
+impl Stopwatch {
+  pub fn start(clock: Option<&Arc<dyn Clock>>) -> Self {
+    let clock = clock.cloned().unwrap_or_else(default_clock);
+    let start = clock.now();
+    return Stopwatch {
+      clock: clock,
+      start: start,
+    };
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    return self.clock.now().checked_sub(self.start).unwrap_or_default();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::neighborhood::NeighborhoodKind;
+  use crate::solver::spec::{solve_with_budget, Budget, SolverSpec};
+  use crate::test_instances::{ft06, StepClock};
+
+  #[test]
+  fn simulated_timeout() {
+    let inst = ft06();
+    let spec = SolverSpec::TabuSearch {
+      seed: 0,
+      neighborhood: NeighborhoodKind::N1,
+      skip_symmetric_swaps: false,
+      secondary_objective: None,
+      constraint: None,
+      verify: false,
+    };
+    let timeout = Duration::from_millis(500);
+    let run = || {
+      let budget = Budget::Time {
+        timeout: timeout,
+        clock: Some(Arc::new(StepClock::new())),
+      };
+      solve_with_budget(&inst, &spec, budget).unwrap().1.unwrap()
+    };
+    let stats = run();
+    assert!(stats.elapsed >= timeout);
+    assert!(stats.iterations > 0);
+    // Every iteration reads the clock at least once
+    assert!(stats.iterations <= 500);
+    assert_eq!(run().iterations, stats.iterations);
+  }
+}
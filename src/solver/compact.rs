@@ -0,0 +1,307 @@
+use crate::data::{Edge, Instance, OpId, Solution};
+use crate::preprocess::is_forced_pair;
+use crate::solver::neighborhood::EvaluatedMove;
+use crate::solver::{get_pre_succ_relations, get_precedence_edges, IntermediateSolution};
+use ndarray::Array1;
+use std::cmp;
+use std::sync::Arc;
+
+// Job and machine neighbors and times of an operation
+#[derive(Debug, Clone, Copy)]
+struct Node {
+  pre_job: Option<OpId>,
+  succ_job: Option<OpId>,
+  pre_machine: Option<OpId>,
+  succ_machine: Option<OpId>,
+  release: u32,
+  // Including the duration of the operation
+  tail: u32,
+}
+
+// Alternative to IntermediateSolution for large instances that is updated in place: the instance is shared, all
+// per-operation data lives in one buffer and swaps recompute the times into reused buffers, so neither evaluating nor
+// applying a swap allocates
+// Like IntermediateSolution it requires linear job chains and an orientation of machine-adjacent operations
+#[derive(Debug, Clone)]
+pub struct CompactSolution {
+  instance: Arc<Instance>,
+  nodes: Vec<Node>,
+  // Swaps keep the position of every arc (as IntermediateSolution::swapped_orientation)
+  oriented_conflict_edges: Vec<Edge>,
+  // Position of the arc to the machine successor of every operation
+  arc_index: Vec<Option<usize>>,
+  cmax: u32,
+  // Buffers of the recomputes
+  order: Vec<OpId>,
+  in_degree: Vec<u8>,
+  release: Vec<u32>,
+}
+
+impl CompactSolution {
+  // Panics if the orientation contains a cycle
+  pub fn new(instance: Arc<Instance>, oriented_conflict_edges: Vec<Edge>) -> Self {
+    assert!(
+      instance.job_precedences.is_none(),
+      "CompactSolution does not support explicit job precedences"
+    );
+    let n_ops = instance.n_ops();
+    let (pre_job, succ_job) = get_pre_succ_relations(&instance, &get_precedence_edges(&instance));
+    let (pre_machine, succ_machine) = get_pre_succ_relations(&instance, &oriented_conflict_edges);
+    let nodes = (0..n_ops)
+      .map(|op| Node {
+        pre_job: pre_job[op],
+        succ_job: succ_job[op],
+        pre_machine: pre_machine[op],
+        succ_machine: succ_machine[op],
+        release: 0,
+        tail: 0,
+      })
+      .collect();
+    let mut arc_index = vec![None; n_ops];
+    for (i, &(a, _)) in oriented_conflict_edges.iter().enumerate() {
+      arc_index[a] = Some(i);
+    }
+
+    let mut solution = CompactSolution {
+      instance: instance,
+      nodes: nodes,
+      oriented_conflict_edges: oriented_conflict_edges,
+      arc_index: arc_index,
+      cmax: 0,
+      order: Vec::with_capacity(n_ops),
+      in_degree: vec![0; n_ops],
+      release: vec![0; n_ops],
+    };
+    assert!(solution.label(), "Orientation contains a cycle");
+    return solution;
+  }
+
+  pub fn from_intermediate(solution: &IntermediateSolution) -> Self {
+    return CompactSolution::new(
+      Arc::new(solution.instance.clone()),
+      solution.oriented_conflict_edges.clone(),
+    );
+  }
+
+  pub fn to_intermediate(&self) -> IntermediateSolution {
+    return IntermediateSolution::new(
+      (*self.instance).clone(),
+      self.oriented_conflict_edges.clone(),
+    );
+  }
+
+  pub fn to_solution(&self) -> Solution {
+    return Solution {
+      start_times: self.nodes.iter().map(|node| node.release).collect(),
+    };
+  }
+
+  pub fn cmax(&self) -> u32 {
+    return self.cmax;
+  }
+
+  pub fn instance(&self) -> &Arc<Instance> {
+    return &self.instance;
+  }
+
+  pub fn oriented_conflict_edges(&self) -> &[Edge] {
+    return &self.oriented_conflict_edges;
+  }
+
+  pub fn release_times(&self) -> Array1<u32> {
+    return self.nodes.iter().map(|node| node.release).collect();
+  }
+
+  pub fn tail_times(&self) -> Array1<u32> {
+    return self.nodes.iter().map(|node| node.tail).collect();
+  }
+
+  // Sorted arcs, equal to IntermediateSolution::orientation_key of the same orientation
+  pub fn orientation_key(&self) -> Vec<Edge> {
+    let mut edges = self.oriented_conflict_edges.clone();
+    edges.sort_unstable();
+    return edges;
+  }
+
+  // Moves of n1::generate_moves_full in the same order and with the same cmax estimates
+  pub fn generate_moves_full(&mut self, skip_symmetric_swaps: bool) -> Vec<EvaluatedMove> {
+    let has_zero_durations = self.instance.durations.iter().any(|&d| d == 0);
+
+    let mut moves = Vec::new();
+    for i in 0..self.oriented_conflict_edges.len() {
+      let (a, b) = self.oriented_conflict_edges[i];
+      if is_forced_pair(&self.instance, a, b)
+        || !self.is_critical(a)
+        || !self.is_critical(b)
+        || self.end(Some(a)) != self.nodes[b].release
+      {
+        continue;
+      }
+      if has_zero_durations && self.evaluate_swap(a, b).is_none() {
+        continue;
+      }
+      if skip_symmetric_swaps && self.is_symmetric_swap(a, b) {
+        continue;
+      }
+
+      moves.push(EvaluatedMove {
+        swap_move: (a, b),
+        cmax: self.cmax_after_swap(a, b),
+      });
+    }
+    return moves;
+  }
+
+  // Exact cmax after swapping the machine-adjacent operations a -> b, the solution is unchanged afterwards
+  // Returns None if b is not the machine successor of a or if the swap would introduce a cycle
+  pub fn evaluate_swap(&mut self, a: OpId, b: OpId) -> Option<u32> {
+    if self.nodes[a].succ_machine != Some(b) {
+      return None;
+    }
+    self.relink(a, b);
+    let cmax = self.label_release();
+    self.relink(b, a);
+    return cmax;
+  }
+
+  // Swaps the machine-adjacent operations a -> b in place, returns false (leaving the solution unchanged) if b is not
+  // the machine successor of a or if the swap would introduce a cycle
+  pub fn swap(&mut self, a: OpId, b: OpId) -> bool {
+    if self.nodes[a].succ_machine != Some(b) {
+      return false;
+    }
+    let pre_a = self.nodes[a].pre_machine;
+    let succ_b = self.nodes[b].succ_machine;
+    self.relink(a, b);
+    // A failed labeling leaves the times untouched
+    if !self.label() {
+      self.relink(b, a);
+      return false;
+    }
+
+    // (p, a) -> (p, b), (a, b) -> (b, a) and (b, s) -> (a, s) at the same positions
+    let (ab_index, bs_index) = (self.arc_index[a], self.arc_index[b]);
+    if let Some(p) = pre_a {
+      let index = self.arc_index[p].unwrap();
+      self.oriented_conflict_edges[index] = (p, b);
+    }
+    self.oriented_conflict_edges[ab_index.unwrap()] = (b, a);
+    if let Some(s) = succ_b {
+      self.oriented_conflict_edges[bs_index.unwrap()] = (a, s);
+    }
+    self.arc_index[b] = ab_index;
+    self.arc_index[a] = bs_index;
+    return true;
+  }
+
+  fn is_critical(&self, op: OpId) -> bool {
+    return self.nodes[op].release + self.nodes[op].tail == self.cmax;
+  }
+
+  fn end(&self, op: Option<OpId>) -> u32 {
+    return op.map_or(0, |op| self.nodes[op].release + self.instance.durations[op]);
+  }
+
+  fn tail(&self, op: Option<OpId>) -> u32 {
+    return op.map_or(0, |op| self.nodes[op].tail);
+  }
+
+  // As IntermediateSolution::is_symmetric_swap
+  fn is_symmetric_swap(&self, a: OpId, b: OpId) -> bool {
+    if self.instance.durations[a] != self.instance.durations[b] {
+      return false;
+    }
+    let (node_a, node_b) = (self.nodes[a], self.nodes[b]);
+    let pre_machine_a_end = self.end(node_a.pre_machine);
+    return cmp::max(self.end(node_a.pre_job), pre_machine_a_end)
+      == cmp::max(self.end(node_b.pre_job), pre_machine_a_end)
+      && self.tail(node_a.succ_job) == self.tail(node_b.succ_job);
+  }
+
+  // Estimate of IntermediateSolution::cmax_after_swap, exact if a critical path passes through a or b
+  fn cmax_after_swap(&self, a: OpId, b: OpId) -> u32 {
+    let durations = &self.instance.durations;
+    let (node_a, node_b) = (self.nodes[a], self.nodes[b]);
+    let b_new_release = cmp::max(self.end(node_a.pre_machine), self.end(node_b.pre_job));
+    let a_new_release = cmp::max(b_new_release + durations[b], self.end(node_a.pre_job));
+    let a_new_tail =
+      cmp::max(self.tail(node_b.succ_machine), self.tail(node_a.succ_job)) + durations[a];
+    let b_new_tail = cmp::max(a_new_tail, self.tail(node_b.succ_job)) + durations[b];
+    return cmp::max(b_new_release + b_new_tail, a_new_release + a_new_tail);
+  }
+
+  // Machine links after swapping the machine-adjacent a -> b, the times are not updated
+  fn relink(&mut self, a: OpId, b: OpId) {
+    let pre_a = self.nodes[a].pre_machine;
+    let succ_b = self.nodes[b].succ_machine;
+    if let Some(p) = pre_a {
+      self.nodes[p].succ_machine = Some(b);
+    }
+    if let Some(s) = succ_b {
+      self.nodes[s].pre_machine = Some(a);
+    }
+    self.nodes[a].pre_machine = Some(b);
+    self.nodes[a].succ_machine = succ_b;
+    self.nodes[b].pre_machine = pre_a;
+    self.nodes[b].succ_machine = Some(a);
+  }
+
+  // Release times into the release buffer in topological order (kept in the order buffer), returns cmax or None if
+  // the links contain a cycle
+  fn label_release(&mut self) -> Option<u32> {
+    let durations = &self.instance.durations;
+    let nodes = &self.nodes;
+    let (order, in_degree, release) = (&mut self.order, &mut self.in_degree, &mut self.release);
+    order.clear();
+    for (op, node) in nodes.iter().enumerate() {
+      in_degree[op] = node.pre_job.is_some() as u8 + node.pre_machine.is_some() as u8;
+      if in_degree[op] == 0 {
+        order.push(op);
+      }
+    }
+
+    let mut cmax = 0;
+    let mut next = 0;
+    while next < order.len() {
+      let op = order[next];
+      next += 1;
+      let end = |pre: Option<OpId>| pre.map_or(0, |pre| release[pre] + durations[pre]);
+      let op_release = cmp::max(end(nodes[op].pre_job), end(nodes[op].pre_machine));
+      release[op] = op_release;
+      cmax = cmp::max(cmax, op_release + durations[op]);
+      for &succ in [nodes[op].succ_job, nodes[op].succ_machine]
+        .iter()
+        .flatten()
+      {
+        in_degree[succ] -= 1;
+        if in_degree[succ] == 0 {
+          order.push(succ);
+        }
+      }
+    }
+
+    if order.len() < nodes.len() {
+      return None;
+    }
+    return Some(cmax);
+  }
+
+  // Release and tail times and cmax of the current links, false if they contain a cycle
+  fn label(&mut self) -> bool {
+    let cmax = match self.label_release() {
+      Some(cmax) => cmax,
+      None => return false,
+    };
+    let durations = &self.instance.durations;
+    let nodes = &mut self.nodes;
+    for &op in self.order.iter().rev() {
+      let node = nodes[op];
+      let tail = |succ: Option<OpId>| succ.map_or(0, |succ| nodes[succ].tail);
+      let op_tail = durations[op] + cmp::max(tail(node.succ_job), tail(node.succ_machine));
+      nodes[op].release = self.release[op];
+      nodes[op].tail = op_tail;
+    }
+    self.cmax = cmax;
+    return true;
+  }
+}
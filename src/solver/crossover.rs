@@ -0,0 +1,181 @@
+use crate::data::{Edge, Instance, OpId};
+use crate::solver::get_precedence_edges;
+use ndarray::Array1;
+use rand::Rng;
+use std::collections::VecDeque;
+
+// Recombination operators for the permutation with repetition representation, see
+// Bierwirth, Mattfeld, and Kopfer, “On Permutation Representations for Scheduling Problems.”
+// Orientations are converted to a sequence of job ids (the k-th occurrence of a job refers to its k-th operation),
+// recombined and converted back, so every child is a feasible (acyclic) orientation
+
+// Job of every operation in a topological order of the given (acyclic) orientation
+pub fn operation_sequence(inst: &Instance, orientation: &[Edge]) -> Vec<usize> {
+  let mut in_degree = Array1::<usize>::from_elem(inst.n_ops(), 0);
+  let mut succs = Array1::from_elem(inst.n_ops(), Vec::new());
+  for &(a, b) in get_precedence_edges(inst).iter().chain(orientation) {
+    in_degree[b] += 1;
+    succs[a].push(b);
+  }
+
+  let mut open: VecDeque<OpId> = (0..inst.n_ops()).filter(|&op| in_degree[op] == 0).collect();
+  let mut sequence = Vec::with_capacity(inst.n_ops());
+  while let Some(op) = open.pop_front() {
+    sequence.push(inst.op_from_id(op)[0]);
+    for &succ in &succs[op] {
+      in_degree[succ] -= 1;
+      if in_degree[succ] == 0 {
+        open.push_back(succ);
+      }
+    }
+  }
+  assert!(
+    sequence.len() == inst.n_ops(),
+    "Orientation contains a cycle"
+  );
+
+  return sequence;
+}
+
+// Orders each machine's operations as they occur in the sequence
+pub fn orientation_from_operation_sequence(inst: &Instance, sequence: &[usize]) -> Vec<Edge> {
+  let mut next_op = Array1::<usize>::from_elem(inst.n_jobs, 0);
+  let mut last_on_machine = Array1::<Option<OpId>>::from_elem(inst.n_machines, None);
+  let mut edges = Vec::new();
+  for &job in sequence {
+    let op = inst.op_to_id([job, next_op[job]]);
+    next_op[job] += 1;
+
+    let m = inst.machines[op];
+    if let Some(last) = last_on_machine[m] {
+      edges.push((last, op));
+    }
+    last_on_machine[m] = Some(op);
+  }
+
+  return edges;
+}
+
+// Generalized order crossover: a substring of a is implanted into b at the position of its first gene in b
+pub fn gox<R: Rng>(
+  inst: &Instance,
+  parent_a: &[Edge],
+  parent_b: &[Edge],
+  rng: &mut R,
+) -> Vec<Edge> {
+  let sequence_a = operation_sequence(inst, parent_a);
+  let sequence_b = operation_sequence(inst, parent_b);
+  let n = sequence_a.len();
+  if n == 0 {
+    return Vec::new();
+  }
+
+  // Substring of roughly a third to a half of the genes
+  let min_len = (n / 3).max(1);
+  let max_len = (n / 2).max(min_len);
+  let len = rng.gen_range(min_len, max_len + 1);
+  let start = rng.gen_range(0, n - len + 1);
+
+  // Genes are identified by (job, occurrence)
+  let occurrences = |sequence: &[usize]| {
+    let mut counts = vec![0; inst.n_jobs];
+    sequence
+      .iter()
+      .map(|&job| {
+        counts[job] += 1;
+        (job, counts[job] - 1)
+      })
+      .collect::<Vec<_>>()
+  };
+  let genes_a = occurrences(&sequence_a);
+  let genes_b = occurrences(&sequence_b);
+  let implant = &genes_a[start..start + len];
+
+  let mut implanted = vec![Vec::new(); inst.n_jobs];
+  for &(job, occurrence) in implant {
+    implanted[job].push(occurrence);
+  }
+  let insert_at = genes_b.iter().position(|gene| *gene == implant[0]).unwrap();
+
+  let mut child = Vec::with_capacity(n);
+  for (i, &(job, occurrence)) in genes_b.iter().enumerate() {
+    if i == insert_at {
+      child.extend(implant.iter().map(|&(job, _)| job));
+    }
+    if !implanted[job].contains(&occurrence) {
+      child.push(job);
+    }
+  }
+
+  return orientation_from_operation_sequence(inst, &child);
+}
+
+// Precedence preservation crossover: genes are taken from the front of a randomly chosen parent and removed from both
+pub fn ppx<R: Rng>(
+  inst: &Instance,
+  parent_a: &[Edge],
+  parent_b: &[Edge],
+  rng: &mut R,
+) -> Vec<Edge> {
+  let mut sequence_a: VecDeque<usize> = operation_sequence(inst, parent_a).into();
+  let mut sequence_b: VecDeque<usize> = operation_sequence(inst, parent_b).into();
+
+  let mut child = Vec::with_capacity(sequence_a.len());
+  // Both parents always contain the same genes
+  while !sequence_a.is_empty() {
+    let job = if rng.gen::<bool>() {
+      sequence_a[0]
+    } else {
+      sequence_b[0]
+    };
+    child.push(job);
+    // Remove the leftmost occurrence of the job from both parents
+    for sequence in [&mut sequence_a, &mut sequence_b].iter_mut() {
+      let position = sequence.iter().position(|&j| j == job).unwrap();
+      sequence.remove(position);
+    }
+  }
+
+  return orientation_from_operation_sequence(inst, &child);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{
+    check_machine_chains, generate_random_solution, is_acyclic_orientation, seeded_rng,
+    IntermediateSolution,
+  };
+  use crate::test_instances::ft06;
+
+  #[test]
+  fn children_are_feasible() {
+    // Job 0 visits machine 0 twice, job 2 has a single operation
+    let revisiting = Instance::from_jobs(
+      2,
+      &[
+        vec![(0, 2), (1, 1), (0, 3)],
+        vec![(1, 2), (0, 1)],
+        vec![(1, 4)],
+      ],
+    );
+    let mut rng = seeded_rng(0);
+    for inst in &[ft06(), revisiting] {
+      let mut random_parent = || {
+        let solution = generate_random_solution(inst, &mut rng);
+        IntermediateSolution::from_solution(inst, &solution)
+          .unwrap()
+          .orientation_key()
+      };
+      let parents: Vec<Vec<Edge>> = (0..20).map(|_| random_parent()).collect();
+      for (a, b) in parents.iter().zip(parents.iter().skip(1)) {
+        for child in &[gox(inst, a, b, &mut rng), ppx(inst, a, b, &mut rng)] {
+          check_machine_chains(inst, child).unwrap();
+          assert!(is_acyclic_orientation(inst, child));
+          let solution = IntermediateSolution::new(inst.clone(), child.clone());
+          assert_eq!(solution.orientation_key().len(), a.len());
+        }
+      }
+    }
+  }
+}
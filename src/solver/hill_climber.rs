@@ -1,40 +1,563 @@
-use crate::data::{Instance, Solution};
-use crate::solver::{get_orientation_from_schedule, n1, IntermediateSolution};
+use crate::data::{Edge, Instance, Solution};
+use crate::solver::clock::{Clock, Stopwatch};
+use crate::solver::compact::CompactSolution;
+use crate::solver::neighborhood::{
+  CandidateOrder, Neighborhood, NeighborhoodKind, SearchMethod, TieBreaker,
+};
+use crate::solver::objective::{
+  compare_solutions, compare_tied_moves, is_better, violation, Constraint, Objective,
+};
+use crate::solver::{seeded_rng, verify_step, IntermediateSolution};
 use log::trace;
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub fn improve_solution(inst: &Instance, initial_solution: &Solution) -> IntermediateSolution {
-  let mut current_solution = IntermediateSolution::new(
-    inst.clone(),
-    get_orientation_from_schedule(&inst, &initial_solution),
-  );
+#[derive(Debug, Clone, Default)]
+pub struct HcConfig {
+  // Accept moves that leave cmax unchanged when no improving move exists
+  pub allow_plateau: bool,
+  // Maximum number of consecutive moves without improvement
+  pub max_plateau_steps: usize,
+  pub neighborhood: NeighborhoodKind,
+  // See NeighborhoodKind::resolve, such swaps are never improvements, only plateau moves
+  pub skip_symmetric_swaps: bool,
+  // Breaks ties in cmax, so moves that keep cmax but lower this objective also count as improvements
+  pub secondary_objective: Option<Objective>,
+  // Only moves to solutions satisfying the constraint, infeasible solutions are first improved towards feasibility
+  pub constraint: Option<Constraint>,
+  // Verify every intermediate solution (expensive)
+  pub verify: bool,
+  // Probability of moving to a uniformly random neighbor regardless of its cmax instead of the best improving move, so
+  // the hill climber becomes a random walk baseline (only improve_solution, 0 is the plain hill climber)
+  pub random_walk_probability: f64,
+  // Seed of the random walk decisions
+  pub seed: u64,
+  // Stop after this long even without reaching a local optimum, a random walk requires this or max_steps
+  pub timeout: Option<Duration>,
+  // Stop after this many moves (improvements, plateau and walk steps) even without reaching a local optimum
+  pub max_steps: Option<u64>,
+}
+
+impl HcConfig {
+  // A random walk continues from local optima, so it needs a timeout or a step limit to stop
+  pub fn validate(&self) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&self.random_walk_probability) {
+      Err(format!(
+        "Invalid random walk probability {}: has to be between 0 and 1",
+        self.random_walk_probability
+      ))?;
+    }
+    if self.random_walk_probability > 0.0 && self.timeout.is_none() && self.max_steps.is_none() {
+      Err("A random walk requires a timeout or a step limit")?;
+    }
+    return Ok(());
+  }
+}
+
+// Fails if the config is invalid (see HcConfig::validate) or the machine sequences of the initial solution contain a
+// cycle
+pub fn improve_solution(
+  inst: &Instance,
+  initial_solution: &Solution,
+  config: &HcConfig,
+) -> Result<IntermediateSolution, String> {
+  return improve_solution_on_clock(inst, initial_solution, config, None);
+}
+
+// Like improve_solution, measuring the timeout on the given clock (the system clock if None)
+pub(crate) fn improve_solution_on_clock(
+  inst: &Instance,
+  initial_solution: &Solution,
+  config: &HcConfig,
+  clock: Option<&Arc<dyn Clock>>,
+) -> Result<IntermediateSolution, String> {
+  config.validate()?;
+  let start = Stopwatch::start(clock);
+  let mut rng = seeded_rng(config.seed);
+  let mut current_solution =
+    IntermediateSolution::from_solution(inst, initial_solution).map_err(|e| e.to_string())?;
+  // The walk may leave the best visited solution
+  let mut best_solution = current_solution.clone();
+
+  let neighborhood = config.neighborhood.resolve(config.skip_symmetric_swaps);
+  let mut plateau_steps = 0;
+  let mut plateau_visited = HashSet::new();
+  let mut steps = 0;
 
   trace!("Starting with {}", current_solution.cmax());
   loop {
-    let maybe_move = n1::find_move(
-      &current_solution,
-      &mut |maybe_best, candidate| {
-        if let Some(best) = maybe_best {
-          candidate.cmax < best.cmax
-        } else {
-          true
-        }
-      },
-      n1::SearchMethod::Exhaustive,
-    );
-    let maybe_improvement = maybe_move.filter(|m| m.cmax < current_solution.cmax());
-
-    if let Some(next_move) = maybe_improvement {
-      let swap_move = next_move.swap_move;
-      current_solution = current_solution.apply_swap(swap_move.0, swap_move.1);
-      trace!("Found improvement to {}", current_solution.cmax());
-    } else {
+    if config
+      .timeout
+      .map_or(false, |timeout| start.elapsed() >= timeout)
+    {
+      trace!("Stopping due to timeout at {}", current_solution.cmax());
+      break;
+    }
+    if config
+      .max_steps
+      .map_or(false, |max_steps| steps >= max_steps)
+    {
       trace!(
-        "Did not find improvement, stopping at {}",
+        "Stopping after {} steps at {}",
+        steps,
         current_solution.cmax()
       );
       break;
     }
+    steps += 1;
+
+    // No random number is drawn without random walk, so the plain hill climber is unchanged
+    let mut walk = config.random_walk_probability > 0.0
+      && rng.gen_range(0.0, 1.0) < config.random_walk_probability;
+    if !walk {
+      if let Some(next_solution) =
+        find_improvement(&current_solution, neighborhood.as_ref(), config)
+      {
+        current_solution = next_solution;
+        plateau_steps = 0;
+        plateau_visited.clear();
+        trace!("Found improvement to {}", current_solution.cmax());
+      } else if let Some(next_solution) = find_plateau_step(
+        &current_solution,
+        neighborhood.as_ref(),
+        config,
+        &mut plateau_steps,
+        &mut plateau_visited,
+      ) {
+        current_solution = next_solution;
+        trace!(
+          "Moved along plateau at {} ({})",
+          current_solution.cmax(),
+          plateau_steps
+        );
+      } else if config.random_walk_probability > 0.0 {
+        // The walk leaves local optima until the timeout
+        walk = true;
+      } else {
+        trace!(
+          "Did not find improvement, stopping at {}",
+          current_solution.cmax()
+        );
+        break;
+      }
+    }
+
+    if walk {
+      let moves = neighborhood.generate_moves(&current_solution);
+      if moves.is_empty() {
+        trace!(
+          "No neighbor to walk to, stopping at {}",
+          current_solution.cmax()
+        );
+        break;
+      }
+      let (a, b) = moves[rng.gen_range(0, moves.len())].swap_move;
+      current_solution = current_solution.apply_swap(a, b);
+      plateau_steps = 0;
+      plateau_visited.clear();
+      trace!("Walked to {}", current_solution.cmax());
+    }
+
+    verify_step(config.verify, inst, || current_solution.to_solution());
+    if config.random_walk_probability > 0.0
+      && is_better(
+        &current_solution,
+        &best_solution,
+        config.secondary_objective,
+        config.constraint,
+      )
+    {
+      best_solution = current_solution.clone();
+    }
+  }
+
+  if config.random_walk_probability > 0.0 {
+    return Ok(best_solution);
+  }
+  return Ok(current_solution);
+}
+
+// Same climb (with the same trajectory) on a CompactSolution, which evaluates and applies the swaps in place
+// Only supports the n1-full neighborhood without constraint, secondary objective or random walk
+pub fn improve_solution_compact(
+  inst: &Instance,
+  initial_solution: &Solution,
+  config: &HcConfig,
+) -> Result<CompactSolution, String> {
+  if config.neighborhood != NeighborhoodKind::N1Full {
+    Err(format!(
+      "A compact hill climber requires the n1-full neighborhood, not {:?}",
+      config.neighborhood
+    ))?;
+  }
+  if config.constraint.is_some()
+    || config.secondary_objective.is_some()
+    || config.random_walk_probability > 0.0
+  {
+    Err("A compact hill climber supports no constraint, secondary objective or random walk")?;
+  }
+  let start = Stopwatch::start(None);
+  let initial =
+    IntermediateSolution::from_solution(inst, initial_solution).map_err(|e| e.to_string())?;
+  let mut current = CompactSolution::new(Arc::new(inst.clone()), initial.oriented_conflict_edges);
+  let mut plateau_steps = 0;
+  let mut plateau_visited = HashSet::new();
+  let mut steps = 0;
+
+  trace!("Starting with {}", current.cmax());
+  loop {
+    if config
+      .timeout
+      .map_or(false, |timeout| start.elapsed() >= timeout)
+    {
+      trace!("Stopping due to timeout at {}", current.cmax());
+      break;
+    }
+    if config
+      .max_steps
+      .map_or(false, |max_steps| steps >= max_steps)
+    {
+      trace!("Stopping after {} steps at {}", steps, current.cmax());
+      break;
+    }
+    steps += 1;
+
+    if find_improvement_compact(&mut current, config) {
+      plateau_steps = 0;
+      plateau_visited.clear();
+      trace!("Found improvement to {}", current.cmax());
+    } else if find_plateau_step_compact(
+      &mut current,
+      config,
+      &mut plateau_steps,
+      &mut plateau_visited,
+    ) {
+      trace!(
+        "Moved along plateau at {} ({})",
+        current.cmax(),
+        plateau_steps
+      );
+    } else {
+      trace!("Did not find improvement, stopping at {}", current.cmax());
+      break;
+    }
+
+    verify_step(config.verify, inst, || current.to_solution());
   }
 
-  return current_solution;
+  return Ok(current);
+}
+
+// Best strictly improving move (lexicographically if a secondary objective is given)
+pub(crate) fn find_improvement(
+  current_solution: &IntermediateSolution,
+  neighborhood: &dyn Neighborhood,
+  config: &HcConfig,
+) -> Option<IntermediateSolution> {
+  let secondary_objective = config.secondary_objective;
+  if let Some(constraint) = config.constraint {
+    // The violation of a move is only known after applying it
+    return neighborhood
+      .generate_moves(current_solution)
+      .iter()
+      .map(|m| current_solution.apply_swap(m.swap_move.0, m.swap_move.1))
+      .min_by(|a, b| compare_solutions(a, b, secondary_objective, Some(constraint)))
+      .filter(|next_solution| {
+        is_better(
+          next_solution,
+          current_solution,
+          secondary_objective,
+          Some(constraint),
+        )
+      });
+  }
+
+  let maybe_move = neighborhood.find_move(
+    current_solution,
+    &mut |maybe_best, candidate| {
+      if let Some(best) = maybe_best {
+        candidate.cmax < best.cmax
+          || (candidate.cmax == best.cmax
+            && compare_tied_moves(current_solution, candidate, best, secondary_objective)
+              == Ordering::Less)
+      } else {
+        true
+      }
+    },
+    SearchMethod::Exhaustive,
+    CandidateOrder::Generated,
+    &mut TieBreaker::default(),
+  );
+
+  // Without secondary objective the move estimate decides, otherwise the tie break needs the exact result
+  let maybe_candidate = maybe_move.filter(|m| match secondary_objective {
+    None => m.cmax < current_solution.cmax(),
+    Some(_) => m.cmax <= current_solution.cmax(),
+  });
+
+  return maybe_candidate
+    .map(|next_move| {
+      let swap_move = next_move.swap_move;
+      current_solution.apply_swap(swap_move.0, swap_move.1)
+    })
+    // The estimate is only a lower bound of the cmax, so a move estimated as improving may keep it, accepting such a
+    // move could cycle forever (e.g. with a plateau step back to the solution it came from)
+    .filter(|next_solution| is_better(next_solution, current_solution, secondary_objective, None));
+}
+
+// Move to a neighbor with equal cmax that has not been visited since the last improvement
+// The visited orientations guard against cycling between the same solutions on a plateau
+pub(crate) fn find_plateau_step(
+  current_solution: &IntermediateSolution,
+  neighborhood: &dyn Neighborhood,
+  config: &HcConfig,
+  plateau_steps: &mut usize,
+  plateau_visited: &mut HashSet<Vec<Edge>>,
+) -> Option<IntermediateSolution> {
+  if !config.allow_plateau || *plateau_steps >= config.max_plateau_steps {
+    return None;
+  }
+
+  plateau_visited.insert(current_solution.orientation_key());
+
+  let cmax = current_solution.cmax();
+  let current_violation = violation(current_solution, config.constraint);
+  for candidate in neighborhood.generate_moves(current_solution) {
+    // The estimate is a lower bound, so only candidates estimated at cmax can stay on the plateau
+    if candidate.cmax != cmax {
+      continue;
+    }
+
+    let (a, b) = candidate.swap_move;
+    let next_solution = current_solution.apply_swap(a, b);
+    if next_solution.cmax() == cmax
+      && violation(&next_solution, config.constraint) <= current_violation
+      && plateau_visited.insert(next_solution.orientation_key())
+    {
+      *plateau_steps += 1;
+      return Some(next_solution);
+    }
+  }
+
+  return None;
+}
+
+// find_improvement on a CompactSolution, applies the move in place and returns whether one was found
+fn find_improvement_compact(current: &mut CompactSolution, config: &HcConfig) -> bool {
+  let cmax = current.cmax();
+  // The first of the lowest estimates, as the exhaustive search of find_improvement
+  let maybe_move = current
+    .generate_moves_full(config.skip_symmetric_swaps)
+    .into_iter()
+    .min_by_key(|candidate| candidate.cmax)
+    .filter(|candidate| candidate.cmax < cmax);
+  return match maybe_move {
+    Some(next_move) => {
+      let (a, b) = next_move.swap_move;
+      current
+        .evaluate_swap(a, b)
+        .map_or(false, |next_cmax| next_cmax < cmax)
+        && current.swap(a, b)
+    }
+    None => false,
+  };
+}
+
+// find_plateau_step on a CompactSolution, candidates that leave the plateau are swapped back
+fn find_plateau_step_compact(
+  current: &mut CompactSolution,
+  config: &HcConfig,
+  plateau_steps: &mut usize,
+  plateau_visited: &mut HashSet<Vec<Edge>>,
+) -> bool {
+  if !config.allow_plateau || *plateau_steps >= config.max_plateau_steps {
+    return false;
+  }
+
+  plateau_visited.insert(current.orientation_key());
+
+  let cmax = current.cmax();
+  for candidate in current.generate_moves_full(config.skip_symmetric_swaps) {
+    if candidate.cmax != cmax {
+      continue;
+    }
+
+    let (a, b) = candidate.swap_move;
+    if !current.swap(a, b) {
+      continue;
+    }
+    if current.cmax() == cmax && plateau_visited.insert(current.orientation_key()) {
+      *plateau_steps += 1;
+      return true;
+    }
+    current.swap(b, a);
+  }
+
+  return false;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::generate_random_solution_seeded;
+  use crate::solver::priority;
+  use crate::solver::verify_solution;
+  use crate::test_instances::ft06;
+  use std::time::Instant;
+
+  #[test]
+  fn plateau_steps_escape_a_local_optimum() {
+    let inst = Instance::from_jobs(
+      3,
+      &[
+        vec![(2, 2), (0, 1), (1, 2)],
+        vec![(2, 3), (0, 1), (1, 1)],
+        vec![(0, 1), (2, 1), (1, 2)],
+      ],
+    );
+    let initial_solution = priority::find_solution_sps(&inst, None);
+    let strict = improve_solution(&inst, &initial_solution, &HcConfig::default()).unwrap();
+    let plateau_config = HcConfig {
+      allow_plateau: true,
+      max_plateau_steps: 10,
+      ..HcConfig::default()
+    };
+    let plateau = improve_solution(&inst, &initial_solution, &plateau_config).unwrap();
+    assert_eq!(strict.cmax(), 9);
+    assert_eq!(plateau.cmax(), 8);
+  }
+
+  #[test]
+  fn compact_trajectory() {
+    let inst = ft06();
+    let config = HcConfig {
+      allow_plateau: true,
+      max_plateau_steps: 20,
+      neighborhood: NeighborhoodKind::N1Full,
+      ..HcConfig::default()
+    };
+    let neighborhood = config.neighborhood.resolve(config.skip_symmetric_swaps);
+    for seed in 0..5 {
+      let initial_solution = generate_random_solution_seeded(&inst, seed);
+      let mut plain = IntermediateSolution::from_solution(&inst, &initial_solution).unwrap();
+      let mut compact = CompactSolution::from_intermediate(&plain);
+      let (mut plain_steps, mut plain_visited) = (0, HashSet::new());
+      let (mut compact_steps, mut compact_visited) = (0, HashSet::new());
+      let mut trajectory = vec![plain.cmax()];
+      loop {
+        let plain_moved =
+          if let Some(next) = find_improvement(&plain, neighborhood.as_ref(), &config) {
+            plain = next;
+            plain_steps = 0;
+            plain_visited.clear();
+            true
+          } else if let Some(next) = find_plateau_step(
+            &plain,
+            neighborhood.as_ref(),
+            &config,
+            &mut plain_steps,
+            &mut plain_visited,
+          ) {
+            plain = next;
+            true
+          } else {
+            false
+          };
+        let compact_moved = if find_improvement_compact(&mut compact, &config) {
+          compact_steps = 0;
+          compact_visited.clear();
+          true
+        } else {
+          find_plateau_step_compact(
+            &mut compact,
+            &config,
+            &mut compact_steps,
+            &mut compact_visited,
+          )
+        };
+
+        assert_eq!(plain_moved, compact_moved);
+        assert_eq!(plain.cmax(), compact.cmax());
+        assert_eq!(plain.orientation_key(), compact.orientation_key());
+        if !plain_moved {
+          break;
+        }
+        trajectory.push(plain.cmax());
+      }
+      assert!(trajectory.len() > 1);
+
+      let climbed = improve_solution(&inst, &initial_solution, &config).unwrap();
+      let compact_climbed = improve_solution_compact(&inst, &initial_solution, &config).unwrap();
+      assert_eq!(climbed.orientation_key(), compact_climbed.orientation_key());
+      assert_eq!(climbed.cmax(), compact_climbed.cmax());
+    }
+
+    let n1_config = HcConfig::default();
+    assert!(
+      improve_solution_compact(&inst, &priority::find_solution_sps(&inst, None), &n1_config)
+        .is_err()
+    );
+  }
+
+  #[test]
+  fn random_walk() {
+    let inst = ft06();
+    let initial_solution = priority::find_solution_sps(&inst, None);
+    let plain = improve_solution(&inst, &initial_solution, &HcConfig::default()).unwrap();
+    let timeout = Duration::from_millis(50);
+    let walk_config = |random_walk_probability: f64| HcConfig {
+      random_walk_probability: random_walk_probability,
+      seed: 7,
+      timeout: Some(timeout),
+      ..HcConfig::default()
+    };
+
+    let no_walk = improve_solution(&inst, &initial_solution, &walk_config(0.0)).unwrap();
+    assert_eq!(no_walk.orientation_key(), plain.orientation_key());
+
+    // The walk does not stop at local optima, only at the timeout
+    for &random_walk_probability in &[0.1, 1.0] {
+      let start = Instant::now();
+      let walked = improve_solution(
+        &inst,
+        &initial_solution,
+        &walk_config(random_walk_probability),
+      )
+      .unwrap();
+      assert!(start.elapsed() >= timeout);
+      verify_solution(&inst, &walked.to_solution()).unwrap();
+      assert!(
+        walked.cmax()
+          <= IntermediateSolution::from_solution(&inst, &initial_solution)
+            .unwrap()
+            .cmax()
+      );
+    }
+
+    for &random_walk_probability in &[0.5, 1.0] {
+      let config = HcConfig {
+        timeout: None,
+        ..walk_config(random_walk_probability)
+      };
+      assert!(improve_solution(&inst, &initial_solution, &config).is_err());
+    }
+    assert!(improve_solution(&inst, &initial_solution, &walk_config(1.5)).is_err());
+
+    // A step limit stops the walk as well
+    let limited = HcConfig {
+      timeout: None,
+      max_steps: Some(100),
+      ..walk_config(1.0)
+    };
+    let walked = improve_solution(&inst, &initial_solution, &limited).unwrap();
+    assert_eq!(
+      walked.orientation_key(),
+      improve_solution(&inst, &initial_solution, &limited)
+        .unwrap()
+        .orientation_key()
+    );
+  }
 }
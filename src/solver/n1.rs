@@ -1,55 +1,17 @@
 extern crate itertools;
 
-use crate::data::OpId;
+use crate::data::{Machine, Op, OpId};
+use crate::preprocess::is_forced_pair;
+use crate::solver::neighborhood::{EvaluatedMove, SwapMove};
 use crate::solver::{op_ordering, IntermediateSolution};
 use log;
 use std::collections::{BTreeSet, VecDeque};
 
-pub enum SearchMethod {
-  Exhaustive,
-  First,
-}
-
-pub type SwapMove = (OpId, OpId);
-#[derive(Debug, Clone)]
-pub struct EvaluatedMove {
-  pub swap_move: SwapMove,
-  pub cmax: u32,
-}
-
-pub fn find_move(
+// With skip_symmetric_swaps, swaps of interchangeable operations are omitted (see is_symmetric_swap)
+pub fn generate_moves(
   solution: &IntermediateSolution,
-  should_accept: &mut dyn FnMut(&Option<EvaluatedMove>, &EvaluatedMove) -> bool,
-  search_method: SearchMethod,
-) -> Option<EvaluatedMove> {
-  let moves = generate_moves(&solution);
-
-  if log::log_enabled!(log::Level::Warn) {
-    if moves.is_empty() {
-      log::warn!("Generated neighborhood is empty");
-    }
-  }
-
-  let mut best = None;
-  for candidate_move in moves {
-    log::trace!("Trying move {:?}", candidate_move);
-    if should_accept(&best, &candidate_move) {
-      log::trace!("Accepted move {:?}", candidate_move);
-      best = Some(candidate_move);
-
-      match search_method {
-        SearchMethod::First => break,
-        SearchMethod::Exhaustive => {}
-      }
-    }
-  }
-
-  log::trace!("best={:?}", best);
-
-  return best;
-}
-
-pub fn generate_moves(solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
+  skip_symmetric_swaps: bool,
+) -> Vec<EvaluatedMove> {
   // Generate neighborhood by swapping critical orientations (on the longest path)
   // see Taillard, Parallel Taboo Search Techniques for the Job Shop Scheduling Problem and Van Laarhoven, Job shop scheduling by simulated annealing
 
@@ -59,8 +21,8 @@ pub fn generate_moves(solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
   let mut open = VecDeque::new();
   for op in 0..solution.instance.n_ops() {
     if solution.is_critical(op)
-      && solution.succ_job[op] == None
-      && solution.succ_machine[op] == None
+      && solution.succ_job[op].is_none()
+      && solution.succ_machine[op].is_none()
     {
       open.push_back(op);
     }
@@ -74,6 +36,8 @@ pub fn generate_moves(solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
     //  \ >------------> /
     // In the earlier graph the edge (01, 12) should not be included
 
+    // This also follows critical predecessors ending before current starts, which are not on a critical path
+    // through current, so unlike n1-full this can swap operations with idle time between them
     let critical_pre_job = solution.pre_job[current].filter(|&op| solution.is_critical(op));
     let critical_pre_machine = solution.pre_machine[current].filter(|&op| solution.is_critical(op));
 
@@ -115,13 +79,35 @@ pub fn generate_moves(solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
     }
   }
 
+  if cfg!(any(debug_assertions, feature = "verify")) {
+    assert!(
+      is_acyclic(&critical_arcs),
+      "Critical arcs contain a cycle: {:?}",
+      critical_arcs
+    );
+  }
+
+  // Swapping critical operations can only introduce a cycle if a different path of the same length connects them,
+  // which requires zero-duration operations on that path
+  let has_zero_durations = solution.instance.durations.iter().any(|&d| d == 0);
+
   let mut moves = Vec::new();
   for &(a, b) in &critical_arcs {
     let swap = (a, b);
     // Swap with successor on same machine
     if solution.instance.machines[a] == solution.instance.machines[b]
+      && !is_forced_pair(&solution.instance, a, b)
       && solution.oriented_conflict_edges.contains(&swap)
     {
+      if has_zero_durations && solution.evaluate_swap(a, b).is_none() {
+        log::trace!("Skipping move {:?} which would introduce a cycle", swap);
+        continue;
+      }
+      if skip_symmetric_swaps && solution.is_symmetric_swap(a, b) {
+        log::trace!("Skipping move {:?} of interchangeable operations", swap);
+        continue;
+      }
+
       let candidate_cmax = solution.cmax_after_swap(a, b);
 
       let candidate_move = EvaluatedMove {
@@ -137,3 +123,300 @@ pub fn generate_moves(solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
 
   return moves;
 }
+
+// Swaps of all machine-adjacent pairs on any critical path, i.e. both operations are critical and the first one ends
+// exactly when the second one starts
+pub fn generate_moves_full(
+  solution: &IntermediateSolution,
+  skip_symmetric_swaps: bool,
+) -> Vec<EvaluatedMove> {
+  let has_zero_durations = solution.instance.durations.iter().any(|&d| d == 0);
+
+  let mut moves = Vec::new();
+  for &(a, b) in &solution.oriented_conflict_edges {
+    if is_forced_pair(&solution.instance, a, b)
+      || !solution.is_critical(a)
+      || !solution.is_critical(b)
+      || solution.release_times[a] + solution.instance.durations[a] != solution.release_times[b]
+    {
+      continue;
+    }
+    if has_zero_durations && solution.evaluate_swap(a, b).is_none() {
+      log::trace!("Skipping move {:?} which would introduce a cycle", (a, b));
+      continue;
+    }
+    if skip_symmetric_swaps && solution.is_symmetric_swap(a, b) {
+      log::trace!("Skipping move {:?} of interchangeable operations", (a, b));
+      continue;
+    }
+
+    moves.push(EvaluatedMove {
+      swap_move: (a, b),
+      cmax: solution.cmax_after_swap(a, b),
+    });
+  }
+
+  log::trace!("moves={:?}", moves);
+
+  return moves;
+}
+
+// Machine with the largest total duration of critical operations (the lowest such machine on ties)
+pub fn bottleneck_machine(solution: &IntermediateSolution) -> Machine {
+  let inst = &solution.instance;
+  let mut critical_time = vec![0u64; inst.n_machines];
+  for op in 0..inst.n_ops() {
+    if solution.is_critical(op) {
+      critical_time[inst.machines[op]] += inst.durations[op] as u64;
+    }
+  }
+
+  return (0..inst.n_machines)
+    .max_by_key(|&m| (critical_time[m], std::cmp::Reverse(m)))
+    .unwrap_or(0);
+}
+
+// Moves of generate_moves on the bottleneck machine only, may be empty even if generate_moves is not
+pub fn generate_moves_bottleneck(
+  solution: &IntermediateSolution,
+  skip_symmetric_swaps: bool,
+) -> Vec<EvaluatedMove> {
+  let machine = bottleneck_machine(solution);
+  let mut moves = generate_moves(solution, skip_symmetric_swaps);
+  moves.retain(|candidate| solution.instance.machines[candidate.swap_move.0] == machine);
+  log::trace!("bottleneck_machine={} moves={:?}", machine, moves);
+
+  return moves;
+}
+
+// A move of the neighborhood with what it does to the solution, for inspecting local optima
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveExplanation {
+  pub swap_move: SwapMove,
+  // [job, index in job] of the swapped operations
+  pub ops: (Op, Op),
+  pub machine: Machine,
+  // Estimate the searches select the moves by
+  pub estimated_cmax: u32,
+  // After applying the move
+  pub cmax: u32,
+  // Whether cmax is lower than that of the solution
+  pub improves: bool,
+}
+
+// Every move of generate_moves (without skipping symmetric swaps) in generated order
+pub fn explain_moves(solution: &IntermediateSolution) -> Vec<MoveExplanation> {
+  let inst = &solution.instance;
+  return generate_moves(solution, false)
+    .into_iter()
+    .map(|candidate| {
+      let (a, b) = candidate.swap_move;
+      // Generated moves never introduce a cycle
+      let cmax = solution
+        .evaluate_swap(a, b)
+        .expect("Move introduces a cycle");
+      MoveExplanation {
+        swap_move: candidate.swap_move,
+        ops: (inst.op_from_id(a), inst.op_from_id(b)),
+        machine: inst.machines[a],
+        estimated_cmax: candidate.cmax,
+        cmax: cmax,
+        improves: cmax < solution.cmax(),
+      }
+    })
+    .collect();
+}
+
+// Attempts a topological sort (Kahn's algorithm) over the arcs, which succeeds iff they form a DAG
+fn is_acyclic(arcs: &BTreeSet<(OpId, OpId)>) -> bool {
+  let mut in_degree = std::collections::BTreeMap::<OpId, usize>::new();
+  for &(u, v) in arcs {
+    in_degree.entry(u).or_insert(0);
+    *in_degree.entry(v).or_insert(0) += 1;
+  }
+
+  let mut open: VecDeque<OpId> = in_degree
+    .iter()
+    .filter(|&(_, &d)| d == 0)
+    .map(|(&op, _)| op)
+    .collect();
+
+  let mut sorted = 0;
+  while let Some(node) = open.pop_front() {
+    sorted += 1;
+    for &(_, v) in arcs.range((node, OpId::MIN)..=(node, OpId::MAX)) {
+      let d = in_degree.get_mut(&v).unwrap();
+      *d -= 1;
+      if *d == 0 {
+        open.push_back(v);
+      }
+    }
+  }
+
+  return sorted == in_degree.len();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::data::Instance;
+  use crate::parser::parse_instance;
+  use crate::solver::{generate_random_solution_seeded, priority, seeded_rng};
+  use crate::test_instances::ft06;
+  use rand::seq::SliceRandom;
+  use rand::Rng;
+  use std::collections::HashSet;
+
+  #[test]
+  fn is_acyclic_detects_cycles() {
+    let chain: BTreeSet<(OpId, OpId)> = [(0, 1), (1, 2), (0, 2)].iter().copied().collect();
+    assert!(is_acyclic(&chain));
+    let cycle: BTreeSet<(OpId, OpId)> = [(0, 1), (1, 2), (2, 0)].iter().copied().collect();
+    assert!(!is_acyclic(&cycle));
+  }
+
+  #[test]
+  fn equal_length_critical_paths() {
+    // Both paths 0 -> 2 -> 3 and 0 -> 1 -> 3 have length 9, the job and machine predecessors of 3 end at the same time
+    let inst = parse_instance("2 2\n0 3 1 2\n0 2 1 4\n").unwrap();
+    let solution =
+      IntermediateSolution::from_solution(&inst, &priority::find_solution_sps(&inst, None))
+        .unwrap();
+    assert_eq!(solution.cmax(), 9);
+    assert_eq!(solution.critical_path_count(), 2);
+
+    let mut swaps: Vec<SwapMove> = generate_moves(&solution, false)
+      .iter()
+      .map(|m| m.swap_move)
+      .collect();
+    swaps.sort_unstable();
+    assert_eq!(swaps, vec![(0, 2), (1, 3)]);
+    for (a, b) in swaps {
+      assert!(solution.evaluate_swap(a, b).is_some());
+    }
+  }
+
+  #[test]
+  fn n1_is_a_subset_of_n1_full_except_across_idle_time() {
+    let mut rng = seeded_rng(0);
+    let mut instances = vec![ft06()];
+    // Small random instances, some with zero durations
+    for _ in 0..30 {
+      let jobs: Vec<Vec<(usize, u32)>> = (0..4)
+        .map(|_| {
+          let mut machines: Vec<usize> = (0..3).collect();
+          machines.shuffle(&mut rng);
+          machines
+            .into_iter()
+            .map(|machine| (machine, rng.gen_range(0, 5)))
+            .collect()
+        })
+        .collect();
+      instances.push(Instance::from_jobs(3, &jobs));
+    }
+    for inst in &instances {
+      for seed in 0..10 {
+        let solution =
+          IntermediateSolution::from_solution(inst, &generate_random_solution_seeded(inst, seed))
+            .unwrap();
+        for &skip_symmetric_swaps in &[false, true] {
+          let full: HashSet<SwapMove> = generate_moves_full(&solution, skip_symmetric_swaps)
+            .iter()
+            .map(|candidate| candidate.swap_move)
+            .collect();
+          for candidate in generate_moves(&solution, skip_symmetric_swaps) {
+            // Except for swaps across idle time, see generate_moves
+            let (a, b) = candidate.swap_move;
+            let end_a = solution.release_times[a] + inst.durations[a];
+            assert!(
+              full.contains(&candidate.swap_move) || end_a < solution.release_times[b],
+              "{:?}",
+              candidate
+            );
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn symmetric_swaps_are_skipped() {
+    // Jobs 0 and 1 are identical (3 on machine 0), job 2 needs machine 0 for 4
+    let inst = Instance::from_jobs(1, &[vec![(0, 3)], vec![(0, 3)], vec![(0, 4)]]);
+    let solution =
+      IntermediateSolution::from_solution(&inst, &priority::find_solution_sps(&inst, None))
+        .unwrap();
+    // Machine 0 processes jobs 0, 1 and 2 in order, the only critical path
+    assert_eq!(solution.cmax(), 10);
+    assert!(solution.is_symmetric_swap(0, 1));
+    assert!(!solution.is_symmetric_swap(1, 2));
+    assert_eq!(solution.apply_swap(0, 1).cmax(), 10);
+
+    let swaps = |moves: Vec<EvaluatedMove>| {
+      let mut swaps: Vec<SwapMove> = moves.iter().map(|candidate| candidate.swap_move).collect();
+      swaps.sort_unstable();
+      swaps
+    };
+    assert_eq!(
+      swaps(generate_moves(&solution, false)),
+      vec![(0, 1), (1, 2)]
+    );
+    assert_eq!(swaps(generate_moves(&solution, true)), vec![(1, 2)]);
+    assert_eq!(
+      swaps(generate_moves_full(&solution, false)),
+      vec![(0, 1), (1, 2)]
+    );
+    assert_eq!(swaps(generate_moves_full(&solution, true)), vec![(1, 2)]);
+  }
+
+  #[test]
+  fn explanations_of_the_neighborhood() {
+    let inst = ft06();
+    for seed in 0..10 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      let moves = generate_moves(&solution, false);
+      let explanations = explain_moves(&solution);
+      assert_eq!(explanations.len(), moves.len());
+      for (explanation, candidate) in explanations.iter().zip(&moves) {
+        let (a, b) = candidate.swap_move;
+        assert_eq!(explanation.swap_move, (a, b));
+        assert_eq!(explanation.ops, (inst.op_from_id(a), inst.op_from_id(b)));
+        assert_eq!(explanation.machine, inst.machines[b]);
+        assert_eq!(explanation.estimated_cmax, candidate.cmax);
+        assert_eq!(explanation.cmax, solution.apply_swap(a, b).cmax());
+        assert_eq!(explanation.improves, explanation.cmax < solution.cmax());
+      }
+    }
+  }
+
+  #[test]
+  fn bottleneck_moves() {
+    let inst = ft06();
+    let mut any_moves = false;
+    for seed in 0..20 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      let machine = bottleneck_machine(&solution);
+      let all_moves = generate_moves(&solution, false);
+      let moves = generate_moves_bottleneck(&solution, false);
+      for candidate in &moves {
+        let (a, b) = candidate.swap_move;
+        assert_eq!(inst.machines[a], machine);
+        assert_eq!(inst.machines[b], machine);
+        assert!(all_moves.iter().any(|other| other.swap_move == (a, b)));
+      }
+      assert_eq!(
+        moves.len(),
+        all_moves
+          .iter()
+          .filter(|candidate| inst.machines[candidate.swap_move.0] == machine)
+          .count()
+      );
+      any_moves |= !moves.is_empty();
+    }
+    assert!(any_moves);
+  }
+}
@@ -0,0 +1,419 @@
+use crate::data::OpId;
+use crate::solver::{n1, seeded_rng, IntermediateSolution};
+use log;
+use rand::seq::SliceRandom;
+use rand_chacha;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMethod {
+  Exhaustive,
+  First,
+}
+
+// Order in which find_move offers the moves to should_accept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateOrder {
+  // As generated by the neighborhood
+  Generated,
+  // By ascending cmax estimate (ties in generated order), so a first-improvement search tries promising moves first
+  Estimate,
+}
+
+impl Default for CandidateOrder {
+  fn default() -> Self {
+    return CandidateOrder::Generated;
+  }
+}
+
+// Which of the moves should_accept considers equally good is selected, since the solvers only accept strictly better
+// candidates this is the first one offered (with CandidateOrder::Estimate among those of equal estimate)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+  // First generated by the neighborhood
+  Generated,
+  // Lexicographically smallest swap pair, independent of how the neighborhood generates its moves
+  Lexicographic,
+  // Uniformly random, reproducible for the seed of the TieBreaker
+  Random,
+}
+
+impl Default for TieBreak {
+  fn default() -> Self {
+    return TieBreak::Generated;
+  }
+}
+
+impl FromStr for TieBreak {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    return match s {
+      "generated" => Ok(TieBreak::Generated),
+      "lexicographic" => Ok(TieBreak::Lexicographic),
+      "random" => Ok(TieBreak::Random),
+      _ => Err(format!("Unknown tie break {}", s)),
+    };
+  }
+}
+
+// Mixed into the seed of a TieBreaker, so it does not draw the same numbers as an rng of the same seed (e.g. the search
+// rng seeded with the same seed)
+const SEED_MIX: u64 = 0x2545_f491_4f6c_dd1d;
+
+// Tie break with its own rng, so random tie breaks do not change the random stream of the search
+pub struct TieBreaker {
+  tie_break: TieBreak,
+  rng: rand_chacha::ChaChaRng,
+}
+
+impl TieBreaker {
+  pub fn new(tie_break: TieBreak, seed: u64) -> Self {
+    return TieBreaker {
+      tie_break: tie_break,
+      rng: seeded_rng(seed ^ SEED_MIX),
+    };
+  }
+
+  // Reorders moves so the winner of a tie comes first among them
+  fn order(&mut self, moves: &mut [EvaluatedMove]) {
+    match self.tie_break {
+      TieBreak::Generated => {}
+      TieBreak::Lexicographic => moves.sort_by_key(|candidate| candidate.swap_move),
+      TieBreak::Random => moves.shuffle(&mut self.rng),
+    }
+  }
+}
+
+impl Default for TieBreaker {
+  fn default() -> Self {
+    return TieBreaker::new(TieBreak::Generated, 0);
+  }
+}
+
+pub type SwapMove = (OpId, OpId);
+#[derive(Debug, Clone)]
+pub struct EvaluatedMove {
+  pub swap_move: SwapMove,
+  pub cmax: u32,
+}
+
+pub trait Neighborhood {
+  fn generate_moves(&self, solution: &IntermediateSolution) -> Vec<EvaluatedMove>;
+
+  fn find_move(
+    &self,
+    solution: &IntermediateSolution,
+    should_accept: &mut dyn FnMut(&Option<EvaluatedMove>, &EvaluatedMove) -> bool,
+    search_method: SearchMethod,
+    candidate_order: CandidateOrder,
+    tie_breaker: &mut TieBreaker,
+  ) -> Option<EvaluatedMove> {
+    let mut moves = self.generate_moves(solution);
+    tie_breaker.order(&mut moves);
+    if candidate_order == CandidateOrder::Estimate {
+      moves.sort_by_key(|candidate| candidate.cmax);
+    }
+
+    if log::log_enabled!(log::Level::Warn) && moves.is_empty() {
+      log::warn!("Generated neighborhood is empty");
+    }
+
+    let mut best = None;
+    for candidate_move in moves {
+      log::trace!("Trying move {:?}", candidate_move);
+      if should_accept(&best, &candidate_move) {
+        log::trace!("Accepted move {:?}", candidate_move);
+        best = Some(candidate_move);
+
+        match search_method {
+          SearchMethod::First => break,
+          SearchMethod::Exhaustive => {}
+        }
+      }
+    }
+
+    log::trace!("best={:?}", best);
+
+    return best;
+  }
+}
+
+// Swaps of adjacent critical operations, see Van Laarhoven, Aarts, and Lenstra, “Job Shop Scheduling by Simulated Annealing.”
+pub struct N1 {
+  pub skip_symmetric_swaps: bool,
+}
+
+impl Neighborhood for N1 {
+  fn generate_moves(&self, solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
+    return n1::generate_moves(solution, self.skip_symmetric_swaps);
+  }
+}
+
+// Swaps of all adjacent critical operations on every critical path (a superset of N1)
+pub struct N1Full {
+  pub skip_symmetric_swaps: bool,
+}
+
+impl Neighborhood for N1Full {
+  fn generate_moves(&self, solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
+    return n1::generate_moves_full(solution, self.skip_symmetric_swaps);
+  }
+}
+
+// Swaps of N1 on the bottleneck machine (see n1::bottleneck_machine), all swaps of N1 if there are none on it
+pub struct N1Bottleneck {
+  pub skip_symmetric_swaps: bool,
+}
+
+impl Neighborhood for N1Bottleneck {
+  fn generate_moves(&self, solution: &IntermediateSolution) -> Vec<EvaluatedMove> {
+    let moves = n1::generate_moves_bottleneck(solution, self.skip_symmetric_swaps);
+    if moves.is_empty() {
+      return n1::generate_moves(solution, self.skip_symmetric_swaps);
+    }
+    return moves;
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighborhoodKind {
+  N1,
+  N1Full,
+  N1Bottleneck,
+}
+
+impl NeighborhoodKind {
+  // With skip_symmetric_swaps, the neighborhood omits swaps of interchangeable operations (see
+  // IntermediateSolution::is_symmetric_swap), which provably leave cmax unchanged: a waste of evaluations for descents,
+  // but they allow plateau moves
+  pub fn resolve(&self, skip_symmetric_swaps: bool) -> Box<dyn Neighborhood> {
+    return match self {
+      NeighborhoodKind::N1 => Box::new(N1 {
+        skip_symmetric_swaps: skip_symmetric_swaps,
+      }),
+      NeighborhoodKind::N1Full => Box::new(N1Full {
+        skip_symmetric_swaps: skip_symmetric_swaps,
+      }),
+      NeighborhoodKind::N1Bottleneck => Box::new(N1Bottleneck {
+        skip_symmetric_swaps: skip_symmetric_swaps,
+      }),
+    };
+  }
+}
+
+impl Default for NeighborhoodKind {
+  fn default() -> Self {
+    return NeighborhoodKind::N1;
+  }
+}
+
+impl FromStr for NeighborhoodKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    return match s {
+      "n1" => Ok(NeighborhoodKind::N1),
+      "n1-full" => Ok(NeighborhoodKind::N1Full),
+      "n1-bottleneck" => Ok(NeighborhoodKind::N1Bottleneck),
+      _ => Err(format!("Unknown neighborhood {}", s)),
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::data::Instance;
+  use crate::solver::spec::{solve_with_budget, Budget, SolverSpec};
+  use crate::solver::{calculate_cmax, generate_random_solution_seeded, verify_solution};
+  use crate::test_instances::ft06;
+  use std::collections::HashSet;
+
+  fn moves(moves: &[EvaluatedMove]) -> Vec<(SwapMove, u32)> {
+    return moves.iter().map(|m| (m.swap_move, m.cmax)).collect();
+  }
+
+  #[test]
+  fn n1_through_the_trait() {
+    let inst = ft06();
+    for seed in 0..10 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      for &skip_symmetric_swaps in &[false, true] {
+        let neighborhood = NeighborhoodKind::N1.resolve(skip_symmetric_swaps);
+        assert_eq!(
+          moves(&neighborhood.generate_moves(&solution)),
+          moves(&n1::generate_moves(&solution, skip_symmetric_swaps))
+        );
+      }
+    }
+  }
+
+  // Results of the searches with n1 for a fixed seed and iteration budget, they only change with the trajectories
+  #[test]
+  fn n1_results() {
+    let inst = ft06();
+    let cmax = |spec: &SolverSpec, iterations: u64| {
+      calculate_cmax(
+        &inst,
+        &solve_with_budget(&inst, spec, Budget::Iterations(iterations))
+          .unwrap()
+          .0,
+      )
+    };
+    for (seed, &(tabu_cmax, sa_cmax)) in [(56, 61), (64, 62), (70, 60)].iter().enumerate() {
+      let seed = seed as u64;
+      let tabu_search = SolverSpec::TabuSearch {
+        seed: seed,
+        neighborhood: NeighborhoodKind::N1,
+        skip_symmetric_swaps: false,
+        secondary_objective: None,
+        constraint: None,
+        verify: false,
+      };
+      let simulated_annealing = SolverSpec::SimulatedAnnealing {
+        seed: seed,
+        start_acceptance_ratio: None,
+        delta: None,
+        neighborhood: NeighborhoodKind::N1,
+        skip_symmetric_swaps: false,
+        secondary_objective: None,
+        constraint: None,
+        verify: false,
+      };
+      assert_eq!(cmax(&tabu_search, 15), tabu_cmax, "seed {}", seed);
+      assert_eq!(cmax(&simulated_annealing, 300), sa_cmax, "seed {}", seed);
+    }
+  }
+
+  #[test]
+  fn all_zero_job() {
+    // Job 1 consists only of zero-duration operations
+    let inst = Instance::from_jobs(
+      3,
+      &[
+        vec![(0, 2), (1, 3), (2, 1)],
+        vec![(1, 0), (2, 0), (0, 0)],
+        vec![(2, 2), (0, 1), (1, 2)],
+      ],
+    );
+    let kinds = [
+      NeighborhoodKind::N1,
+      NeighborhoodKind::N1Full,
+      NeighborhoodKind::N1Bottleneck,
+    ];
+    for seed in 0..20 {
+      let start = generate_random_solution_seeded(&inst, seed);
+      let solution = IntermediateSolution::from_solution(&inst, &start).unwrap();
+      // Operations starting at the same instant are ordered deterministically
+      let again = IntermediateSolution::from_solution(&inst, &start).unwrap();
+      assert_eq!(solution.orientation_key(), again.orientation_key());
+      assert_eq!(solution.cmax(), calculate_cmax(&inst, &start));
+      assert!(solution.critical_path_count() >= 1);
+
+      for kind in &kinds {
+        for &skip_symmetric_swaps in &[false, true] {
+          for candidate in kind.resolve(skip_symmetric_swaps).generate_moves(&solution) {
+            let (a, b) = candidate.swap_move;
+            let next = solution.apply_swap(a, b).to_solution();
+            verify_solution(&inst, &next).unwrap();
+            assert!(candidate.cmax <= calculate_cmax(&inst, &next));
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn first_improvement_with_presorted_candidates() {
+    let inst = ft06();
+    let neighborhood = NeighborhoodKind::N1Full.resolve(false);
+    for seed in 0..10 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      let moves = neighborhood.generate_moves(&solution);
+      // Rejects some candidates, like the tabu list of a search would
+      let acceptable = |candidate: &EvaluatedMove| candidate.swap_move.0 % 3 != 0;
+      let best_estimate = moves
+        .iter()
+        .filter(|candidate| acceptable(candidate))
+        .map(|candidate| candidate.cmax)
+        .min();
+      let selected = neighborhood.find_move(
+        &solution,
+        &mut |_, candidate| acceptable(candidate),
+        SearchMethod::First,
+        CandidateOrder::Estimate,
+        &mut TieBreaker::default(),
+      );
+      assert_eq!(selected.map(|selected| selected.cmax), best_estimate);
+    }
+  }
+
+  #[test]
+  fn tie_break_winner() {
+    let inst = ft06();
+    let neighborhood = NeighborhoodKind::N1Full.resolve(false);
+    let find = |solution: &IntermediateSolution, tie_breaker: &mut TieBreaker| {
+      neighborhood
+        .find_move(
+          solution,
+          &mut |best, candidate| {
+            best
+              .as_ref()
+              .map_or(true, |best| candidate.cmax < best.cmax)
+          },
+          SearchMethod::Exhaustive,
+          CandidateOrder::Generated,
+          tie_breaker,
+        )
+        .unwrap()
+        .swap_move
+    };
+    let mut ties = 0;
+    let mut random_winners = HashSet::new();
+    for seed in 0..10 {
+      let solution =
+        IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, seed))
+          .unwrap();
+      let moves = neighborhood.generate_moves(&solution);
+      let best_cmax = moves.iter().map(|candidate| candidate.cmax).min().unwrap();
+      let tied: Vec<SwapMove> = moves
+        .iter()
+        .filter(|candidate| candidate.cmax == best_cmax)
+        .map(|candidate| candidate.swap_move)
+        .collect();
+      if tied.len() > 1 {
+        ties += 1;
+      }
+
+      assert_eq!(find(&solution, &mut TieBreaker::default()), tied[0]);
+      let mut lexicographic = TieBreaker::new(TieBreak::Lexicographic, seed);
+      assert_eq!(
+        find(&solution, &mut lexicographic),
+        *tied.iter().min().unwrap()
+      );
+      // Random winners are among the tied moves and reproducible for the seed
+      let winners: Vec<SwapMove> = (0..2)
+        .map(|_| {
+          let winner = find(&solution, &mut TieBreaker::new(TieBreak::Random, 3));
+          assert!(tied.contains(&winner));
+          winner
+        })
+        .collect();
+      assert_eq!(winners[0], winners[1]);
+      for tie_seed in 0..20 {
+        random_winners.insert((
+          seed,
+          find(&solution, &mut TieBreaker::new(TieBreak::Random, tie_seed)),
+        ));
+      }
+    }
+    assert!(ties > 0);
+    // Different seeds choose different winners of a tie
+    assert!(random_winners.len() > 10);
+  }
+}
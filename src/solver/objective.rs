@@ -0,0 +1,194 @@
+use crate::data::{Instance, Solution};
+use crate::solver::neighborhood::EvaluatedMove;
+use crate::solver::{
+  calculate_cmax, calculate_machine_completion_spread, calculate_total_flow_time,
+  calculate_total_idle, calculate_total_tardiness, calculate_weighted_earliness_tardiness,
+  IntermediateSolution,
+};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+// The solvers always minimize the makespan, the other objectives are only secondary objectives breaking its ties
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+  Makespan,
+  // Sum of the completion times of all jobs
+  TotalFlowTime,
+  // Sum of the delays of all jobs past their due dates, requires due dates
+  TotalTardiness,
+  // Sum of the gaps between consecutive operations on each machine (see calculate_total_idle)
+  TotalIdleTime,
+  // Spread of the completion times of the machines (see calculate_machine_completion_spread)
+  MachineCompletionSpread,
+  // Weighted sum of the earliness and tardiness of all jobs (see calculate_weighted_earliness_tardiness), requires due
+  // dates
+  WeightedEarlinessTardiness,
+}
+
+impl Objective {
+  // Full recompute from the schedule, lower is better, panics for objectives requiring due dates on instances without
+  // them
+  pub fn evaluate(&self, inst: &Instance, solution: &Solution) -> u64 {
+    return match self {
+      Objective::Makespan => calculate_cmax(inst, solution) as u64,
+      Objective::TotalFlowTime => calculate_total_flow_time(inst, solution),
+      Objective::TotalTardiness => calculate_total_tardiness(inst, solution),
+      Objective::TotalIdleTime => calculate_total_idle(inst, solution),
+      Objective::MachineCompletionSpread => calculate_machine_completion_spread(inst, solution),
+      Objective::WeightedEarlinessTardiness => {
+        calculate_weighted_earliness_tardiness(inst, solution)
+      }
+    };
+  }
+
+  pub fn requires_due_dates(&self) -> bool {
+    return matches!(
+      self,
+      Objective::TotalTardiness | Objective::WeightedEarlinessTardiness
+    );
+  }
+}
+
+impl FromStr for Objective {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    return match s {
+      "makespan" => Ok(Objective::Makespan),
+      "total-flow-time" => Ok(Objective::TotalFlowTime),
+      "total-tardiness" => Ok(Objective::TotalTardiness),
+      "total-idle-time" => Ok(Objective::TotalIdleTime),
+      "machine-completion-spread" => Ok(Objective::MachineCompletionSpread),
+      "weighted-earliness-tardiness" => Ok(Objective::WeightedEarlinessTardiness),
+      _ => Err(format!("Unknown objective {}", s)),
+    };
+  }
+}
+
+// Upper bound on an objective that the search has to satisfy, evaluating a tardiness bound panics without due dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Constraint {
+  pub objective: Objective,
+  pub max_value: u64,
+}
+
+impl Constraint {
+  // Parses e.g. tardiness-leq 100
+  pub fn parse(kind: &str, max_value: &str) -> Result<Self, String> {
+    let objective = match kind {
+      "tardiness-leq" => Objective::TotalTardiness,
+      _ => return Err(format!("Unknown constraint {}", kind)),
+    };
+    let max_value = max_value
+      .parse()
+      .map_err(|_| format!("Invalid constraint value {}", max_value))?;
+
+    return Ok(Constraint {
+      objective: objective,
+      max_value: max_value,
+    });
+  }
+
+  // Amount by which the solution exceeds the bound, zero iff the constraint is satisfied
+  pub fn violation(&self, solution: &IntermediateSolution) -> u64 {
+    return solution
+      .evaluate(self.objective)
+      .saturating_sub(self.max_value);
+  }
+}
+
+// Zero without constraint
+pub(crate) fn violation(solution: &IntermediateSolution, constraint: Option<Constraint>) -> u64 {
+  return match constraint {
+    Some(constraint) => constraint.violation(solution),
+    None => 0,
+  };
+}
+
+// Violation after applying the move to current, only applies the move if there is a constraint
+pub(crate) fn move_violation(
+  current: &IntermediateSolution,
+  candidate: &EvaluatedMove,
+  constraint: Option<Constraint>,
+) -> u64 {
+  return match constraint {
+    Some(constraint) => {
+      constraint.violation(&current.apply_swap(candidate.swap_move.0, candidate.swap_move.1))
+    }
+    None => 0,
+  };
+}
+
+// Lexicographic comparison by constraint violation (if any), cmax and the secondary objective (if any)
+// Feasible solutions are therefore always better than infeasible ones
+pub(crate) fn compare_solutions(
+  a: &IntermediateSolution,
+  b: &IntermediateSolution,
+  secondary_objective: Option<Objective>,
+  constraint: Option<Constraint>,
+) -> Ordering {
+  return violation(a, constraint)
+    .cmp(&violation(b, constraint))
+    .then_with(|| a.cmax().cmp(&b.cmax()))
+    .then_with(|| match secondary_objective {
+      Some(objective) => a.evaluate(objective).cmp(&b.evaluate(objective)),
+      None => Ordering::Equal,
+    });
+}
+
+pub(crate) fn is_better(
+  a: &IntermediateSolution,
+  b: &IntermediateSolution,
+  secondary_objective: Option<Objective>,
+  constraint: Option<Constraint>,
+) -> bool {
+  return compare_solutions(a, b, secondary_objective, constraint) == Ordering::Less;
+}
+
+// Breaks a cmax tie between two moves from current by applying both, Equal without secondary objective
+pub(crate) fn compare_tied_moves(
+  current: &IntermediateSolution,
+  a: &EvaluatedMove,
+  b: &EvaluatedMove,
+  secondary_objective: Option<Objective>,
+) -> Ordering {
+  return match secondary_objective {
+    Some(objective) => {
+      let a_solution = current.apply_swap(a.swap_move.0, a.swap_move.1);
+      let b_solution = current.apply_swap(b.swap_move.0, b.swap_move.1);
+      a_solution
+        .evaluate(objective)
+        .cmp(&b_solution.evaluate(objective))
+    }
+    None => Ordering::Equal,
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn solution(inst: &Instance, sequences: &[Vec<usize>]) -> IntermediateSolution {
+    let solution = Solution::from_machine_sequences(inst, sequences).unwrap();
+    return IntermediateSolution::from_solution(inst, &solution).unwrap();
+  }
+
+  #[test]
+  fn secondary_objective_breaks_cmax_ties() {
+    // Job 2 alone determines the cmax of 10, the order of jobs 0 and 1 on machine 0 only changes the flow time
+    let inst = Instance::from_jobs(2, &[vec![(0, 1)], vec![(0, 2)], vec![(1, 10)]]);
+    let shortest_first = solution(&inst, &[vec![0, 1], vec![2]]);
+    let longest_first = solution(&inst, &[vec![1, 0], vec![2]]);
+    assert_eq!(shortest_first.cmax(), longest_first.cmax());
+    assert_eq!(
+      shortest_first.evaluate(Objective::TotalFlowTime),
+      1 + 3 + 10
+    );
+    assert_eq!(longest_first.evaluate(Objective::TotalFlowTime), 2 + 3 + 10);
+
+    let flow_time = Some(Objective::TotalFlowTime);
+    assert!(is_better(&shortest_first, &longest_first, flow_time, None));
+    assert!(!is_better(&longest_first, &shortest_first, flow_time, None));
+    assert!(!is_better(&shortest_first, &longest_first, None, None));
+  }
+}
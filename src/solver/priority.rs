@@ -1,104 +1,129 @@
 use crate::data::{Instance, OpId, Solution};
 use ndarray::Array1;
+use rand::{Rng, RngCore};
 use std::cmp;
 
-pub fn find_solution_sps(inst: &Instance) -> Solution {
-  return find_solution(&inst, &mut |candidates| {
-    candidates
-      .iter()
-      .enumerate()
-      .min_by_key(|&(_, op_id)| {
-        let [j, o] = inst.op_from_id(*op_id);
-        (o, j)
-      })
-      .unwrap()
-      .0
-  });
+// The rules dispatch by an index tie-break between candidates of equal priority, or with a tie_break rng uniformly at
+// random between them, so different rng states explore different schedules of the same rule
+
+pub fn find_solution_sps(inst: &Instance, tie_break: Option<&mut dyn RngCore>) -> Solution {
+  return find_solution_by_priority(
+    inst,
+    |op_id| inst.op_from_id(op_id)[1] as u32,
+    false,
+    tie_break,
+  );
 }
 
-pub fn find_solution_lps(inst: &Instance) -> Solution {
-  return find_solution(&inst, &mut |candidates| {
-    candidates
-      .iter()
-      .enumerate()
-      .max_by_key(|&(_, op_id)| {
-        let [j, o] = inst.op_from_id(*op_id);
-        (o, j)
-      })
-      .unwrap()
-      .0
-  });
+pub fn find_solution_lps(inst: &Instance, tie_break: Option<&mut dyn RngCore>) -> Solution {
+  return find_solution_by_priority(
+    inst,
+    |op_id| inst.op_from_id(op_id)[1] as u32,
+    true,
+    tie_break,
+  );
 }
 
-pub fn find_solution_spt(inst: &Instance) -> Solution {
-  return find_solution(&inst, &mut |candidates| {
-    candidates
-      .iter()
-      .enumerate()
-      .min_by_key(|&(_, &op_id)| {
-        let [j, o] = inst.op_from_id(op_id);
-        (inst.durations[op_id], j, o)
-      })
-      .unwrap()
-      .0
-  });
+pub fn find_solution_spt(inst: &Instance, tie_break: Option<&mut dyn RngCore>) -> Solution {
+  return find_solution_by_priority(inst, |op_id| inst.durations[op_id], false, tie_break);
 }
 
-pub fn find_solution_lpt(inst: &Instance) -> Solution {
-  return find_solution(&inst, &mut |candidates| {
-    candidates
-      .iter()
-      .enumerate()
-      .max_by_key(|&(_, &op_id)| {
-        let [j, o] = inst.op_from_id(op_id);
-        (inst.durations[op_id], j, o)
-      })
-      .unwrap()
-      .0
-  });
+pub fn find_solution_lpt(inst: &Instance, tie_break: Option<&mut dyn RngCore>) -> Solution {
+  return find_solution_by_priority(inst, |op_id| inst.durations[op_id], true, tie_break);
 }
 
-pub fn find_solution_lwrm(inst: &Instance) -> Solution {
-  return find_solution(&inst, &mut |candidates| {
-    candidates
-      .iter()
-      .enumerate()
-      .min_by_key(|&(_, &op_id)| {
-        let [j, o] = inst.op_from_id(op_id);
-        (get_work_remaining(&inst, j, o), j, o)
-      })
-      .unwrap()
-      .0
-  });
+pub fn find_solution_lwrm(inst: &Instance, tie_break: Option<&mut dyn RngCore>) -> Solution {
+  return find_solution_by_priority(
+    inst,
+    |op_id| {
+      let [j, o] = inst.op_from_id(op_id);
+      get_work_remaining(inst, j, o)
+    },
+    false,
+    tie_break,
+  );
 }
 
-pub fn find_solution_mwrm(inst: &Instance) -> Solution {
-  return find_solution(&inst, &mut |candidates| {
-    candidates
-      .iter()
-      .enumerate()
-      .max_by_key(|&(_, &op_id)| {
-        let [j, o] = inst.op_from_id(op_id);
-        (get_work_remaining(&inst, j, o), j, o)
-      })
-      .unwrap()
-      .0
+pub fn find_solution_mwrm(inst: &Instance, tie_break: Option<&mut dyn RngCore>) -> Solution {
+  return find_solution_by_priority(
+    inst,
+    |op_id| {
+      let [j, o] = inst.op_from_id(op_id);
+      get_work_remaining(inst, j, o)
+    },
+    true,
+    tie_break,
+  );
+}
+
+// Dispatches the candidate of lowest (or with maximize highest) priority, ties broken by the lowest (highest) job index
+// or with a tie_break rng uniformly at random (candidates are the next operations of distinct jobs)
+fn find_solution_by_priority(
+  inst: &Instance,
+  priority: impl Fn(OpId) -> u32,
+  maximize: bool,
+  mut tie_break: Option<&mut dyn RngCore>,
+) -> Solution {
+  return find_solution(inst, &mut |candidates, _state| {
+    let key = |&(_, &op_id): &(usize, &OpId)| (priority(op_id), inst.op_from_id(op_id)[0]);
+    let rng = match tie_break.as_deref_mut() {
+      Some(rng) => rng,
+      None => {
+        let chosen = if maximize {
+          candidates.iter().enumerate().max_by_key(key)
+        } else {
+          candidates.iter().enumerate().min_by_key(key)
+        };
+        return chosen.unwrap().0;
+      }
+    };
+    let priorities: Vec<u32> = candidates.iter().map(|&op_id| priority(op_id)).collect();
+    let best = if maximize {
+      priorities.iter().max()
+    } else {
+      priorities.iter().min()
+    }
+    .copied()
+    .unwrap();
+    let tied: Vec<usize> = (0..candidates.len())
+      .filter(|&i| priorities[i] == best)
+      .collect();
+    tied[rng.gen_range(0, tied.len())]
   });
 }
 
-fn get_work_remaining(inst: &Instance, job: usize, op: usize) -> u32 {
+pub fn get_work_remaining(inst: &Instance, job: usize, op: usize) -> u32 {
   let mut work_remaining = 0;
-  for upcoming_op in op..inst.n_machines {
+  for upcoming_op in op..inst.job_len(job) {
     let upcoming_op_id = inst.op_to_id([job, upcoming_op]);
     work_remaining += inst.durations[upcoming_op_id];
   }
   return work_remaining;
 }
 
+// Partial schedule at a dispatching decision, allows time-aware rules (e.g. slack based ones)
+pub struct SchedulerState<'a> {
+  // Earliest start of any candidate, i.e. when the conflicting machine can start the next operation
+  pub time: u32,
+  pub machine_next_release: &'a Array1<u32>,
+  pub job_next_release: &'a Array1<u32>,
+}
+
+impl SchedulerState<'_> {
+  pub fn earliest_start(&self, inst: &Instance, op: OpId) -> u32 {
+    let [j, _o] = inst.op_from_id(op);
+    return cmp::max(
+      self.job_next_release[j],
+      self.machine_next_release[inst.machines[op]],
+    );
+  }
+}
+
 // A Computational Study of Representations in Genetic Programming to Evolve Dispatching Rules for the Job Shop Scheduling Problem
+// choose_next returns the index of the chosen candidate
 pub fn find_solution(
   inst: &Instance,
-  choose_next: &mut dyn FnMut(&Vec<OpId>) -> usize,
+  choose_next: &mut dyn FnMut(&Vec<OpId>, &SchedulerState) -> usize,
 ) -> Solution {
   let mut op_start_times = Array1::<u32>::from_elem(inst.n_ops(), 0);
   let mut machine_next_release = Array1::<u32>::from_elem(inst.n_machines, 0);
@@ -106,7 +131,9 @@ pub fn find_solution(
 
   let mut ready = Vec::new();
   for j in 0..inst.n_jobs {
-    ready.push(inst.op_to_id([j, 0]));
+    if inst.job_len(j) > 0 {
+      ready.push(inst.op_to_id([j, 0]));
+    }
   }
 
   while !ready.is_empty() {
@@ -144,7 +171,17 @@ pub fn find_solution(
       })
       .collect();
 
-    let chosen_idx = choose_next(&candidates);
+    let mut state = SchedulerState {
+      time: 0,
+      machine_next_release: &machine_next_release,
+      job_next_release: &job_next_release,
+    };
+    state.time = candidates
+      .iter()
+      .map(|&op_id| state.earliest_start(inst, op_id))
+      .min()
+      .unwrap();
+    let chosen_idx = choose_next(&candidates, &state);
     let chosen_op = candidates[chosen_idx];
 
     let [j, o] = inst.op_from_id(chosen_op);
@@ -158,7 +195,7 @@ pub fn find_solution(
 
     // Remove from queue and push successor (if exists)
     ready.retain(|&op| op != chosen_op);
-    if o < inst.n_machines - 1 {
+    if o + 1 < inst.job_len(j) {
       ready.push(inst.op_to_id([j, o + 1]));
     }
   }
@@ -167,3 +204,64 @@ pub fn find_solution(
     start_times: op_start_times,
   };
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{seeded_rng, verify_solution};
+
+  #[test]
+  fn minimum_slack() {
+    // Three single-operation jobs on one machine: (duration, due date) (2, 10), (3, 4) and (1, 6)
+    let mut inst = Instance::from_jobs(1, &[vec![(0, 2)], vec![(0, 3)], vec![(0, 1)]]);
+    inst.due_dates = Some(Array1::from(vec![10, 4, 6]));
+    let due_dates = inst.due_dates.clone().unwrap();
+
+    let mut times = Vec::new();
+    let solution = find_solution(&inst, &mut |candidates, state| {
+      times.push(state.time);
+      for &op_id in candidates {
+        assert_eq!(
+          state.earliest_start(&inst, op_id),
+          state.machine_next_release[0]
+        );
+      }
+      candidates
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, &op_id)| {
+          let [j, o] = inst.op_from_id(op_id);
+          let slack =
+            due_dates[j] as i64 - state.time as i64 - get_work_remaining(&inst, j, o) as i64;
+          (slack, j)
+        })
+        .unwrap()
+        .0
+    });
+    // Slacks at time 0 are 8, 1 and 5, at time 3 they are 5 and 2
+    assert_eq!(times, vec![0, 3, 4]);
+    assert_eq!(solution.start_times.to_vec(), vec![4, 0, 3]);
+  }
+
+  #[test]
+  fn random_ties() {
+    // All four jobs tie for every rule on both machines
+    let inst = Instance::from_jobs(2, &vec![vec![(0, 2), (1, 2)]; 4]);
+    let deterministic = find_solution_spt(&inst, None);
+    assert_eq!(
+      deterministic.start_times.to_vec(),
+      vec![0, 2, 2, 4, 4, 6, 6, 8]
+    );
+
+    let solutions: Vec<Solution> = (0..2)
+      .map(|seed| find_solution_spt(&inst, Some(&mut seeded_rng(seed))))
+      .collect();
+    assert_ne!(solutions[0].start_times, solutions[1].start_times);
+    for solution in &solutions {
+      verify_solution(&inst, solution).unwrap();
+    }
+    // The same seed gives the same schedule
+    let again = find_solution_spt(&inst, Some(&mut seeded_rng(0)));
+    assert_eq!(again.start_times, solutions[0].start_times);
+  }
+}
@@ -1,83 +1,447 @@
-use crate::data::{Instance, Solution};
+use crate::data::{Edge, Instance, Solution};
+use crate::solver::checkpoint::Checkpointer;
+use crate::solver::clock::Stopwatch;
+use crate::solver::crossover;
+use crate::solver::hill_climber::{find_improvement, find_plateau_step, HcConfig};
+use crate::solver::objective::{compare_solutions, is_better, Constraint, Objective};
+use crate::solver::reservoir::{finish_trajectory, Trajectory};
+use crate::solver::run_options::RunOptions;
+use crate::solver::spec::{timed_zero_timeout_run, zero_timeout_solution, PriorityRule};
+use crate::solver::stall::StallDetector;
+use crate::solver::time_checkpoints::TimeCheckpoints;
 use crate::solver::{
-  generate_random_solution, get_orientation_from_schedule, n1, IntermediateSolution,
+  generate_initial_solution, is_exhausted, publish_best, seeded_rng, timed, timeout_fraction,
+  verify_step, DurationBias, IntermediateSolution, SolveStats,
 };
 use log::{debug, info, trace};
-use rand::SeedableRng;
-use rand_chacha;
-use std::time::{Duration, Instant};
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
 
 pub struct Config {
   pub timeout: Duration,
   pub seed: u64,
+  pub hill_climber: HcConfig,
+  // An iteration is a hill climbing step or a restart, the restarts are drawn from the search rng
+  pub run: RunOptions,
+  // Resample restarts that differ in fewer machine arcs from one of the recent starts
+  pub min_restart_distance: Option<usize>,
+  // Number of best distinct local optima kept for recombination, 0 disables recombination restarts
+  pub elite_pool_size: usize,
+  // Probability of restarting from a recombination of two elites (if there are at least two) instead of a random start
+  pub recombination_probability: f64,
+  // Returned instead of the first start solution for a zero timeout, which leaves no time to climb: the solution of this
+  // dispatching rule, or of the best one if None (see spec::zero_timeout_solution)
+  pub zero_timeout_rule: Option<PriorityRule>,
 }
 
-pub fn find_solution(inst: &Instance, config: &Config) -> IntermediateSolution {
-  let mut rng = rand_chacha::ChaChaRng::seed_from_u64(config.seed);
-  let mut current_solution = IntermediateSolution::new(
-    inst.clone(),
-    get_orientation_from_schedule(&inst, &generate_solution(&inst, &mut rng)),
-  );
+// Number of recent starts a restart is compared against
+const RESTART_HISTORY_SIZE: usize = 16;
+// Number of resamples after which a restart is accepted even if it is too similar
+const MAX_RESTART_ATTEMPTS: usize = 100;
+
+pub fn find_solution(inst: &Instance, config: &Config) -> (IntermediateSolution, SolveStats) {
+  if config.timeout == Duration::from_secs(0) && config.run.resume.is_none() {
+    return timed_zero_timeout_run(config.run.clock.as_ref(), || {
+      zero_timeout_solution(
+        inst,
+        config.zero_timeout_rule,
+        config.hill_climber.constraint,
+      )
+    });
+  }
+  let start = Stopwatch::start(config.run.clock.as_ref());
+  let mut rng = seeded_rng(config.seed);
+  let mut start_rng = config.run.start_seed.map(seeded_rng);
+  let mut trajectory = config
+    .run
+    .trajectory_capacity
+    .map(|capacity| Trajectory::new(capacity, config.seed));
+  let mut restart_history = VecDeque::new();
+  let mut elite_pool = ElitePool::new(config.elite_pool_size);
+  let mut construction = Duration::default();
+  let (mut current_solution, previous_stats) = match &config.run.resume {
+    Some((solution, stats)) => (solution.clone(), stats.clone()),
+    None => (
+      timed(&start, &mut construction, || {
+        generate_start(
+          inst,
+          start_rng.as_mut().unwrap_or(&mut rng),
+          config,
+          &mut restart_history,
+        )
+      }),
+      SolveStats::default(),
+    ),
+  };
   let mut best_solution = current_solution.clone();
+  let start_key = current_solution.orientation_key();
+  publish_best(&config.run.shared_best, best_solution.cmax());
+  let mut stall = StallDetector::new(config.run.stall_window, config.run.stall_epsilon);
+  stall.observe(
+    previous_stats.iterations,
+    &best_solution,
+    config.hill_climber.constraint,
+  );
+  let mut checkpointer = Checkpointer::new(config.run.checkpoint.as_ref(), &start);
+  let mut time_checkpoints = TimeCheckpoints::new(&config.run.time_checkpoints);
+  time_checkpoints.observe(&start, &best_solution, config.hill_climber.constraint);
+  let neighborhood = config
+    .hill_climber
+    .neighborhood
+    .resolve(config.hill_climber.skip_symmetric_swaps);
+  let mut plateau_steps = 0;
+  let mut plateau_visited = HashSet::new();
 
   trace!("Starting with {}", current_solution.cmax());
-  let mut iteration = 0;
-  let start = Instant::now();
-  while Instant::now().duration_since(start) < config.timeout {
-    let maybe_move = n1::find_move(
-      &current_solution,
-      &mut |maybe_best, candidate| {
-        if let Some(best) = maybe_best {
-          candidate.cmax < best.cmax
-        } else {
-          true
-        }
-      },
-      n1::SearchMethod::Exhaustive,
-    );
-    let maybe_improvement = maybe_move.filter(|m| m.cmax < current_solution.cmax());
-
-    if let Some(next_move) = maybe_improvement {
-      let swap_move = next_move.swap_move;
-      current_solution = current_solution.apply_swap(swap_move.0, swap_move.1);
+  let mut iteration = previous_stats.iterations;
+  let mut search = Duration::default();
+  let stats_at = |iteration: u64, construction: Duration, search: Duration| SolveStats {
+    iterations: iteration,
+    elapsed: previous_stats.elapsed + start.elapsed(),
+    construction: previous_stats.construction + construction,
+    search: previous_stats.search + search,
+    ..SolveStats::default()
+  };
+  let mut improvements = Vec::new();
+  if config.run.collect_improvements {
+    improvements.push((iteration, best_solution.cmax(), best_solution.to_solution()));
+  }
+  while !is_exhausted(&start, config.timeout, config.run.max_iterations, iteration)
+    && !stall.is_stalled(iteration)
+  {
+    if let Some(next_solution) = timed(&start, &mut search, || {
+      find_improvement(
+        &current_solution,
+        neighborhood.as_ref(),
+        &config.hill_climber,
+      )
+    }) {
+      current_solution = next_solution;
+      plateau_steps = 0;
+      plateau_visited.clear();
       trace!(
         "Found improvement to {} ({})",
         current_solution.cmax(),
         iteration
       );
+    } else if let Some(next_solution) = timed(&start, &mut search, || {
+      find_plateau_step(
+        &current_solution,
+        neighborhood.as_ref(),
+        &config.hill_climber,
+        &mut plateau_steps,
+        &mut plateau_visited,
+      )
+    }) {
+      current_solution = next_solution;
+      trace!(
+        "Moved along plateau at {} ({})",
+        current_solution.cmax(),
+        iteration
+      );
     } else {
       trace!(
         "Did not find improvement over {}, resetting ({})",
         current_solution.cmax(),
         iteration
       );
-      current_solution = IntermediateSolution::new(
-        inst.clone(),
-        get_orientation_from_schedule(&inst, &generate_solution(&inst, &mut rng)),
+      elite_pool.insert(
+        &current_solution,
+        config.hill_climber.secondary_objective,
+        config.hill_climber.constraint,
       );
+      let recombined = if elite_pool.len() >= 2 && config.recombination_probability > 0.0 {
+        rng.gen_range(0.0, 1.0) < config.recombination_probability
+      } else {
+        false
+      };
+      current_solution = timed(&start, &mut construction, || {
+        if recombined {
+          elite_pool.recombine(inst, &mut rng)
+        } else {
+          generate_start(inst, &mut rng, config, &mut restart_history)
+        }
+      });
+      if recombined {
+        trace!(
+          "Restarting from recombination of elites at {} ({})",
+          current_solution.cmax(),
+          iteration
+        );
+      }
+      plateau_steps = 0;
+      plateau_visited.clear();
     }
+    verify_step(config.hill_climber.verify, inst, || {
+      current_solution.to_solution()
+    });
 
-    if current_solution.cmax() < best_solution.cmax() {
+    if is_better(
+      &current_solution,
+      &best_solution,
+      config.hill_climber.secondary_objective,
+      config.hill_climber.constraint,
+    ) {
       best_solution = current_solution.clone();
+      publish_best(&config.run.shared_best, best_solution.cmax());
+      stall.observe(iteration, &best_solution, config.hill_climber.constraint);
       debug!(
         "Found global improvement to {} ({})",
         best_solution.cmax(),
         iteration
       );
+      if config.run.collect_improvements {
+        improvements.push((iteration, best_solution.cmax(), best_solution.to_solution()));
+      }
     }
+    if let Some(trajectory) = &mut trajectory {
+      trajectory.offer(iteration, current_solution.cmax());
+    }
+    time_checkpoints.observe(&start, &best_solution, config.hill_climber.constraint);
+    checkpointer.maybe_save(&best_solution, || stats_at(iteration, construction, search));
 
     iteration += 1;
   }
 
-  info!(
-    "Stopping due to timeout at {} ({})",
-    best_solution.cmax(),
-    iteration
-  );
+  if stall.is_stalled(iteration) {
+    info!(
+      "Stopping because the best stalled at {} ({})",
+      best_solution.cmax(),
+      iteration
+    );
+  } else {
+    info!(
+      "Stopping due to timeout at {} ({})",
+      best_solution.cmax(),
+      iteration
+    );
+  }
+
+  let mut stats = stats_at(iteration, construction, search);
+  stats.timeout_fraction_used = timeout_fraction(start.elapsed(), config.timeout);
+  checkpointer.save(&best_solution, &stats);
+  stats.improvements = improvements;
+  stats.trajectory = finish_trajectory(trajectory, iteration, best_solution.cmax());
+  stats.orientation_distance_from_start = best_solution.arc_distance(&start_key);
+  stats.checkpoint_cmax = time_checkpoints.finish(&best_solution);
 
-  return best_solution;
+  return (best_solution, stats);
 }
 
-fn generate_solution<R: rand::Rng>(inst: &Instance, rng: &mut R) -> Solution {
-  generate_random_solution(inst, rng)
+// Best distinct local optima found so far, sorted from best to worst
+struct ElitePool {
+  capacity: usize,
+  elites: Vec<(Vec<Edge>, IntermediateSolution)>,
+}
+
+impl ElitePool {
+  fn new(capacity: usize) -> Self {
+    return ElitePool {
+      capacity: capacity,
+      elites: Vec::with_capacity(capacity),
+    };
+  }
+
+  fn len(&self) -> usize {
+    return self.elites.len();
+  }
+
+  // Keeps the solution if its orientation is not in the pool yet and it is better than the worst elite (or the pool is not full)
+  fn insert(
+    &mut self,
+    solution: &IntermediateSolution,
+    secondary_objective: Option<Objective>,
+    constraint: Option<Constraint>,
+  ) {
+    if self.capacity == 0 {
+      return;
+    }
+    let key = solution.orientation_key();
+    if self.elites.iter().any(|(elite_key, _)| *elite_key == key) {
+      return;
+    }
+
+    let position = self
+      .elites
+      .iter()
+      .position(|(_, elite)| {
+        compare_solutions(solution, elite, secondary_objective, constraint) == Ordering::Less
+      })
+      .unwrap_or(self.elites.len());
+    if position < self.capacity {
+      self.elites.insert(position, (key, solution.clone()));
+      self.elites.truncate(self.capacity);
+    }
+  }
+
+  // Child of two distinct uniformly chosen elites, requires at least two elites
+  fn recombine<R: rand::Rng>(&self, inst: &Instance, rng: &mut R) -> IntermediateSolution {
+    let a = rng.gen_range(0, self.elites.len());
+    let b = (a + rng.gen_range(1, self.elites.len())) % self.elites.len();
+    let child = crossover::gox(inst, &self.elites[a].0, &self.elites[b].0, rng);
+    return IntermediateSolution::new(inst.clone(), child);
+  }
+}
+
+// Random start which (if configured) differs sufficiently from the recent starts
+fn generate_start<R: rand::Rng>(
+  inst: &Instance,
+  rng: &mut R,
+  config: &Config,
+  restart_history: &mut VecDeque<Vec<Edge>>,
+) -> IntermediateSolution {
+  let mut attempt = 0;
+  loop {
+    let candidate = IntermediateSolution::from_solution(
+      inst,
+      &generate_solution(inst, rng, config.run.init_bias),
+    )
+    .expect("Generated solution is invalid");
+    attempt += 1;
+
+    if let Some(min_distance) = config.min_restart_distance {
+      let too_similar = restart_history
+        .iter()
+        .any(|start| candidate.arc_distance(start) < min_distance);
+      if too_similar && attempt < MAX_RESTART_ATTEMPTS {
+        trace!(
+          "Rejected restart too similar to a recent start ({})",
+          attempt
+        );
+        continue;
+      }
+      if too_similar {
+        debug!(
+          "Accepting restart after {} attempts without sufficiently different start",
+          attempt
+        );
+      }
+
+      restart_history.push_back(candidate.orientation_key());
+      if restart_history.len() > RESTART_HISTORY_SIZE {
+        restart_history.pop_front();
+      }
+    }
+
+    return candidate;
+  }
+}
+
+fn generate_solution<R: rand::Rng>(
+  inst: &Instance,
+  rng: &mut R,
+  bias: Option<DurationBias>,
+) -> Solution {
+  generate_initial_solution(inst, rng, bias)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{generate_random_solution_seeded, verify_solution};
+  use crate::test_instances::ft06;
+
+  fn config(seed: u64, iterations: u64) -> Config {
+    return Config {
+      timeout: Duration::from_secs(u64::MAX),
+      seed: seed,
+      hill_climber: HcConfig::default(),
+      run: RunOptions {
+        max_iterations: Some(iterations),
+        ..RunOptions::default()
+      },
+      min_restart_distance: None,
+      elite_pool_size: 0,
+      recombination_probability: 0.0,
+      zero_timeout_rule: None,
+    };
+  }
+
+  // Distances between consecutive starts of 20 restarts
+  fn restart_distances(config: &Config) -> Vec<usize> {
+    let inst = ft06();
+    let mut rng = seeded_rng(0);
+    let mut restart_history = VecDeque::new();
+    let starts: Vec<IntermediateSolution> = (0..20)
+      .map(|_| generate_start(&inst, &mut rng, config, &mut restart_history))
+      .collect();
+    return starts
+      .windows(2)
+      .map(|pair| pair[1].arc_distance(&pair[0].orientation_key()))
+      .collect();
+  }
+
+  #[test]
+  fn restarts_keep_their_distance() {
+    // Random starts of ft06 typically differ in 20 to 27 of the 90 machine arcs
+    let min_distance = 22;
+    assert!(restart_distances(&config(0, 0))
+      .iter()
+      .any(|&distance| distance < min_distance));
+    let config = Config {
+      min_restart_distance: Some(min_distance),
+      ..config(0, 0)
+    };
+    assert!(restart_distances(&config)
+      .iter()
+      .all(|&distance| distance >= min_distance));
+  }
+
+  #[test]
+  fn fixed_start_seed() {
+    let inst = ft06();
+    let fixed_start = generate_random_solution_seeded(&inst, 7);
+    for seed in 0..2 {
+      let config = Config {
+        run: RunOptions {
+          start_seed: Some(7),
+          collect_improvements: true,
+          max_iterations: Some(10),
+          ..RunOptions::default()
+        },
+        ..config(seed, 10)
+      };
+      let (_, stats) = find_solution(&inst, &config);
+      assert_eq!(stats.improvements[0].2.start_times, fixed_start.start_times);
+    }
+  }
+
+  #[test]
+  fn elite_pool() {
+    let inst = ft06();
+    let mut pool = ElitePool::new(4);
+    let mut rng = seeded_rng(0);
+    for seed in 0..30 {
+      // Every solution is offered twice
+      let solution = IntermediateSolution::from_solution(
+        &inst,
+        &generate_random_solution_seeded(&inst, seed / 2),
+      )
+      .unwrap();
+      pool.insert(&solution, None, None);
+      assert!(pool.len() <= 4);
+      let keys: HashSet<&Vec<Edge>> = pool.elites.iter().map(|(key, _)| key).collect();
+      assert_eq!(keys.len(), pool.len());
+      assert!(pool
+        .elites
+        .windows(2)
+        .all(|pair| pair[0].1.cmax() <= pair[1].1.cmax()));
+
+      if pool.len() >= 2 {
+        let child = pool.recombine(&inst, &mut rng);
+        verify_solution(&inst, &child.to_solution()).unwrap();
+      }
+    }
+    assert_eq!(pool.len(), 4);
+
+    let config = Config {
+      elite_pool_size: 4,
+      recombination_probability: 1.0,
+      ..config(0, 200)
+    };
+    let (best, _) = find_solution(&inst, &config);
+    verify_solution(&inst, &best.to_solution()).unwrap();
+  }
 }
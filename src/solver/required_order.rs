@@ -0,0 +1,203 @@
+use crate::data::{Edge, Instance, Machine, OpId, Solution};
+use crate::solver::{
+  dispatch_orientation, get_orientation_from_schedule, get_pre_succ_relations,
+  is_acyclic_orientation,
+};
+use itertools::Itertools;
+use ndarray::Array1;
+use std::fmt;
+use std::str::FromStr;
+
+// Jobs that have to be processed in this relative order on the machine, e.g. from expert knowledge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequiredOrder {
+  pub machine: Machine,
+  pub before_job: usize,
+  pub after_job: usize,
+}
+
+impl FromStr for RequiredOrder {
+  type Err = String;
+
+  // Parses machine,before_job,after_job e.g. 2,0,3
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let fields: Vec<&str> = s.split(',').collect();
+    if fields.len() != 3 {
+      return Err(format!(
+        "Invalid required order {}: expected machine,before_job,after_job",
+        s
+      ));
+    }
+    let parse = |field: &str| {
+      field
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| format!("Invalid required order {}: {} is not an index", s, field))
+    };
+
+    return Ok(RequiredOrder {
+      machine: parse(fields[0])?,
+      before_job: parse(fields[1])?,
+      after_job: parse(fields[2])?,
+    });
+  }
+}
+
+// As parsed, machine,before_job,after_job
+impl fmt::Display for RequiredOrder {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    return write!(f, "{},{},{}", self.machine, self.before_job, self.after_job);
+  }
+}
+
+// Pairs (a, b) of operations on the same machine where a has to precede b, every operation of the before job on the
+// machine precedes every operation of the after job on it
+// Fails for unknown machines or jobs, jobs not visiting the machine and orders that contradict each other or the job
+// precedences, i.e. where no schedule satisfies all of them
+pub fn required_edges(inst: &Instance, orders: &[RequiredOrder]) -> Result<Vec<Edge>, String> {
+  let mut edges = Vec::new();
+  for order in orders {
+    if order.machine >= inst.n_machines {
+      return Err(format!(
+        "Machine {} of required order {} out of range (n_machines={})",
+        order.machine, order, inst.n_machines
+      ));
+    }
+    if order.before_job == order.after_job {
+      return Err(format!(
+        "Required order {} orders a job before itself",
+        order
+      ));
+    }
+    let ops_on_machine = |job: usize| -> Result<Vec<OpId>, String> {
+      if job >= inst.n_jobs {
+        return Err(format!(
+          "Job {} of required order {} out of range (n_jobs={})",
+          job, order, inst.n_jobs
+        ));
+      }
+      let ops: Vec<OpId> = (0..inst.job_len(job))
+        .map(|o| inst.op_to_id([job, o]))
+        .filter(|&op| inst.machines[op] == order.machine)
+        .collect();
+      if ops.is_empty() {
+        return Err(format!(
+          "Job {} of required order {} has no operation on machine {}",
+          job, order, order.machine
+        ));
+      }
+      return Ok(ops);
+    };
+    for (a, b) in ops_on_machine(order.before_job)?
+      .into_iter()
+      .cartesian_product(ops_on_machine(order.after_job)?)
+    {
+      if !edges.contains(&(a, b)) {
+        edges.push((a, b));
+      }
+    }
+  }
+
+  // Together with the job precedences the required edges have to be acyclic, then any topological order gives
+  // machine sequences satisfying all of them
+  if !is_acyclic_orientation(inst, &edges) {
+    return Err(
+      "Required orders are inconsistent: they form a cycle with each other or the job precedences"
+        .to_string(),
+    );
+  }
+
+  return Ok(edges);
+}
+
+// Whether a precedes b on its machine for every required edge (a, b) of the complete orientation
+pub fn satisfies_required_edges(
+  inst: &Instance,
+  oriented_conflict_edges: &[Edge],
+  required_edges: &[Edge],
+) -> bool {
+  let position = machine_positions(inst, oriented_conflict_edges);
+  return required_edges
+    .iter()
+    .all(|&(a, b)| position[a] < position[b]);
+}
+
+// Orientation of the solution, or if it violates a required edge the orientation of dispatching the operations in the
+// order of their start times in the solution with the required edges (see dispatch_orientation), so the machine
+// sequences change as little as possible
+pub fn repair_orientation(
+  inst: &Instance,
+  solution: &Solution,
+  required_edges: &[Edge],
+) -> Vec<Edge> {
+  let orientation = get_orientation_from_schedule(inst, solution);
+  if satisfies_required_edges(inst, &orientation, required_edges) {
+    return orientation;
+  }
+
+  return dispatch_orientation(inst, &solution.start_times, required_edges);
+}
+
+fn machine_positions(inst: &Instance, oriented_conflict_edges: &[Edge]) -> Array1<usize> {
+  let (pre_machine, _) = get_pre_succ_relations(inst, &oriented_conflict_edges.to_vec());
+  let mut position = Array1::<usize>::from_elem(inst.n_ops(), 0);
+  for op in 0..inst.n_ops() {
+    let mut pre = pre_machine[op];
+    while let Some(p) = pre {
+      position[op] += 1;
+      pre = pre_machine[p];
+    }
+  }
+  return position;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_instances::ft06;
+
+  fn order(machine: Machine, before_job: usize, after_job: usize) -> RequiredOrder {
+    return RequiredOrder {
+      machine: machine,
+      before_job: before_job,
+      after_job: after_job,
+    };
+  }
+
+  #[test]
+  fn inconsistent_orders_are_rejected() {
+    let inst = ft06();
+    assert_eq!(
+      required_edges(&inst, &[order(0, 1, 2), order(0, 2, 3)])
+        .unwrap()
+        .len(),
+      2
+    );
+    let cycle = [order(0, 1, 2), order(0, 2, 3), order(0, 3, 1)];
+    assert!(required_edges(&inst, &cycle)
+      .unwrap_err()
+      .contains("inconsistent"));
+
+    // Job 0 visits machine 2 before machine 0 and job 1 visits machine 0 before machine 2 (after machine 1), so job 1
+    // first on machine 2 and job 0 first on machine 0 contradict the job precedences
+    let inst = Instance::from_jobs(3, &[vec![(2, 1), (0, 1)], vec![(1, 1), (0, 1), (2, 1)]]);
+    assert!(required_edges(&inst, &[order(2, 1, 0)]).is_ok());
+    assert!(required_edges(&inst, &[order(0, 0, 1)]).is_ok());
+    assert!(required_edges(&inst, &[order(2, 1, 0), order(0, 0, 1)])
+      .unwrap_err()
+      .contains("inconsistent"));
+
+    assert!(required_edges(&inst, &[order(3, 0, 1)])
+      .unwrap_err()
+      .contains("out of range"));
+    assert!(required_edges(&inst, &[order(0, 0, 2)])
+      .unwrap_err()
+      .contains("out of range"));
+    assert!(required_edges(&inst, &[order(1, 0, 1)])
+      .unwrap_err()
+      .contains("no operation on machine 1"));
+    assert!(required_edges(&inst, &[order(0, 1, 1)])
+      .unwrap_err()
+      .contains("before itself"));
+  }
+}
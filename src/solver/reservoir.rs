@@ -0,0 +1,130 @@
+use crate::solver::seeded_rng;
+use rand::Rng;
+use rand_chacha;
+
+// Mixed into the search seed, so the sampling rng does not draw the same numbers as the search rng
+const SEED_MIX: u64 = 0x5bd1_e995_9e37_79b9;
+
+// Uniform sample of bounded size from a stream of unknown length
+// Vitter, “Random Sampling with a Reservoir.” (algorithm R)
+#[derive(Debug, Clone)]
+pub struct Reservoir<T> {
+  capacity: usize,
+  // Number of offered items
+  seen: u64,
+  samples: Vec<T>,
+  // Independent of the search rng, so sampling does not change the search for a given seed
+  rng: rand_chacha::ChaChaRng,
+}
+
+impl<T> Reservoir<T> {
+  pub fn new(capacity: usize, seed: u64) -> Self {
+    return Reservoir {
+      capacity: capacity,
+      seen: 0,
+      samples: Vec::with_capacity(capacity),
+      rng: seeded_rng(seed ^ SEED_MIX),
+    };
+  }
+
+  // Every offered item is retained with probability capacity / seen
+  pub fn offer(&mut self, item: T) {
+    self.seen += 1;
+    if self.samples.len() < self.capacity {
+      self.samples.push(item);
+      return;
+    }
+
+    let index = self.rng.gen_range(0, self.seen);
+    if index < self.capacity as u64 {
+      self.samples[index as usize] = item;
+    }
+  }
+
+  pub fn into_samples(self) -> Vec<T> {
+    return self.samples;
+  }
+}
+
+// (iteration, cmax of the current solution) pairs of a search, at most capacity including the final pair
+// One slot is reserved for the final pair, so the sample of the other pairs stays uniform
+#[derive(Debug, Clone)]
+pub(crate) struct Trajectory {
+  capacity: usize,
+  samples: Reservoir<(u64, u32)>,
+}
+
+impl Trajectory {
+  pub(crate) fn new(capacity: usize, seed: u64) -> Self {
+    return Trajectory {
+      capacity: capacity,
+      samples: Reservoir::new(capacity.saturating_sub(1), seed),
+    };
+  }
+
+  pub(crate) fn offer(&mut self, iteration: u64, cmax: u32) {
+    self.samples.offer((iteration, cmax));
+  }
+
+  // Samples sorted by iteration, followed by the final iteration count with the best cmax
+  pub(crate) fn finish(self, iterations: u64, best_cmax: u32) -> Vec<(u64, u32)> {
+    let mut samples = self.samples.into_samples();
+    samples.sort_unstable();
+    if self.capacity > 0 {
+      samples.push((iterations, best_cmax));
+    }
+    return samples;
+  }
+}
+
+pub(crate) fn finish_trajectory(
+  trajectory: Option<Trajectory>,
+  iterations: u64,
+  best_cmax: u32,
+) -> Vec<(u64, u32)> {
+  return trajectory.map_or(Vec::new(), |trajectory| {
+    trajectory.finish(iterations, best_cmax)
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cmp;
+
+  #[test]
+  fn bounded_with_the_final_pair() {
+    for capacity in 0..5 {
+      for length in 0..10 {
+        let mut trajectory = Trajectory::new(capacity, 0);
+        for iteration in 0..length {
+          trajectory.offer(iteration, 100 - iteration as u32);
+        }
+        let samples = trajectory.finish(length, 42);
+        assert!(samples.len() <= capacity);
+        if capacity > 0 {
+          assert_eq!(samples.last(), Some(&(length, 42)));
+          assert_eq!(samples.len(), cmp::min(capacity, length as usize + 1));
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn uniform_sample() {
+    // Every one of 10 items is retained with probability 3 / 10
+    let mut counts = [0; 10];
+    for seed in 0..3000 {
+      let mut reservoir = Reservoir::new(3, seed);
+      for item in 0..10 {
+        reservoir.offer(item);
+      }
+      for item in reservoir.into_samples() {
+        counts[item] += 1;
+      }
+    }
+    for count in counts.iter() {
+      assert!((800..1000).contains(count), "{:?}", counts);
+    }
+  }
+}
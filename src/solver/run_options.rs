@@ -0,0 +1,53 @@
+use crate::solver::checkpoint::CheckpointConfig;
+use crate::solver::clock::Clock;
+use crate::solver::{DurationBias, IntermediateSolution, SolveStats};
+use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Options of a metaheuristic run that do not change its search, shared by the configs of the tabu search, simulated
+// annealing and the random restart hill climber (what counts as an iteration or a start solution is documented there)
+pub struct RunOptions {
+  // Continue from a previously saved best solution and statistics
+  pub resume: Option<(IntermediateSolution, SolveStats)>,
+  pub checkpoint: Option<CheckpointConfig>,
+  // Updated whenever the best solution improves, allows polling the progress from other threads
+  pub shared_best: Option<Arc<AtomicU32>>,
+  // Collect every new global best solution in the returned stats
+  pub collect_improvements: bool,
+  // Sample the cmax of the current solution at every iteration into the returned stats, keeping at most this many samples
+  pub trajectory_capacity: Option<usize>,
+  // Record the best cmax in the returned stats whenever the elapsed time of this run passes one of these
+  pub time_checkpoints: Vec<Duration>,
+  // Stop after this many iterations (counting those of a resumed run) even if the timeout is not reached
+  pub max_iterations: Option<u64>,
+  // Stop early once the best cmax (of feasible solutions) improved by at most stall_epsilon (relative to the cmax at the
+  // window start) during the last stall_window iterations
+  pub stall_window: Option<u64>,
+  pub stall_epsilon: f64,
+  // Bias of the random start solutions, uniform if None
+  pub init_bias: Option<DurationBias>,
+  // Seed of the rng drawing the first start solution (unused when resuming) instead of the search rng
+  pub start_seed: Option<u64>,
+  // Measures the timeout, defaults to the system clock
+  pub clock: Option<Arc<dyn Clock>>,
+}
+
+impl Default for RunOptions {
+  fn default() -> Self {
+    return RunOptions {
+      resume: None,
+      checkpoint: None,
+      shared_best: None,
+      collect_improvements: false,
+      trajectory_capacity: None,
+      time_checkpoints: vec![],
+      max_iterations: None,
+      stall_window: None,
+      stall_epsilon: 0.0,
+      init_bias: None,
+      start_seed: None,
+      clock: None,
+    };
+  }
+}
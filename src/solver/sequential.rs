@@ -6,7 +6,7 @@ pub fn find_solution(inst: &Instance) -> Solution {
 
   let mut next_start_time = 0;
   for j in 0..inst.n_jobs {
-    for o in 0..inst.n_machines {
+    for o in 0..inst.job_len(j) {
       let op = [j, o];
       let op_id = inst.op_to_id(op);
       let start = next_start_time;
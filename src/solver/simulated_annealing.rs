@@ -1,88 +1,284 @@
 use crate::data::Instance;
+use crate::solver::checkpoint::Checkpointer;
+use crate::solver::clock::Stopwatch;
+use crate::solver::neighborhood::{EvaluatedMove, Neighborhood, NeighborhoodKind};
+use crate::solver::objective::{is_better, move_violation, violation, Constraint, Objective};
+use crate::solver::reservoir::{finish_trajectory, Trajectory};
+use crate::solver::run_options::RunOptions;
+use crate::solver::spec::{timed_zero_timeout_run, zero_timeout_solution, PriorityRule};
+use crate::solver::stall::StallDetector;
+use crate::solver::time_checkpoints::TimeCheckpoints;
 use crate::solver::{
-  generate_random_solution, get_orientation_from_schedule, n1, IntermediateSolution,
+  generate_initial_solution, is_exhausted, publish_best, seeded_rng, timed, timeout_fraction,
+  verify_step, DurationBias, IntermediateSolution, SolveStats,
 };
 use log::{debug, info, trace};
-use rand::seq::IteratorRandom;
-use rand::{Rng, SeedableRng};
-use rand_chacha;
+use rand::Rng;
 use std::cmp;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 pub struct Config {
   pub timeout: Duration,
   pub seed: u64,
   pub start_acceptance_ratio: f64,
   pub delta: f64,
+  // End a cycle once the temperature drops below this value
+  pub min_temperature: Option<f64>,
+  // Verify every accepted solution (expensive)
+  pub verify: bool,
+  // Decide acceptance by comparing 32-bit fixed-point numbers instead of floats, so that the decisions for a given
+  // temperature do not depend on the platform's exp implementation (changes the results for a given seed)
+  pub deterministic_acceptance: bool,
+  // Estimate the initial temperature anew at the start of every cycle (30 trial solutions each), otherwise every cycle
+  // starts at the temperature estimated for the first one
+  pub reestimate_temperature_each_cycle: bool,
+  // Start every cycle at this temperature instead of estimating one (start_acceptance_ratio is ignored then)
+  pub initial_temperature: Option<f64>,
+  // An iteration is a trial move (max_iterations also stops within a temperature step), start_seed only draws the start
+  // solution of the first cycle unless warm started, later cycles start from solutions drawn from the search rng
+  pub run: RunOptions,
+  // Start the first cycle from this solution instead of a random one (also the initial best unless resuming)
+  pub warm_start: Option<IntermediateSolution>,
+  pub neighborhood: NeighborhoodKind,
+  // See NeighborhoodKind::resolve, such swaps would be accepted like any move of equal cmax
+  pub skip_symmetric_swaps: bool,
+  // Breaks ties between (local and global) best solutions of equal cmax
+  pub secondary_objective: Option<Objective>,
+  // Moves of feasible solutions have to satisfy the constraint, infeasible solutions only accept moves not increasing the violation
+  pub constraint: Option<Constraint>,
+  // Dispatching rule constructing the result of a zero timeout, which leaves no time for a single cycle (the best rule
+  // if None, see spec::zero_timeout_solution), unless resuming or warm started
+  pub zero_timeout_rule: Option<PriorityRule>,
 }
 
-pub fn find_solution(inst: &Instance, config: &Config) -> IntermediateSolution {
-  let mut rng = rand_chacha::ChaChaRng::seed_from_u64(config.seed);
+// Neighborhood used if none is given, the larger and noisier neighborhood helps the random walk
+pub const DEFAULT_NEIGHBORHOOD: NeighborhoodKind = NeighborhoodKind::N1Full;
 
-  let mut best = generate_solution(inst, &mut rng);
+// Start acceptance ratio used if none is given
+pub const DEFAULT_START_ACCEPTANCE_RATIO: f64 = 0.9;
+
+// Delta used if none is given, 10 / n_ops (at most 1), so larger instances are cooled more slowly
+pub fn default_delta(inst: &Instance) -> f64 {
+  return f64::min(1.0, 10.0 / cmp::max(inst.n_ops(), 1) as f64);
+}
+
+pub fn find_solution(inst: &Instance, config: &Config) -> (IntermediateSolution, SolveStats) {
+  if config.timeout == Duration::from_secs(0)
+    && config.run.resume.is_none()
+    && config.warm_start.is_none()
+  {
+    return timed_zero_timeout_run(config.run.clock.as_ref(), || {
+      zero_timeout_solution(inst, config.zero_timeout_rule, config.constraint)
+    });
+  }
+  let start = Stopwatch::start(config.run.clock.as_ref());
+  let mut rng = seeded_rng(config.seed);
+  let mut start_rng = config.run.start_seed.map(seeded_rng);
+  let mut trajectory = config
+    .run
+    .trajectory_capacity
+    .map(|capacity| Trajectory::new(capacity, config.seed));
+
+  let mut construction = Duration::default();
+  let (mut best, previous_stats) = match (&config.run.resume, &config.warm_start) {
+    (Some((solution, stats)), _) => (solution.clone(), stats.clone()),
+    (None, Some(solution)) => (solution.clone(), SolveStats::default()),
+    (None, None) => (
+      timed(&start, &mut construction, || {
+        generate_solution(
+          inst,
+          start_rng.as_mut().unwrap_or(&mut rng),
+          config.run.init_bias,
+        )
+      }),
+      SolveStats::default(),
+    ),
+  };
+  let start_key = best.orientation_key();
+  publish_best(&config.run.shared_best, best.cmax());
+  let mut stall = StallDetector::new(config.run.stall_window, config.run.stall_epsilon);
+  stall.observe(previous_stats.iterations, &best, config.constraint);
+  let mut checkpointer = Checkpointer::new(config.run.checkpoint.as_ref(), &start);
+  let mut time_checkpoints = TimeCheckpoints::new(&config.run.time_checkpoints);
+  time_checkpoints.observe(&start, &best, config.constraint);
+  let neighborhood = config.neighborhood.resolve(config.skip_symmetric_swaps);
 
   // Cruz-Chavez and Frausto-Solis, “Simulated Annealing with Restart to Job Shop Scheduling Problem Using Upper Bounds.”
-  let start = Instant::now();
   let mut global_iteration = 0;
-  while Instant::now().duration_since(start) < config.timeout {
-    let improved = run_sa(inst, &mut rng, &mut global_iteration, &start, config);
+  // The first cycle starts from the warm start or fixed start solution (if any), later cycles from random solutions
+  let mut fixed_start = match (
+    &config.warm_start,
+    config.run.start_seed,
+    &config.run.resume,
+  ) {
+    (Some(solution), _, _) => Some(solution.clone()),
+    (None, Some(_), None) => Some(best.clone()),
+    _ => None,
+  };
+  // Initial temperature of the first cycle (of this run, a resumed run estimates it again)
+  let mut estimated_temperature = None;
+  let mut stats = previous_stats.clone();
+  stats.construction += construction;
+  if config.run.collect_improvements {
+    stats
+      .improvements
+      .push((stats.iterations, best.cmax(), best.to_solution()));
+  }
+  while !is_exhausted(
+    &start,
+    config.timeout,
+    config.run.max_iterations,
+    stats.iterations,
+  ) && !stall.is_stalled(stats.iterations)
+  {
+    let improved = run_sa(
+      inst,
+      fixed_start.take(),
+      neighborhood.as_ref(),
+      &mut rng,
+      &mut estimated_temperature,
+      &mut global_iteration,
+      &mut stats,
+      &mut trajectory,
+      &mut time_checkpoints,
+      &mut stall,
+      &start,
+      config,
+    );
 
-    if improved.cmax() < best.cmax() {
+    if is_better(
+      &improved,
+      &best,
+      config.secondary_objective,
+      config.constraint,
+    ) {
       best = improved;
+      publish_best(&config.run.shared_best, best.cmax());
       debug!(
         "Improved global best to {} (iteration {})",
         best.cmax(),
         global_iteration
       );
+      if config.run.collect_improvements {
+        stats
+          .improvements
+          .push((stats.iterations, best.cmax(), best.to_solution()));
+      }
     }
+    checkpointer.maybe_save(&best, || {
+      let elapsed = previous_stats.elapsed + start.elapsed();
+      SolveStats {
+        iterations: stats.iterations,
+        elapsed: elapsed,
+        construction: stats.construction,
+        search: stats.search,
+        ..SolveStats::default()
+      }
+    });
 
     global_iteration += 1;
   }
 
-  info!(
-    "Stopping due to timeout at {} (iteration {})",
-    best.cmax(),
-    global_iteration
-  );
+  if stall.is_stalled(stats.iterations) {
+    info!(
+      "Stopping because the best stalled at {} (iteration {})",
+      best.cmax(),
+      global_iteration
+    );
+  } else {
+    info!(
+      "Stopping due to timeout at {} (iteration {})",
+      best.cmax(),
+      global_iteration
+    );
+  }
 
-  return best;
+  stats.elapsed = previous_stats.elapsed + start.elapsed();
+  stats.timeout_fraction_used = timeout_fraction(start.elapsed(), config.timeout);
+  checkpointer.save(&best, &stats);
+  stats.trajectory = finish_trajectory(trajectory, stats.iterations, best.cmax());
+  stats.orientation_distance_from_start = best.arc_distance(&start_key);
+  stats.checkpoint_cmax = time_checkpoints.finish(&best);
+
+  return (best, stats);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_sa<R: Rng>(
   inst: &Instance,
+  cycle_start: Option<IntermediateSolution>,
+  neighborhood: &dyn Neighborhood,
   rng: &mut R,
+  estimated_temperature: &mut Option<f64>,
   global_iteration: &mut u64,
-  start: &Instant,
+  stats: &mut SolveStats,
+  trajectory: &mut Option<Trajectory>,
+  time_checkpoints: &mut TimeCheckpoints,
+  stall: &mut StallDetector,
+  start: &Stopwatch,
   config: &Config,
 ) -> IntermediateSolution {
-  let mut current = generate_solution(inst, rng);
-  let mut current_neighborhood = n1::generate_moves(&current);
+  let mut current = match cycle_start {
+    Some(solution) => solution,
+    None => timed(start, &mut stats.construction, || {
+      generate_solution(inst, rng, config.run.init_bias)
+    }),
+  };
+  let mut current_neighborhood = neighborhood.generate_moves(&current);
   let mut best = current.clone();
 
   let start_acceptance_ratio = config.start_acceptance_ratio;
   let delta = config.delta;
   // Size of largest possible neighborhood
   // From Van Laarhoven, Aarts, and Lenstra, “Job Shop Scheduling by Simulated Annealing.”
-  let equilibrium_iterations = cmp::max(inst.n_ops().checked_sub(inst.n_machines).unwrap(), 1);
+  let equilibrium_iterations = cmp::max(inst.n_ops().saturating_sub(inst.n_machines), 1);
 
   // Aarts and Van Laarhoven, "Statistical Cooling."
-  let initial_temperature = estimate_initial_temperature(inst, rng, start_acceptance_ratio);
+  // Only samples random solutions, so it is accounted as construction
+  let initial_temperature = match (config.initial_temperature, *estimated_temperature) {
+    (Some(temperature), _) => temperature,
+    (None, Some(temperature)) if !config.reestimate_temperature_each_cycle => temperature,
+    _ => {
+      let temperature = timed(start, &mut stats.construction, || {
+        estimate_initial_temperature(inst, neighborhood, rng, start_acceptance_ratio)
+      });
+      debug!("Estimated initial temperature {}", temperature);
+      stats.temperature_estimations += 1;
+      *estimated_temperature = Some(temperature);
+      temperature
+    }
+  };
   let mut temperature = initial_temperature;
+  let mut current_violation = violation(&current, config.constraint);
   debug!(
     "Starting with cmax {}, temp {}, iterations {}",
     current.cmax(),
     temperature,
     equilibrium_iterations
   );
-  while Instant::now().duration_since(*start) < config.timeout {
+  while !is_exhausted(
+    start,
+    config.timeout,
+    config.run.max_iterations,
+    stats.iterations,
+  ) && !stall.is_stalled(stats.iterations)
+  {
     let mut accepted_move_costs = vec![current.cmax()];
     for inner_iteration in 0..equilibrium_iterations {
       // Abort early if inner loop exceeds timeout
-      if Instant::now().duration_since(*start) >= config.timeout {
+      if is_exhausted(
+        start,
+        config.timeout,
+        config.run.max_iterations,
+        stats.iterations,
+      ) || stall.is_stalled(stats.iterations)
+      {
         break;
       }
+      stats.iterations += 1;
 
-      if let Some(next_move) = current_neighborhood.iter().choose(rng) {
+      let search_start = start.elapsed();
+      if let Some(next_move) = choose_move(&current_neighborhood, rng) {
         let cost_next = next_move.cmax as f64;
         let cost_current = current.cmax() as f64;
         let cost_delta = cost_next - cost_current;
@@ -91,13 +287,28 @@ fn run_sa<R: Rng>(
         } else {
           f64::min(1.0, (-cost_delta / temperature).exp())
         };
-        let should_accept_move = rng.gen_range(0.0, 1.0) < acceptance_threshold;
+        let candidate_violation = move_violation(&current, next_move, config.constraint);
+        let should_accept_move = if current_violation > 0 {
+          // Restore feasibility first
+          candidate_violation <= current_violation
+        } else if config.deterministic_acceptance {
+          candidate_violation == 0
+            && (rng.gen::<u32>() as u64) < fixed_point_acceptance(cost_delta, temperature)
+        } else {
+          candidate_violation == 0 && rng.gen_range(0.0, 1.0) < acceptance_threshold
+        };
         if should_accept_move {
           let swap_move = next_move.swap_move;
           let (a, b) = swap_move;
           current = current.apply_swap(a, b);
-          current_neighborhood = n1::generate_moves(&current);
+          current_violation = candidate_violation;
+          current_neighborhood = neighborhood.generate_moves(&current);
+          stats.search += start
+            .elapsed()
+            .checked_sub(search_start)
+            .unwrap_or_default();
           accepted_move_costs.push(current.cmax());
+          stall.observe(stats.iterations, &current, config.constraint);
           trace!(
             "Accepted move {:?} to {} (iteration {}-{}, temp {})",
             swap_move,
@@ -112,10 +323,12 @@ fn run_sa<R: Rng>(
             global_iteration,
             inner_iteration
           );
-          #[cfg(debug_assertions)]
-          crate::solver::verify_solution(&inst, &current.to_solution())
-            .expect("Verification failed");
+          verify_step(config.verify, inst, || current.to_solution());
         } else {
+          stats.search += start
+            .elapsed()
+            .checked_sub(search_start)
+            .unwrap_or_default();
           trace!(
             "Rejected move {:?} (iteration {}-{}, temp {})",
             next_move.swap_move,
@@ -124,6 +337,10 @@ fn run_sa<R: Rng>(
             temperature
           );
         }
+        if let Some(trajectory) = trajectory {
+          trajectory.offer(stats.iterations, current.cmax());
+        }
+        time_checkpoints.observe(start, &best, config.constraint);
       } else {
         // Should only happen when there are no candidates in the neighborhood e.g. for single machine problems
         debug!(
@@ -134,8 +351,15 @@ fn run_sa<R: Rng>(
       }
     }
 
-    if current.cmax() < best.cmax() {
+    if is_better(
+      &current,
+      &best,
+      config.secondary_objective,
+      config.constraint,
+    ) {
       best = current.clone();
+      // Local improvements are only published if they also improve the global best
+      publish_best(&config.run.shared_best, best.cmax());
       debug!(
         "Improved local best to {} (iteration {}, temp {})",
         best.cmax(),
@@ -161,6 +385,19 @@ fn run_sa<R: Rng>(
       return best;
     }
 
+    if let Some(min_temperature) = config.min_temperature {
+      if temperature < min_temperature {
+        debug!(
+          "Stopping because temp {} is below minimum {} at {} ({})",
+          temperature,
+          min_temperature,
+          best.cmax(),
+          global_iteration,
+        );
+        return best;
+      }
+    }
+
     *global_iteration += 1;
   }
 
@@ -173,13 +410,51 @@ fn run_sa<R: Rng>(
   return best;
 }
 
-fn generate_solution<R: Rng>(inst: &Instance, rng: &mut R) -> IntermediateSolution {
-  let orientation = get_orientation_from_schedule(inst, &generate_random_solution(inst, rng));
+fn generate_solution<R: Rng>(
+  inst: &Instance,
+  rng: &mut R,
+  bias: Option<DurationBias>,
+) -> IntermediateSolution {
+  return IntermediateSolution::from_solution(inst, &generate_initial_solution(inst, rng, bias))
+    .expect("Generated solution is invalid");
+}
 
-  return IntermediateSolution::new(inst.clone(), orientation);
+// Uniformly samples a move by drawing its index, consuming exactly one value from the rng per non-empty neighborhood
+// Note that this differs from the sequence drawn by IteratorRandom::choose, so results for a given seed changed with it
+fn choose_move<'a, R: Rng>(moves: &'a [EvaluatedMove], rng: &mut R) -> Option<&'a EvaluatedMove> {
+  if moves.is_empty() {
+    return None;
+  }
+  return Some(&moves[rng.gen_range(0, moves.len())]);
 }
 
-fn mean(vec: &Vec<u32>) -> Option<f64> {
+// Acceptance probability min(1, exp(-cost_delta / temperature)) scaled by 2^32
+// Only uses basic arithmetic, which IEEE 754 rounds identically on every platform (unlike exp)
+fn fixed_point_acceptance(cost_delta: f64, temperature: f64) -> u64 {
+  const ONE: u64 = 1 << 32;
+  if cost_delta <= 0.0 {
+    return ONE;
+  }
+  let x = cost_delta / temperature;
+  // Probabilities below 2^-32 round to zero (also rejects for a NaN temperature)
+  if x.is_nan() || x >= 32.0 * std::f64::consts::LN_2 {
+    return 0;
+  }
+
+  // exp(-x) = 2^-k * exp(-r) with r = x - k * ln(2) in [0, ln(2))
+  let k = (x / std::f64::consts::LN_2) as u32;
+  let r = x - k as f64 * std::f64::consts::LN_2;
+  let mut term = 1.0;
+  let mut sum = 1.0;
+  for i in 1..=20 {
+    term = term * -r / i as f64;
+    sum += term;
+  }
+
+  return cmp::min(((sum * ONE as f64) as u64) >> k, ONE);
+}
+
+fn mean(vec: &[u32]) -> Option<f64> {
   let sum: f64 = vec.iter().map(|&x| x as f64).sum();
   let count = vec.len();
 
@@ -189,7 +464,7 @@ fn mean(vec: &Vec<u32>) -> Option<f64> {
   };
 }
 
-fn std_dev(vec: &Vec<u32>) -> Option<f64> {
+fn std_dev(vec: &[u32]) -> Option<f64> {
   let mean = mean(vec)?;
   let count = vec.len();
 
@@ -202,6 +477,7 @@ fn std_dev(vec: &Vec<u32>) -> Option<f64> {
 
 fn estimate_initial_temperature<R: Rng>(
   inst: &Instance,
+  neighborhood: &dyn Neighborhood,
   rng: &mut R,
   start_acceptance_ratio: f64,
 ) -> f64 {
@@ -209,11 +485,12 @@ fn estimate_initial_temperature<R: Rng>(
   let trials = 30;
   let mut deltas = Vec::new();
   for _ in 0..trials {
-    let solution = generate_solution(inst, rng);
-    let moves = n1::generate_moves(&solution);
-    if let Some(chosen_move) = moves.iter().choose(rng) {
-      let delta = chosen_move.cmax - solution.cmax;
-      deltas.push(delta as f64);
+    // The temperature estimate always samples uniformly random solutions
+    let solution = generate_solution(inst, rng, None);
+    let moves = neighborhood.generate_moves(&solution);
+    if let Some(chosen_move) = choose_move(&moves, rng) {
+      let delta = chosen_move.cmax as f64 - solution.cmax as f64;
+      deltas.push(delta);
     }
   }
 
@@ -230,3 +507,205 @@ fn estimate_initial_temperature<R: Rng>(
   let c0 = avg_positive_delta / ((m2 / (m2 * x0 - (1.0 - x0) * m1)).ln());
   return c0;
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::generate_random_solution_seeded;
+  use crate::test_instances::{assert_improvements, ft06};
+
+  // ft06 has 36 operations on 6 machines, so every temperature step takes 30 iterations
+  const EQUILIBRIUM_ITERATIONS: u64 = 30;
+
+  fn config(iterations: u64) -> Config {
+    return Config {
+      timeout: Duration::from_secs(u64::MAX),
+      seed: 0,
+      start_acceptance_ratio: DEFAULT_START_ACCEPTANCE_RATIO,
+      delta: 0.1,
+      min_temperature: None,
+      verify: false,
+      deterministic_acceptance: false,
+      reestimate_temperature_each_cycle: true,
+      initial_temperature: None,
+      run: RunOptions {
+        max_iterations: Some(iterations),
+        ..RunOptions::default()
+      },
+      warm_start: None,
+      neighborhood: DEFAULT_NEIGHBORHOOD,
+      skip_symmetric_swaps: false,
+      secondary_objective: None,
+      constraint: None,
+      zero_timeout_rule: None,
+    };
+  }
+
+  #[test]
+  fn min_temperature_ends_the_cycle() {
+    let inst = ft06();
+    let iterations = 10 * EQUILIBRIUM_ITERATIONS;
+    let (_, stats) = find_solution(&inst, &config(iterations));
+    assert!(stats.temperature_estimations < 10);
+
+    // Every cycle estimates its initial temperature, and its first (strong) cooling step ends below the minimum
+    let config = Config {
+      delta: 1000.0,
+      min_temperature: Some(f64::MAX),
+      ..config(iterations)
+    };
+    let (_, stats) = find_solution(&inst, &config);
+    assert_eq!(stats.iterations, iterations);
+    assert_eq!(stats.temperature_estimations, 10);
+  }
+
+  #[test]
+  fn improvements_strictly_decrease() {
+    let inst = ft06();
+    let config = Config {
+      run: RunOptions {
+        collect_improvements: true,
+        max_iterations: Some(1000),
+        ..RunOptions::default()
+      },
+      ..config(1000)
+    };
+    let (best, stats) = find_solution(&inst, &config);
+    assert_improvements(&inst, &stats, &best);
+  }
+
+  #[test]
+  fn move_selection_is_uniform() {
+    let moves: Vec<EvaluatedMove> = (0..6)
+      .map(|i| EvaluatedMove {
+        swap_move: (i, i + 1),
+        cmax: 0,
+      })
+      .collect();
+    let mut rng = seeded_rng(0);
+    let mut counts = [0; 6];
+    for _ in 0..60000 {
+      let chosen = choose_move(&moves, &mut rng).unwrap();
+      counts[chosen.swap_move.0] += 1;
+    }
+    assert!(
+      counts.iter().all(|&count| (9500..10500).contains(&count)),
+      "{:?}",
+      counts
+    );
+
+    // Exactly one index is drawn per selection
+    let mut rng = seeded_rng(1);
+    let mut expected_rng = seeded_rng(1);
+    for _ in 0..100 {
+      let chosen = choose_move(&moves, &mut rng).unwrap();
+      assert_eq!(chosen.swap_move.0, expected_rng.gen_range(0, moves.len()));
+    }
+    assert!(choose_move(&[], &mut rng).is_none());
+  }
+
+  #[test]
+  fn fixed_start_seed() {
+    let inst = ft06();
+    let fixed_start = generate_random_solution_seeded(&inst, 7);
+    for seed in 0..2 {
+      let config = Config {
+        seed: seed,
+        run: RunOptions {
+          start_seed: Some(7),
+          collect_improvements: true,
+          max_iterations: Some(10),
+          ..RunOptions::default()
+        },
+        ..config(10)
+      };
+      let (_, stats) = find_solution(&inst, &config);
+      assert_eq!(stats.improvements[0].2.start_times, fixed_start.start_times);
+    }
+  }
+
+  #[test]
+  fn deterministic_acceptance() {
+    // The scaled probabilities only depend on the inputs, not on the platform's exp
+    assert_eq!(fixed_point_acceptance(-3.0, 1.0), 1 << 32);
+    assert_eq!(fixed_point_acceptance(0.0, 1.0), 1 << 32);
+    assert_eq!(fixed_point_acceptance(1.0, 1.0), 1580030168);
+    assert_eq!(fixed_point_acceptance(3.0, 10.0), 3181790030);
+    assert_eq!(fixed_point_acceptance(7.0, 2.5), 261177230);
+    assert_eq!(fixed_point_acceptance(20.0, 0.9), 0);
+    assert_eq!(fixed_point_acceptance(1.0, f64::NAN), 0);
+    for i in 1..200 {
+      let x = i as f64 / 10.0;
+      let expected = (-x).exp() * (1u64 << 32) as f64;
+      assert!((fixed_point_acceptance(x, 1.0) as f64 - expected).abs() < 4.0);
+    }
+
+    let inst = ft06();
+    let config = Config {
+      deterministic_acceptance: true,
+      ..config(300)
+    };
+    let (solution, stats) = find_solution(&inst, &config);
+    let (other_solution, other_stats) = find_solution(&inst, &config);
+    assert_eq!(stats.iterations, other_stats.iterations);
+    assert_eq!(
+      solution.to_solution().start_times,
+      other_solution.to_solution().start_times
+    );
+  }
+
+  #[test]
+  fn temperature_estimated_once() {
+    let inst = ft06();
+    let iterations = 10 * EQUILIBRIUM_ITERATIONS;
+    // Ten cycles as in min_temperature_ends_the_cycle
+    let config = Config {
+      delta: 1000.0,
+      min_temperature: Some(f64::MAX),
+      reestimate_temperature_each_cycle: false,
+      ..config(iterations)
+    };
+    let (_, stats) = find_solution(&inst, &config);
+    assert_eq!(stats.iterations, iterations);
+    assert_eq!(stats.temperature_estimations, 1);
+  }
+
+  #[test]
+  fn orientation_distance_from_start() {
+    let inst = ft06();
+    let (_, stats) = find_solution(&inst, &config(0));
+    assert_eq!(stats.orientation_distance_from_start, 0);
+    let (_, stats) = find_solution(&inst, &config(1000));
+    assert!(stats.orientation_distance_from_start > 0);
+  }
+
+  #[test]
+  fn given_initial_temperature() {
+    let inst = ft06();
+    // The current cmax of every iteration in the first temperature step
+    let run = |temperature: f64| {
+      let (_, stats) = find_solution(
+        &inst,
+        &Config {
+          initial_temperature: Some(temperature),
+          run: RunOptions {
+            trajectory_capacity: Some(EQUILIBRIUM_ITERATIONS as usize + 1),
+            max_iterations: Some(EQUILIBRIUM_ITERATIONS),
+            ..RunOptions::default()
+          },
+          ..config(EQUILIBRIUM_ITERATIONS)
+        },
+      );
+      assert_eq!(stats.temperature_estimations, 0);
+      let mut trajectory: Vec<u32> = stats.trajectory.iter().map(|&(_, cmax)| cmax).collect();
+      // The final pair has the best cmax
+      trajectory.pop();
+      assert_eq!(trajectory.len(), EQUILIBRIUM_ITERATIONS as usize);
+      trajectory
+    };
+    let increases = |trajectory: &[u32]| trajectory.windows(2).filter(|w| w[1] > w[0]).count();
+    // Too cold to accept any worse move, hot enough to accept nearly every one
+    assert_eq!(increases(&run(1e-9)), 0);
+    assert!(increases(&run(1e9)) > 0);
+  }
+}
@@ -0,0 +1,213 @@
+use crate::data::{Edge, OpId};
+use crate::solver::IntermediateSolution;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+// Least recently used solutions by the hash of their orientation key, so revisited orientations (e.g. by tabu search
+// cycling) do not have to be recomputed
+// A key is looked up by its hash and then compared with the cached one, so hash collisions are misses
+#[derive(Debug, Clone)]
+pub struct SolutionCache {
+  capacity: usize,
+  entries: HashMap<u64, Entry>,
+  // (time, hash) of every use in order, a use is outdated if its entry was used again (or evicted) later; the first
+  // up-to-date one is the least recently used entry, which is evicted when full
+  uses: VecDeque<(u64, u64)>,
+  time: u64,
+  hits: u64,
+  misses: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+  // Canonical orientation, see IntermediateSolution::orientation_key
+  key: Vec<Edge>,
+  solution: IntermediateSolution,
+  last_used: u64,
+}
+
+impl SolutionCache {
+  // The capacity has to be positive
+  pub fn new(capacity: usize) -> Self {
+    assert!(capacity > 0, "Solution cache capacity has to be positive");
+    return SolutionCache {
+      capacity: capacity,
+      entries: HashMap::with_capacity(capacity),
+      uses: VecDeque::new(),
+      time: 0,
+      hits: 0,
+      misses: 0,
+    };
+  }
+
+  // Solution after swapping the machine-adjacent a and b in current, computed only if it is not cached
+  pub fn apply_swap(
+    &mut self,
+    current: &IntermediateSolution,
+    a: OpId,
+    b: OpId,
+  ) -> &IntermediateSolution {
+    let key = canonical(current.swapped_orientation(a, b));
+    let hash = key_hash(&key);
+    if !self.lookup(&key, hash) {
+      self.insert_entry(key, hash, current.apply_swap(a, b));
+    }
+    return &self.entries[&hash].solution;
+  }
+
+  pub fn len(&self) -> usize {
+    return self.entries.len();
+  }
+
+  pub fn is_empty(&self) -> bool {
+    return self.entries.is_empty();
+  }
+
+  pub fn capacity(&self) -> usize {
+    return self.capacity;
+  }
+
+  pub fn hits(&self) -> u64 {
+    return self.hits;
+  }
+
+  pub fn misses(&self) -> u64 {
+    return self.misses;
+  }
+
+  // Whether the key is cached (marking it as used), counting the hit or miss
+  fn lookup(&mut self, key: &[Edge], hash: u64) -> bool {
+    if self
+      .entries
+      .get(&hash)
+      .map_or(true, |entry| entry.key.as_slice() != key)
+    {
+      self.misses += 1;
+      return false;
+    }
+
+    self.hits += 1;
+    self.time += 1;
+    self.entries.get_mut(&hash).unwrap().last_used = self.time;
+    self.uses.push_back((self.time, hash));
+    // Drop the outdated uses, so their number stays bounded by twice the capacity
+    if self.uses.len() > 2 * self.capacity {
+      let entries = &self.entries;
+      self
+        .uses
+        .retain(|&(time, hash)| is_up_to_date(entries, time, hash));
+    }
+    return true;
+  }
+
+  fn insert_entry(&mut self, key: Vec<Edge>, hash: u64, solution: IntermediateSolution) {
+    if self.entries.remove(&hash).is_none() && self.entries.len() >= self.capacity {
+      while let Some((time, evicted)) = self.uses.pop_front() {
+        if is_up_to_date(&self.entries, time, evicted) {
+          self.entries.remove(&evicted);
+          break;
+        }
+      }
+    }
+
+    self.time += 1;
+    self.uses.push_back((self.time, hash));
+    self.entries.insert(
+      hash,
+      Entry {
+        key: key,
+        solution: solution,
+        last_used: self.time,
+      },
+    );
+  }
+}
+
+fn is_up_to_date(entries: &HashMap<u64, Entry>, time: u64, hash: u64) -> bool {
+  return entries
+    .get(&hash)
+    .map_or(false, |entry| entry.last_used == time);
+}
+
+fn canonical(mut oriented_conflict_edges: Vec<Edge>) -> Vec<Edge> {
+  oriented_conflict_edges.sort_unstable();
+  return oriented_conflict_edges;
+}
+
+fn key_hash(key: &[Edge]) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  key.hash(&mut hasher);
+  return hasher.finish();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{priority, seeded_rng};
+  use crate::test_instances::ft06;
+  use rand::seq::SliceRandom;
+
+  #[test]
+  fn cached_cmax_matches_recomputation() {
+    let inst = ft06();
+    let mut cache = SolutionCache::new(8);
+    let mut rng = seeded_rng(0);
+    let mut current =
+      IntermediateSolution::from_solution(&inst, &priority::find_solution_sps(&inst, None))
+        .unwrap();
+    for _ in 0..500 {
+      // Random feasible swaps, half of them back to the previous orientation
+      let swaps: Vec<(OpId, OpId)> = current
+        .oriented_conflict_edges
+        .iter()
+        .copied()
+        .filter(|&(a, b)| {
+          current.succ_machine[a] == Some(b) && current.evaluate_swap(a, b).is_some()
+        })
+        .collect();
+      let &(a, b) = swaps.choose(&mut rng).unwrap();
+      let expected = current.apply_swap(a, b);
+      let cached = cache.apply_swap(&current, a, b).clone();
+      assert_eq!(cached.cmax(), expected.cmax());
+      assert_eq!(
+        cached.to_solution().start_times,
+        expected.to_solution().start_times
+      );
+      assert_eq!(cache.apply_swap(&current, a, b).cmax(), expected.cmax());
+      assert_eq!(cache.apply_swap(&expected, b, a).cmax(), current.cmax());
+
+      assert!(cache.len() <= cache.capacity());
+      assert!(cache.uses.len() <= 2 * cache.capacity() + 1);
+      current = expected;
+    }
+    assert!(cache.hits() >= 500);
+  }
+
+  #[test]
+  fn evicts_the_least_recently_used() {
+    let inst = ft06();
+    let current =
+      IntermediateSolution::from_solution(&inst, &priority::find_solution_sps(&inst, None))
+        .unwrap();
+    let swaps: Vec<(OpId, OpId)> = current
+      .oriented_conflict_edges
+      .iter()
+      .copied()
+      .filter(|&(a, b)| current.succ_machine[a] == Some(b))
+      .take(3)
+      .collect();
+    let mut cache = SolutionCache::new(2);
+    cache.apply_swap(&current, swaps[0].0, swaps[0].1);
+    cache.apply_swap(&current, swaps[1].0, swaps[1].1);
+    // Using the first one again makes the second one the least recently used
+    cache.apply_swap(&current, swaps[0].0, swaps[0].1);
+    cache.apply_swap(&current, swaps[2].0, swaps[2].1);
+    assert_eq!((cache.hits(), cache.misses()), (1, 3));
+    assert_eq!(cache.len(), 2);
+    cache.apply_swap(&current, swaps[0].0, swaps[0].1);
+    assert_eq!((cache.hits(), cache.misses()), (2, 3));
+    cache.apply_swap(&current, swaps[1].0, swaps[1].1);
+    assert_eq!((cache.hits(), cache.misses()), (2, 4));
+  }
+}
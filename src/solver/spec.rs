@@ -0,0 +1,781 @@
+use crate::data::{Instance, Solution};
+use crate::solver::clock::{Clock, Stopwatch};
+use crate::solver::hill_climber::{self, HcConfig};
+use crate::solver::neighborhood::{CandidateOrder, NeighborhoodKind, SearchMethod, TieBreak};
+use crate::solver::objective::{violation, Constraint, Objective};
+use crate::solver::run_options::RunOptions;
+use crate::solver::{
+  best_construct, calculate_cmax, priority, random_restart_hill_climber, repair, sequential,
+  simulated_annealing, steepest_descent, tabu_search, timed, timeout_fraction, verify_solution,
+  IntermediateSolution, SolveStats,
+};
+use rand::{Rng, RngCore};
+use std::cmp;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityRule {
+  Sps,
+  Lps,
+  Spt,
+  Lpt,
+  Lwrm,
+  Mwrm,
+}
+
+impl PriorityRule {
+  pub const ALL: [PriorityRule; 6] = [
+    PriorityRule::Sps,
+    PriorityRule::Lps,
+    PriorityRule::Spt,
+    PriorityRule::Lpt,
+    PriorityRule::Lwrm,
+    PriorityRule::Mwrm,
+  ];
+
+  // Name as used in the solver names, e.g. sps for priority-sps
+  pub fn name(&self) -> &'static str {
+    return match self {
+      PriorityRule::Sps => "sps",
+      PriorityRule::Lps => "lps",
+      PriorityRule::Spt => "spt",
+      PriorityRule::Lpt => "lpt",
+      PriorityRule::Lwrm => "lwrm",
+      PriorityRule::Mwrm => "mwrm",
+    };
+  }
+
+  pub fn find_solution(&self, inst: &Instance) -> Solution {
+    return self.find_solution_ties(inst, None);
+  }
+
+  // Solution of the rule with ties between candidates of equal priority broken uniformly at random instead of by job
+  // index
+  pub fn find_solution_random_ties<R: Rng>(&self, inst: &Instance, rng: &mut R) -> Solution {
+    return self.find_solution_ties(inst, Some(rng));
+  }
+
+  fn find_solution_ties(&self, inst: &Instance, tie_break: Option<&mut dyn RngCore>) -> Solution {
+    return match self {
+      PriorityRule::Sps => priority::find_solution_sps(inst, tie_break),
+      PriorityRule::Lps => priority::find_solution_lps(inst, tie_break),
+      PriorityRule::Spt => priority::find_solution_spt(inst, tie_break),
+      PriorityRule::Lpt => priority::find_solution_lpt(inst, tie_break),
+      PriorityRule::Lwrm => priority::find_solution_lwrm(inst, tie_break),
+      PriorityRule::Mwrm => priority::find_solution_mwrm(inst, tie_break),
+    };
+  }
+}
+
+// Solver and its parameters, independent of how long it may run
+#[derive(Debug, Clone)]
+pub enum SolverSpec {
+  // With a random walk it also stops at the budget, an iteration being a step
+  HillClimber(HcConfig),
+  // Deterministic descent over all machine-adjacent swaps from the priority-sps solution
+  SteepestDescent {
+    // Verify every intermediate solution (expensive)
+    verify: bool,
+  },
+  RandomRestartHillClimber {
+    seed: u64,
+    hill_climber: HcConfig,
+  },
+  TabuSearch {
+    seed: u64,
+    neighborhood: NeighborhoodKind,
+    skip_symmetric_swaps: bool,
+    secondary_objective: Option<Objective>,
+    constraint: Option<Constraint>,
+    // Verify every intermediate solution (expensive)
+    verify: bool,
+  },
+  SimulatedAnnealing {
+    seed: u64,
+    // DEFAULT_START_ACCEPTANCE_RATIO and default_delta of simulated_annealing if None
+    start_acceptance_ratio: Option<f64>,
+    delta: Option<f64>,
+    neighborhood: NeighborhoodKind,
+    skip_symmetric_swaps: bool,
+    secondary_objective: Option<Objective>,
+    constraint: Option<Constraint>,
+    // Verify every intermediate solution (expensive)
+    verify: bool,
+  },
+  Priority(PriorityRule),
+  // Best of all dispatching rules and this many random constructions
+  BestConstruct {
+    seed: u64,
+    random_constructions: usize,
+  },
+  Sequential,
+}
+
+impl SolverSpec {
+  // Solvers that run until their budget is exhausted
+  pub fn is_metaheuristic(&self) -> bool {
+    return matches!(
+      self,
+      SolverSpec::RandomRestartHillClimber { .. }
+        | SolverSpec::TabuSearch { .. }
+        | SolverSpec::SimulatedAnnealing { .. }
+    );
+  }
+
+  // Whether the solver can run on the instance, checked by the entry points instead of panicking during the search
+  pub fn validate(&self, inst: &Instance) -> Result<(), String> {
+    if inst.job_precedences.is_some() {
+      return Err("The solvers do not support explicit job precedences".to_string());
+    }
+    let (secondary_objective, constraint) = match self {
+      SolverSpec::HillClimber(config) => (config.secondary_objective, config.constraint),
+      SolverSpec::RandomRestartHillClimber { hill_climber, .. } => {
+        (hill_climber.secondary_objective, hill_climber.constraint)
+      }
+      SolverSpec::TabuSearch {
+        secondary_objective,
+        constraint,
+        ..
+      }
+      | SolverSpec::SimulatedAnnealing {
+        secondary_objective,
+        constraint,
+        ..
+      } => (*secondary_objective, *constraint),
+      _ => (None, None),
+    };
+    if inst.due_dates.is_some() {
+      return Ok(());
+    }
+    if let Some(objective) = secondary_objective {
+      if objective.requires_due_dates() {
+        return Err("The secondary objective requires due dates".to_string());
+      }
+    }
+    if let Some(constraint) = constraint {
+      if constraint.objective.requires_due_dates() {
+        return Err("The constraint requires due dates".to_string());
+      }
+    }
+    return Ok(());
+  }
+}
+
+// Returned by the metaheuristics for a zero timeout instead of their random start solution, since they would not
+// iterate at all: the solution of the rule, or if None the one of the dispatching rule with the lowest violation of the
+// constraint and then the lowest cmax (the first of PriorityRule::ALL on ties)
+pub fn zero_timeout_solution(
+  inst: &Instance,
+  rule: Option<PriorityRule>,
+  constraint: Option<Constraint>,
+) -> IntermediateSolution {
+  let rules = match rule {
+    Some(rule) => vec![rule],
+    None => PriorityRule::ALL.to_vec(),
+  };
+  return rules
+    .iter()
+    .map(|rule| {
+      IntermediateSolution::from_solution(inst, &rule.find_solution(inst))
+        .expect("Dispatching rule solution is invalid")
+    })
+    .min_by_key(|solution| (violation(solution, constraint), solution.cmax()))
+    .unwrap();
+}
+
+// Constructs the result of a zero timeout on the clock of the metaheuristic, its stats count the whole run as
+// construction using all of the timeout
+pub(crate) fn timed_zero_timeout_run(
+  clock: Option<&Arc<dyn Clock>>,
+  construct: impl FnOnce() -> IntermediateSolution,
+) -> (IntermediateSolution, SolveStats) {
+  let start = Stopwatch::start(clock);
+  let mut construction = Duration::default();
+  let solution = timed(&start, &mut construction, construct);
+  let elapsed = start.elapsed();
+  let stats = SolveStats {
+    elapsed: elapsed,
+    timeout_fraction_used: timeout_fraction(elapsed, Duration::from_secs(0)),
+    construction: construction,
+    ..SolveStats::default()
+  };
+  return (solution, stats);
+}
+
+// Limit of a metaheuristic run (and of a random walk hill climber), simple heuristics ignore it
+pub enum Budget {
+  // Measured on the given clock, or the system clock if none is given
+  Time {
+    timeout: Duration,
+    clock: Option<Arc<dyn Clock>>,
+  },
+  // Does not depend on any clock, so runs are reproducible
+  Iterations(u64),
+}
+
+// Entry point without file I/O and (given a clock or an iteration budget) without std::time
+// A zero timeout makes the metaheuristics return the best dispatching rule solution (see zero_timeout_solution)
+// Fails if the spec cannot run on the instance (see SolverSpec::validate)
+pub fn solve_with_budget(
+  inst: &Instance,
+  spec: &SolverSpec,
+  budget: Budget,
+) -> Result<(Solution, Option<SolveStats>), String> {
+  spec.validate(inst)?;
+  return run(inst, spec, budget, None);
+}
+
+// Warm-starts the solver with the initial solution (the metaheuristics start from it, the local searches descend from
+// it) and returns the better of its result and the initial solution, so the cmax never increases
+// Constructive solvers ignore the initial solution, their solution is only returned if its cmax is lower
+// An infeasible initial solution (e.g. with overlapping operations) is repaired first and takes its place
+// Fails as solve_with_budget
+pub fn improve(
+  inst: &Instance,
+  initial: &Solution,
+  spec: &SolverSpec,
+  budget: Budget,
+) -> Result<Solution, String> {
+  spec.validate(inst)?;
+  let initial = match verify_solution(inst, initial) {
+    Ok(_) => initial.clone(),
+    Err(_) => repair(inst, initial),
+  };
+  let (solution, _) = run(inst, spec, budget, Some(&initial))?;
+  if calculate_cmax(inst, &solution) < calculate_cmax(inst, &initial) {
+    return Ok(solution);
+  }
+  return Ok(initial);
+}
+
+fn run(
+  inst: &Instance,
+  spec: &SolverSpec,
+  budget: Budget,
+  initial: Option<&Solution>,
+) -> Result<(Solution, Option<SolveStats>), String> {
+  let initial_solution = || match initial {
+    Some(solution) => solution.clone(),
+    None => priority::find_solution_sps(inst, None),
+  };
+  let warm_start = || {
+    initial
+      .map(|solution| {
+        IntermediateSolution::from_solution(inst, solution).map_err(|e| e.to_string())
+      })
+      .transpose()
+  };
+  let (timeout, max_iterations, clock) = match budget {
+    Budget::Time { timeout, clock } => (timeout, None, clock),
+    Budget::Iterations(iterations) => (Duration::from_secs(u64::MAX), Some(iterations), None),
+  };
+
+  return Ok(match spec {
+    SolverSpec::HillClimber(config) => {
+      // A random walk does not stop at local optima, so it stops at the budget as well as at its own limits
+      let config = if config.random_walk_probability > 0.0 {
+        HcConfig {
+          timeout: Some(config.timeout.map_or(timeout, |own| cmp::min(own, timeout))),
+          max_steps: max_iterations.into_iter().chain(config.max_steps).min(),
+          ..config.clone()
+        }
+      } else {
+        config.clone()
+      };
+      (
+        hill_climber::improve_solution_on_clock(
+          inst,
+          &initial_solution(),
+          &config,
+          clock.as_ref(),
+        )?
+        .to_solution(),
+        None,
+      )
+    }
+    SolverSpec::SteepestDescent { verify } => (
+      steepest_descent::improve_solution(inst, &initial_solution(), *verify).to_solution(),
+      None,
+    ),
+    SolverSpec::RandomRestartHillClimber { seed, hill_climber } => {
+      let config = random_restart_hill_climber::Config {
+        timeout: timeout,
+        seed: *seed,
+        hill_climber: hill_climber.clone(),
+        min_restart_distance: None,
+        elite_pool_size: 0,
+        recombination_probability: 0.0,
+        run: RunOptions {
+          resume: warm_start()?.map(|solution| (solution, SolveStats::default())),
+          max_iterations: max_iterations,
+          clock: clock,
+          ..RunOptions::default()
+        },
+        zero_timeout_rule: None,
+      };
+      let (solution, stats) = random_restart_hill_climber::find_solution(inst, &config);
+      (solution.to_solution(), Some(stats))
+    }
+    SolverSpec::TabuSearch {
+      seed,
+      neighborhood,
+      skip_symmetric_swaps,
+      secondary_objective,
+      constraint,
+      verify,
+    } => {
+      let config = tabu_search::Config {
+        timeout: timeout,
+        seed: *seed,
+        verify: *verify,
+        run: RunOptions {
+          resume: warm_start()?.map(|solution| (solution, SolveStats::default())),
+          max_iterations: max_iterations,
+          clock: clock,
+          ..RunOptions::default()
+        },
+        unique_solutions_cap: None,
+        termination: None,
+        max_stagnant_iterations: None,
+        neighborhood: *neighborhood,
+        skip_symmetric_swaps: *skip_symmetric_swaps,
+        search_method: SearchMethod::Exhaustive,
+        candidate_order: CandidateOrder::Generated,
+        tie_break: TieBreak::Generated,
+        secondary_objective: *secondary_objective,
+        constraint: *constraint,
+        required_edges: Vec::new(),
+        solution_cache_capacity: None,
+        penalty_coefficient: tabu_search::DEFAULT_PENALTY_COEFFICIENT,
+        penalty_exponent: tabu_search::DEFAULT_PENALTY_EXPONENT,
+        zero_timeout_rule: None,
+      };
+      let (solution, stats) = tabu_search::find_solution(inst, &config);
+      (solution.to_solution(), Some(stats))
+    }
+    SolverSpec::SimulatedAnnealing {
+      seed,
+      start_acceptance_ratio,
+      delta,
+      neighborhood,
+      skip_symmetric_swaps,
+      secondary_objective,
+      constraint,
+      verify,
+    } => {
+      let config = simulated_annealing::Config {
+        timeout: timeout,
+        seed: *seed,
+        start_acceptance_ratio: start_acceptance_ratio
+          .unwrap_or(simulated_annealing::DEFAULT_START_ACCEPTANCE_RATIO),
+        delta: delta.unwrap_or_else(|| simulated_annealing::default_delta(inst)),
+        min_temperature: None,
+        deterministic_acceptance: false,
+        reestimate_temperature_each_cycle: true,
+        initial_temperature: None,
+        verify: *verify,
+        run: RunOptions {
+          max_iterations: max_iterations,
+          clock: clock,
+          ..RunOptions::default()
+        },
+        warm_start: warm_start()?,
+        neighborhood: *neighborhood,
+        skip_symmetric_swaps: *skip_symmetric_swaps,
+        secondary_objective: *secondary_objective,
+        constraint: *constraint,
+        zero_timeout_rule: None,
+      };
+      let (solution, stats) = simulated_annealing::find_solution(inst, &config);
+      (solution.to_solution(), Some(stats))
+    }
+    SolverSpec::Priority(rule) => (rule.find_solution(inst), None),
+    SolverSpec::BestConstruct {
+      seed,
+      random_constructions,
+    } => {
+      let config = best_construct::Config {
+        seed: *seed,
+        random_constructions: *random_constructions,
+        init_bias: None,
+      };
+      (best_construct::find_solution(inst, &config).0, None)
+    }
+    SolverSpec::Sequential => (sequential::find_solution(inst), None),
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::{
+    branch_and_bound, calculate_total_tardiness, generate_random_solution_seeded,
+  };
+  use crate::test_instances::{ft06, StepClock};
+  use ndarray::Array1;
+
+  fn searches(verify: bool) -> Vec<SolverSpec> {
+    let hill_climber = HcConfig {
+      verify: verify,
+      ..HcConfig::default()
+    };
+    return vec![
+      SolverSpec::HillClimber(hill_climber.clone()),
+      SolverSpec::SteepestDescent { verify: verify },
+      SolverSpec::RandomRestartHillClimber {
+        seed: 1,
+        hill_climber: hill_climber,
+      },
+      SolverSpec::TabuSearch {
+        seed: 1,
+        neighborhood: NeighborhoodKind::N1,
+        skip_symmetric_swaps: false,
+        secondary_objective: None,
+        constraint: None,
+        verify: verify,
+      },
+      SolverSpec::SimulatedAnnealing {
+        seed: 1,
+        start_acceptance_ratio: None,
+        delta: None,
+        neighborhood: NeighborhoodKind::N1Full,
+        skip_symmetric_swaps: false,
+        secondary_objective: None,
+        constraint: None,
+        verify: verify,
+      },
+    ];
+  }
+
+  #[test]
+  fn iteration_budget_is_reproducible() {
+    let inst = ft06();
+    for spec in searches(false)
+      .iter()
+      .filter(|spec| spec.is_metaheuristic())
+    {
+      let (solution, stats) = solve_with_budget(&inst, spec, Budget::Iterations(100)).unwrap();
+      let (other_solution, other_stats) =
+        solve_with_budget(&inst, spec, Budget::Iterations(100)).unwrap();
+      let (stats, other_stats) = (stats.unwrap(), other_stats.unwrap());
+      assert!(stats.iterations > 0, "{:?}", spec);
+      assert_eq!(stats.iterations, other_stats.iterations, "{:?}", spec);
+      assert_eq!(
+        calculate_cmax(&inst, &solution),
+        calculate_cmax(&inst, &other_solution),
+        "{:?}",
+        spec
+      );
+    }
+  }
+
+  #[test]
+  fn verification_does_not_change_the_result() {
+    let inst = ft06();
+    for (verified, unverified) in searches(true).iter().zip(&searches(false)) {
+      let (solution, _) = solve_with_budget(&inst, verified, Budget::Iterations(200)).unwrap();
+      verify_solution(&inst, &solution).unwrap();
+      let (unverified_solution, _) =
+        solve_with_budget(&inst, unverified, Budget::Iterations(200)).unwrap();
+      assert_eq!(
+        solution.start_times, unverified_solution.start_times,
+        "{:?}",
+        verified
+      );
+    }
+  }
+
+  #[test]
+  fn construction_and_search_times() {
+    let inst = ft06();
+    for spec in searches(false)
+      .iter()
+      .filter(|spec| spec.is_metaheuristic())
+    {
+      let (_, stats) = solve_with_budget(&inst, spec, Budget::Iterations(300)).unwrap();
+      let stats = stats.unwrap();
+      let measured = stats.construction + stats.search;
+      assert!(stats.construction > Duration::from_secs(0), "{:?}", spec);
+      assert!(stats.search > Duration::from_secs(0), "{:?}", spec);
+      // Only bookkeeping between the iterations is neither
+      assert!(measured <= stats.elapsed, "{:?}", spec);
+      assert!(measured >= stats.elapsed / 2, "{:?} {:?}", spec, stats);
+    }
+  }
+
+  #[test]
+  fn zero_timeout_stats() {
+    let inst = ft06();
+    for spec in searches(false)
+      .iter()
+      .filter(|spec| spec.is_metaheuristic())
+    {
+      let budget = Budget::Time {
+        timeout: Duration::from_secs(0),
+        clock: Some(Arc::new(StepClock::new())),
+      };
+      let (_, stats) = solve_with_budget(&inst, spec, budget).unwrap();
+      let stats = stats.unwrap();
+      assert_eq!(stats.iterations, 0, "{:?}", spec);
+      assert_eq!(stats.timeout_fraction_used, 1.0, "{:?}", spec);
+      assert!(stats.construction > Duration::from_secs(0), "{:?}", spec);
+      assert!(stats.construction <= stats.elapsed, "{:?}", spec);
+    }
+  }
+
+  #[test]
+  fn zero_durations() {
+    // Interior, leading and trailing zero-duration operations and a job
+    // that consists only of them. The optimum is the length of job 0.
+    let inst = Instance::from_jobs(
+      3,
+      &[
+        vec![(0, 2), (1, 0), (2, 3)],
+        vec![(1, 0), (0, 0), (2, 0)],
+        vec![(2, 1), (0, 0), (1, 2)],
+      ],
+    );
+    let optimum = branch_and_bound::find_optimal_solution(&inst);
+    verify_solution(&inst, &optimum).unwrap();
+    assert_eq!(calculate_cmax(&inst, &optimum), 5);
+    let mut specs = searches(true);
+    specs.push(SolverSpec::Sequential);
+    specs.push(SolverSpec::BestConstruct {
+      seed: 1,
+      random_constructions: 5,
+    });
+    specs.extend(
+      PriorityRule::ALL
+        .iter()
+        .map(|rule| SolverSpec::Priority(*rule)),
+    );
+    for spec in &specs {
+      let (solution, _) = solve_with_budget(&inst, spec, Budget::Iterations(100)).unwrap();
+      verify_solution(&inst, &solution).unwrap();
+      assert!(calculate_cmax(&inst, &solution) >= 5, "{:?}", spec);
+    }
+    for seed in 0..10 {
+      let solution = generate_random_solution_seeded(&inst, seed);
+      verify_solution(&inst, &solution).unwrap();
+      verify_solution(&inst, &repair(&inst, &solution)).unwrap();
+    }
+  }
+
+  #[test]
+  fn tardiness_constraint() {
+    let mut inst = ft06();
+    inst.due_dates = Some(Array1::from(vec![40; 6]));
+    let constraint = Constraint::parse("tardiness-leq", "80").unwrap();
+    // The start solution violates the constraint, so the searches first have to reduce the tardiness
+    let start = priority::find_solution_sps(&inst, None);
+    assert_eq!(calculate_total_tardiness(&inst, &start), 86);
+    let specs = vec![
+      SolverSpec::TabuSearch {
+        seed: 1,
+        neighborhood: NeighborhoodKind::N1,
+        skip_symmetric_swaps: false,
+        secondary_objective: None,
+        constraint: Some(constraint),
+        verify: false,
+      },
+      SolverSpec::SimulatedAnnealing {
+        seed: 1,
+        start_acceptance_ratio: None,
+        delta: None,
+        neighborhood: NeighborhoodKind::N1Full,
+        skip_symmetric_swaps: false,
+        secondary_objective: None,
+        constraint: Some(constraint),
+        verify: false,
+      },
+    ];
+    for spec in &specs {
+      let (solution, _) = solve_with_budget(&inst, spec, Budget::Iterations(300)).unwrap();
+      verify_solution(&inst, &solution).unwrap();
+      assert!(
+        calculate_total_tardiness(&inst, &solution) <= 80,
+        "{:?}",
+        spec
+      );
+    }
+  }
+
+  #[test]
+  fn improve_never_worsens() {
+    let inst = Instance::from_jobs(
+      3,
+      &[
+        vec![(0, 3), (1, 2), (2, 2)],
+        vec![(0, 2), (2, 1), (1, 4)],
+        vec![(1, 4), (2, 3), (0, 1)],
+      ],
+    );
+    let optimal = branch_and_bound::find_optimal_solution(&inst);
+    let optimum = calculate_cmax(&inst, &optimal);
+    let mut specs = searches(false);
+    specs.push(SolverSpec::Priority(PriorityRule::Mwrm));
+    specs.push(SolverSpec::BestConstruct {
+      seed: 0,
+      random_constructions: 5,
+    });
+    specs.push(SolverSpec::Sequential);
+    for spec in &specs {
+      let improved = improve(&inst, &optimal, spec, Budget::Iterations(50)).unwrap();
+      assert_eq!(calculate_cmax(&inst, &improved), optimum, "{:?}", spec);
+
+      for seed in 0..5 {
+        let poor = generate_random_solution_seeded(&inst, seed);
+        let improved = improve(&inst, &poor, spec, Budget::Iterations(50)).unwrap();
+        verify_solution(&inst, &improved).unwrap();
+        assert!(
+          calculate_cmax(&inst, &improved) <= calculate_cmax(&inst, &poor),
+          "{:?}",
+          spec
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn random_walk_budget() {
+    let inst = ft06();
+    let walk = |random_walk_probability: f64| {
+      SolverSpec::HillClimber(HcConfig {
+        random_walk_probability: random_walk_probability,
+        seed: 3,
+        ..HcConfig::default()
+      })
+    };
+    // Without its own limit the walk stops at the budget, so it reproduces for an iteration budget
+    let (solution, _) = solve_with_budget(&inst, &walk(1.0), Budget::Iterations(200)).unwrap();
+    verify_solution(&inst, &solution).unwrap();
+    let (again, _) = solve_with_budget(&inst, &walk(1.0), Budget::Iterations(200)).unwrap();
+    assert_eq!(solution.start_times, again.start_times);
+    let budget = Budget::Time {
+      timeout: Duration::from_millis(50),
+      clock: Some(Arc::new(StepClock::new())),
+    };
+    solve_with_budget(&inst, &walk(0.5), budget).unwrap();
+
+    let error = solve_with_budget(&inst, &walk(1.5), Budget::Iterations(200)).unwrap_err();
+    assert_eq!(
+      error,
+      "Invalid random walk probability 1.5: has to be between 0 and 1"
+    );
+  }
+
+  #[test]
+  fn improve_repairs_infeasible_initial_solutions() {
+    let inst = ft06();
+    let overlapping = Solution {
+      start_times: Array1::from_elem(inst.n_ops(), 0),
+    };
+    for spec in &searches(false) {
+      let improved = improve(&inst, &overlapping, spec, Budget::Iterations(50)).unwrap();
+      verify_solution(&inst, &improved).unwrap();
+      assert!(
+        calculate_cmax(&inst, &improved) <= calculate_cmax(&inst, &repair(&inst, &overlapping)),
+        "{:?}",
+        spec
+      );
+    }
+  }
+
+  #[test]
+  fn per_solver_neighborhoods() {
+    let inst = ft06();
+    let kinds = [
+      NeighborhoodKind::N1,
+      NeighborhoodKind::N1Full,
+      NeighborhoodKind::N1Bottleneck,
+    ];
+    assert_ne!(
+      tabu_search::DEFAULT_NEIGHBORHOOD,
+      simulated_annealing::DEFAULT_NEIGHBORHOOD
+    );
+    for &tabu_neighborhood in &kinds {
+      for &sa_neighborhood in kinds.iter().filter(|&&kind| kind != tabu_neighborhood) {
+        let tabu = SolverSpec::TabuSearch {
+          seed: 0,
+          neighborhood: tabu_neighborhood,
+          skip_symmetric_swaps: false,
+          secondary_objective: None,
+          constraint: None,
+          verify: true,
+        };
+        let annealing = SolverSpec::SimulatedAnnealing {
+          seed: 0,
+          start_acceptance_ratio: None,
+          delta: None,
+          neighborhood: sa_neighborhood,
+          skip_symmetric_swaps: false,
+          secondary_objective: None,
+          constraint: None,
+          verify: true,
+        };
+        for spec in &[tabu, annealing] {
+          let (solution, stats) = solve_with_budget(&inst, spec, Budget::Iterations(200)).unwrap();
+          verify_solution(&inst, &solution).unwrap();
+          assert!(calculate_cmax(&inst, &solution) >= 55);
+          assert_eq!(stats.unwrap().iterations, 200, "{:?}", spec);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn constraint_without_due_dates() {
+    let inst = ft06();
+    let constraint = Some(Constraint {
+      objective: Objective::TotalTardiness,
+      max_value: 10,
+    });
+    let specs = vec![
+      SolverSpec::HillClimber(HcConfig {
+        constraint: constraint,
+        ..HcConfig::default()
+      }),
+      SolverSpec::TabuSearch {
+        seed: 1,
+        neighborhood: NeighborhoodKind::N1,
+        skip_symmetric_swaps: false,
+        secondary_objective: None,
+        constraint: constraint,
+        verify: false,
+      },
+    ];
+    let initial = priority::find_solution_sps(&inst, None);
+    for spec in &specs {
+      let error = solve_with_budget(&inst, spec, Budget::Iterations(10)).unwrap_err();
+      assert_eq!(error, "The constraint requires due dates");
+      assert!(improve(&inst, &initial, spec, Budget::Iterations(10)).is_err());
+    }
+
+    let mut inst = inst;
+    inst.due_dates = Some(Array1::from_elem(inst.n_jobs, 50));
+    for spec in &specs {
+      solve_with_budget(&inst, spec, Budget::Iterations(10)).unwrap();
+    }
+  }
+
+  #[test]
+  fn secondary_objective_without_due_dates() {
+    let mut inst = ft06();
+    let spec = SolverSpec::SimulatedAnnealing {
+      seed: 1,
+      start_acceptance_ratio: None,
+      delta: None,
+      neighborhood: NeighborhoodKind::N1Full,
+      skip_symmetric_swaps: false,
+      secondary_objective: Some(Objective::WeightedEarlinessTardiness),
+      constraint: None,
+      verify: false,
+    };
+    let error = solve_with_budget(&inst, &spec, Budget::Iterations(10)).unwrap_err();
+    assert_eq!(error, "The secondary objective requires due dates");
+
+    inst.due_dates = Some(Array1::from_elem(inst.n_jobs, 50));
+    solve_with_budget(&inst, &spec, Budget::Iterations(10)).unwrap();
+  }
+}
@@ -0,0 +1,61 @@
+use crate::solver::objective::{violation, Constraint};
+use crate::solver::IntermediateSolution;
+use std::collections::VecDeque;
+
+// Detects that the best cmax of the feasible solutions improved by at most a relative epsilon during the last window
+// of iterations, never stalls without a window
+pub(crate) struct StallDetector {
+  window: Option<u64>,
+  epsilon: f64,
+  // (iteration, cmax) of every improvement, only the last one before the window start and those inside are retained
+  improvements: VecDeque<(u64, u32)>,
+}
+
+impl StallDetector {
+  pub(crate) fn new(window: Option<u64>, epsilon: f64) -> Self {
+    return StallDetector {
+      window: window,
+      epsilon: epsilon,
+      improvements: VecDeque::new(),
+    };
+  }
+
+  // Infeasible solutions are ignored, so the window starts with the first feasible solution
+  pub(crate) fn observe(
+    &mut self,
+    iteration: u64,
+    solution: &IntermediateSolution,
+    constraint: Option<Constraint>,
+  ) {
+    if self.window.is_none() || violation(solution, constraint) > 0 {
+      return;
+    }
+    let cmax = solution.cmax();
+    if self
+      .improvements
+      .back()
+      .map_or(true, |&(_, best)| cmax < best)
+    {
+      self.improvements.push_back((iteration, cmax));
+    }
+  }
+
+  pub(crate) fn is_stalled(&mut self, iteration: u64) -> bool {
+    let window_start = match self.window.and_then(|window| iteration.checked_sub(window)) {
+      Some(window_start) => window_start,
+      None => return false,
+    };
+    while self.improvements.len() >= 2 && self.improvements[1].0 <= window_start {
+      self.improvements.pop_front();
+    }
+
+    return match (self.improvements.front(), self.improvements.back()) {
+      (Some(&(first_iteration, reference)), Some(&(_, best)))
+        if first_iteration <= window_start =>
+      {
+        (reference - best) as f64 <= self.epsilon * reference as f64
+      }
+      _ => false,
+    };
+  }
+}
@@ -0,0 +1,79 @@
+use crate::data::{Instance, Solution};
+use crate::solver::compact::CompactSolution;
+use crate::solver::{verify_step, IntermediateSolution};
+use log::trace;
+use std::sync::Arc;
+
+// Repeatedly applies the best improving swap of any two machine-adjacent operations (not only critical ones)
+// Fully deterministic: every swap is evaluated exactly and ties are broken by the order of the oriented edges
+pub fn improve_solution(
+  inst: &Instance,
+  initial_solution: &Solution,
+  verify: bool,
+) -> IntermediateSolution {
+  let mut current = IntermediateSolution::from_solution(inst, initial_solution)
+    .expect("Initial solution is invalid");
+
+  trace!("Starting with {}", current.cmax());
+  loop {
+    let mut best_move = None;
+    let mut best_cmax = current.cmax();
+    for &(a, b) in &current.oriented_conflict_edges {
+      // Swaps introducing a cycle are not evaluated
+      if let Some(cmax) = current.evaluate_swap(a, b) {
+        if cmax < best_cmax {
+          best_move = Some((a, b));
+          best_cmax = cmax;
+        }
+      }
+    }
+
+    match best_move {
+      Some((a, b)) => {
+        current = current.apply_swap(a, b);
+        trace!("Swapped {:?} to {}", (a, b), current.cmax());
+        verify_step(verify, inst, || current.to_solution());
+      }
+      None => break,
+    }
+  }
+
+  return current;
+}
+
+// Same descent (with the same result) on a CompactSolution, which evaluates and applies the swaps in place
+pub fn improve_solution_compact(
+  inst: &Instance,
+  initial_solution: &Solution,
+  verify: bool,
+) -> CompactSolution {
+  let initial = IntermediateSolution::from_solution(inst, initial_solution)
+    .expect("Initial solution is invalid");
+  let mut current = CompactSolution::new(Arc::new(inst.clone()), initial.oriented_conflict_edges);
+
+  trace!("Starting with {}", current.cmax());
+  loop {
+    let mut best_move = None;
+    let mut best_cmax = current.cmax();
+    for i in 0..current.oriented_conflict_edges().len() {
+      let (a, b) = current.oriented_conflict_edges()[i];
+      if let Some(cmax) = current.evaluate_swap(a, b) {
+        if cmax < best_cmax {
+          best_move = Some((a, b));
+          best_cmax = cmax;
+        }
+      }
+    }
+
+    match best_move {
+      Some((a, b)) => {
+        current.swap(a, b);
+        trace!("Swapped {:?} to {}", (a, b), current.cmax());
+        verify_step(verify, inst, || current.to_solution());
+      }
+      None => break,
+    }
+  }
+
+  return current;
+}
@@ -1,52 +1,348 @@
-use crate::data::Instance;
+use crate::data::{Edge, Instance, Solution};
+use crate::solver::checkpoint::Checkpointer;
+use crate::solver::clock::Stopwatch;
+use crate::solver::neighborhood::{
+  CandidateOrder, Neighborhood, NeighborhoodKind, SearchMethod, TieBreak, TieBreaker,
+};
+use crate::solver::objective::{
+  compare_tied_moves, is_better, move_violation, violation, Constraint, Objective,
+};
+use crate::solver::required_order::{repair_orientation, satisfies_required_edges};
+use crate::solver::reservoir::{finish_trajectory, Trajectory};
+use crate::solver::run_options::RunOptions;
+use crate::solver::solution_cache::SolutionCache;
+use crate::solver::spec::{timed_zero_timeout_run, zero_timeout_solution, PriorityRule};
+use crate::solver::stall::StallDetector;
+use crate::solver::termination::{
+  AnyOf, MaxIterations, SearchState, TerminationCriterion, Timeout,
+};
+use crate::solver::time_checkpoints::TimeCheckpoints;
+use crate::solver::visited::VisitedSolutions;
 use crate::solver::{
-  generate_random_solution, get_orientation_from_schedule, n1, IntermediateSolution,
+  generate_initial_solution, publish_best, seeded_rng, timed, timeout_fraction, verify_step,
+  DurationBias, IntermediateSolution, SolveStats,
 };
 use log::{debug, info, trace};
 use ndarray::Array1;
-use rand::{Rng, SeedableRng};
+use rand::Rng;
 use rand_chacha;
 use std::cmp;
-use std::time::{Duration, Instant};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub struct Config {
   pub timeout: Duration,
   pub seed: u64,
+  // Verify every intermediate solution (expensive)
+  pub verify: bool,
+  // An iteration is a move, start_seed only draws the first start solution, the stagnation restarts are drawn from the
+  // search rng
+  pub run: RunOptions,
+  // Count the distinct orientations visited into the returned stats, remembering at most this many
+  pub unique_solutions_cap: Option<usize>,
+  // Stop additionally once this criterion is met (the timeout and max_iterations always apply)
+  pub termination: Option<Arc<dyn TerminationCriterion>>,
+  // Restart from a new random solution after this many iterations without improving the best solution
+  pub max_stagnant_iterations: Option<u64>,
+  pub neighborhood: NeighborhoodKind,
+  // See NeighborhoodKind::resolve, tabu search would only make such swaps as moves of equal cmax
+  pub skip_symmetric_swaps: bool,
+  // First selects the first admissible move instead of the best admissible one
+  pub search_method: SearchMethod,
+  // Order of the moves, with exhaustive search it only decides ties
+  pub candidate_order: CandidateOrder,
+  // Decides between moves of equal evaluation (within equal estimates if the candidates are ordered by them)
+  pub tie_break: TieBreak,
+  // Breaks ties between moves and solutions of equal cmax
+  pub secondary_objective: Option<Objective>,
+  // Moves of feasible solutions have to satisfy the constraint, infeasible solutions first minimize the violation
+  pub constraint: Option<Constraint>,
+  // Operation pairs (a, b) on the same machine that are never swapped (see required_order::required_edges), start
+  // solutions are repaired to satisfy them
+  pub required_edges: Vec<Edge>,
+  // Keep the solutions of this many recently evaluated orientations, so moves back to them are not recomputed, and
+  // evaluate moves by the exact cmax of their solution instead of the estimate of the neighborhood
+  pub solution_cache_capacity: Option<usize>,
+  // Scaling of the frequency penalty, penalty_coefficient * max_delta * (n * m)^penalty_exponent (0 disables it)
+  pub penalty_coefficient: f32,
+  pub penalty_exponent: f32,
+  // Without any iteration for a zero timeout the search returns the solution of this rule (the best one if None, see
+  // spec::zero_timeout_solution) instead of its start solution, reordered to satisfy the required edges
+  pub zero_timeout_rule: Option<PriorityRule>,
+}
+
+// Neighborhood used if none is given, the focused n1 suits the deterministic move selection
+pub const DEFAULT_NEIGHBORHOOD: NeighborhoodKind = NeighborhoodKind::N1;
+
+// Penalty 0.5 * max_delta * sqrt(n * m) of Taillard, Parallel Taboo Search Techniques for the Job Shop Scheduling Problem
+pub const DEFAULT_PENALTY_COEFFICIENT: f32 = 0.5;
+pub const DEFAULT_PENALTY_EXPONENT: f32 = 0.5;
+
+pub fn find_solution(inst: &Instance, config: &Config) -> (IntermediateSolution, SolveStats) {
+  // A resumed run still returns its saved best
+  if config.timeout == Duration::from_secs(0) && config.run.resume.is_none() {
+    return timed_zero_timeout_run(config.run.clock.as_ref(), || {
+      let solution = zero_timeout_solution(inst, config.zero_timeout_rule, config.constraint);
+      if satisfies_required_edges(
+        inst,
+        &solution.oriented_conflict_edges,
+        &config.required_edges,
+      ) {
+        return solution;
+      }
+      let orientation = repair_orientation(inst, &solution.to_solution(), &config.required_edges);
+      return IntermediateSolution::new(inst.clone(), orientation);
+    });
+  }
+  let mut search = Search::new(inst, config);
+  while !search.is_finished() {
+    search.step();
+  }
+
+  return search.finish();
+}
+
+// Runs the search one iteration per next(), yielding the best solution after each, until the timeout, the iteration
+// limit or a stall stops it; dropping the iterator stops the search (without saving a final checkpoint)
+// The timeout is measured on the clock of the config, so it also elapses between calls
+pub fn iter<'a>(
+  inst: &'a Instance,
+  config: &'a Config,
+) -> impl Iterator<Item = IntermediateSolution> + 'a {
+  let mut search = Search::new(inst, config);
+  return std::iter::from_fn(move || {
+    if search.is_finished() {
+      return None;
+    }
+    search.step();
+    return Some(search.best.clone());
+  });
 }
 
-pub fn find_solution(inst: &Instance, config: &Config) -> IntermediateSolution {
-  let mut rng = rand_chacha::ChaChaRng::seed_from_u64(config.seed);
-  let mut current = generate_solution(inst, &mut rng);
-  let mut best = current.clone();
-
-  // Taillard, Parallel Taboo Search Techniques for the Job Shop Scheduling Problem
-  let n = inst.n_jobs as f32;
-  let m = inst.n_machines as f32;
-  let tabu_duration =
-    ((n + m / 2.0) * (-n / (5.0 * m)).exp() + (n * m) / 2.0 * (-5.0 * m / n).exp()) as i32;
-  let mut op_last_swap = Array1::<i32>::from_elem(inst.n_ops(), i32::min_value());
-  let mut op_push_back_count = Array1::<i32>::from_elem(inst.n_ops(), 0);
-  let mut total_push_back_count = 0;
+// State of a search between iterations
+struct Search<'a> {
+  inst: &'a Instance,
+  config: &'a Config,
+  start: Stopwatch,
+  rng: rand_chacha::ChaChaRng,
+  trajectory: Option<Trajectory>,
+  time_checkpoints: TimeCheckpoints,
+  visited: Option<VisitedSolutions>,
+  construction: Duration,
+  search: Duration,
+  current: IntermediateSolution,
+  best: IntermediateSolution,
+  // Orientation key of the start solution
+  start_key: Vec<Edge>,
+  // Timeout, max_iterations and the criterion of the config
+  termination: AnyOf,
+  // Iteration of the last improvement of the best solution (or the start)
+  last_improvement: i32,
+  previous_stats: SolveStats,
+  stall: StallDetector,
+  checkpointer: Checkpointer<'a>,
+  neighborhood: Box<dyn Neighborhood>,
+  tie_breaker: TieBreaker,
+  required: HashSet<Edge>,
+  solution_cache: Option<SolutionCache>,
+  tabu_duration: i32,
+  op_last_swap: Array1<i32>,
+  op_push_back_count: Array1<i32>,
+  total_push_back_count: i32,
   // Maximum increase of cmax between two successive solutions
-  let mut max_delta = 0;
-
-  trace!("Starting with {}", current.cmax());
-  let start = Instant::now();
-  let mut iteration = 0;
-  while Instant::now().duration_since(start) < config.timeout {
-    let penalty_factor = 0.5 * max_delta as f32 * (n * m).sqrt();
-    let maybe_move = n1::find_move(
-      &current,
+  max_delta: u32,
+  // Iterations since the last improvement of the best solution or the last restart
+  stagnant_iterations: u64,
+  iteration: i32,
+  improvements: Vec<(u64, u32, Solution)>,
+  // Iterations of this run where a tabu move would have improved the current solution more than the selected one
+  tabu_blocked_improvements: u64,
+}
+
+impl<'a> Search<'a> {
+  fn new(inst: &'a Instance, config: &'a Config) -> Self {
+    let start = Stopwatch::start(config.run.clock.as_ref());
+    let mut rng = seeded_rng(config.seed);
+    let mut start_rng = config.run.start_seed.map(seeded_rng);
+    let trajectory = config
+      .run
+      .trajectory_capacity
+      .map(|capacity| Trajectory::new(capacity, config.seed));
+    let mut construction = Duration::default();
+    let (current, previous_stats) = match &config.run.resume {
+      Some((solution, stats)) if config.required_edges.is_empty() => {
+        (solution.clone(), stats.clone())
+      }
+      Some((solution, stats)) => (
+        IntermediateSolution::new(
+          inst.clone(),
+          repair_orientation(inst, &solution.to_solution(), &config.required_edges),
+        ),
+        stats.clone(),
+      ),
+      None => (
+        timed(&start, &mut construction, || {
+          generate_solution(
+            inst,
+            start_rng.as_mut().unwrap_or(&mut rng),
+            config.run.init_bias,
+            &config.required_edges,
+          )
+        }),
+        SolveStats::default(),
+      ),
+    };
+    let best = current.clone();
+    publish_best(&config.run.shared_best, best.cmax());
+    let mut stall = StallDetector::new(config.run.stall_window, config.run.stall_epsilon);
+    stall.observe(previous_stats.iterations, &best, config.constraint);
+    let checkpointer = Checkpointer::new(config.run.checkpoint.as_ref(), &start);
+    let mut time_checkpoints = TimeCheckpoints::new(&config.run.time_checkpoints);
+    time_checkpoints.observe(&start, &best, config.constraint);
+
+    // Taillard, Parallel Taboo Search Techniques for the Job Shop Scheduling Problem
+    let n = inst.n_jobs as f32;
+    let m = inst.n_machines as f32;
+    let tabu_duration =
+      ((n + m / 2.0) * (-n / (5.0 * m)).exp() + (n * m) / 2.0 * (-5.0 * m / n).exp()) as i32;
+
+    let mut criteria: Vec<Box<dyn TerminationCriterion>> = vec![Box::new(Timeout(config.timeout))];
+    if let Some(max_iterations) = config.run.max_iterations {
+      criteria.push(Box::new(MaxIterations(max_iterations)));
+    }
+    if let Some(termination) = &config.termination {
+      criteria.push(Box::new(termination.clone()));
+    }
+
+    trace!("Starting with {}", current.cmax());
+    let iteration = previous_stats.iterations as i32;
+    let mut improvements = Vec::new();
+    if config.run.collect_improvements {
+      improvements.push((iteration as u64, best.cmax(), best.to_solution()));
+    }
+
+    return Search {
+      inst: inst,
+      config: config,
+      start: start,
+      rng: rng,
+      trajectory: trajectory,
+      time_checkpoints: time_checkpoints,
+      visited: config.unique_solutions_cap.map(VisitedSolutions::new),
+      construction: construction,
+      search: Duration::default(),
+      start_key: current.orientation_key(),
+      termination: AnyOf(criteria),
+      last_improvement: iteration,
+      current: current,
+      best: best,
+      previous_stats: previous_stats,
+      stall: stall,
+      checkpointer: checkpointer,
+      neighborhood: config.neighborhood.resolve(config.skip_symmetric_swaps),
+      tie_breaker: TieBreaker::new(config.tie_break, config.seed),
+      required: config.required_edges.iter().copied().collect(),
+      solution_cache: config.solution_cache_capacity.map(SolutionCache::new),
+      tabu_duration: tabu_duration,
+      op_last_swap: Array1::<i32>::from_elem(inst.n_ops(), i32::MIN),
+      op_push_back_count: Array1::<i32>::from_elem(inst.n_ops(), 0),
+      total_push_back_count: 0,
+      max_delta: 0,
+      stagnant_iterations: 0,
+      iteration: iteration,
+      improvements: improvements,
+      tabu_blocked_improvements: 0,
+    };
+  }
+
+  fn is_finished(&mut self) -> bool {
+    let state = SearchState {
+      elapsed: self.start.elapsed(),
+      iteration: self.iteration as u64,
+      best_cmax: Some(self.best.cmax())
+        .filter(|_| violation(&self.best, self.config.constraint) == 0),
+      iterations_since_improvement: (self.iteration - self.last_improvement) as u64,
+    };
+    return self.termination.should_stop(&state) || self.stall.is_stalled(self.iteration as u64);
+  }
+
+  fn stats(&self) -> SolveStats {
+    return stats_at(
+      &self.previous_stats,
+      &self.start,
+      self.iteration,
+      self.construction,
+      self.search,
+    );
+  }
+
+  fn step(&mut self) {
+    let config = self.config;
+    let iteration = self.iteration;
+    let n = self.inst.n_jobs as f32;
+    let m = self.inst.n_machines as f32;
+    let penalty_factor =
+      config.penalty_coefficient * self.max_delta as f32 * (n * m).powf(config.penalty_exponent);
+    let current_violation = violation(&self.current, config.constraint);
+    let best_violation = violation(&self.best, config.constraint);
+    let current = &self.current;
+    let best = &self.best;
+    let required = &self.required;
+    let solution_cache = &mut self.solution_cache;
+    let op_last_swap = &self.op_last_swap;
+    let op_push_back_count = &self.op_push_back_count;
+    let total_push_back_count = self.total_push_back_count;
+    let tabu_duration = self.tabu_duration;
+    // Violation and cmax of the move selected so far
+    let mut selected_violation = 0;
+    let mut selected_cmax = 0;
+    // Lowest cmax of the skipped tabu moves that improve the current solution
+    let mut blocked_cmax: Option<u32> = None;
+    let search_start = self.start.elapsed();
+    let maybe_move = self.neighborhood.find_move(
+      current,
       &mut |maybe_best, candidate| {
+        if required.contains(&candidate.swap_move) {
+          trace!(
+            "Skipping move {:?} because it reverses a required order",
+            candidate
+          );
+          return false;
+        }
         let (a, b) = candidate.swap_move;
+        // With a cache, moves are evaluated by the exact cmax of the neighbor instead of the estimate of the neighborhood
+        let (candidate_cmax, candidate_violation) = match solution_cache.as_mut() {
+          Some(cache) => {
+            let neighbor = cache.apply_swap(current, a, b);
+            let violation = config
+              .constraint
+              .map_or(0, |constraint| constraint.violation(neighbor));
+            (neighbor.cmax(), violation)
+          }
+          None => (
+            candidate.cmax,
+            move_violation(current, candidate, config.constraint),
+          ),
+        };
+        if current_violation == 0 && candidate_violation > 0 {
+          trace!(
+            "Skipping move {:?} because it violates the constraint by {}",
+            candidate,
+            candidate_violation
+          );
+          return false;
+        }
+
         let tabu_until = op_last_swap[a] + tabu_duration;
         if iteration < tabu_until {
           // Aspiration criterion (globally better move)
-          if candidate.cmax < best.cmax {
+          if candidate_cmax < best.cmax && candidate_violation <= best_violation {
             trace!(
               "Including tabu move {:?} because it is better than global best {:?} < {:?}",
               candidate,
-              candidate.cmax,
+              candidate_cmax,
               best.cmax
             );
           } else {
@@ -55,69 +351,702 @@ pub fn find_solution(inst: &Instance, config: &Config) -> IntermediateSolution {
               candidate,
               tabu_until
             );
+            // Exact even without a cache, the estimate of the neighborhood may miss improvements
+            if let Some(cmax) = current.evaluate_swap(a, b) {
+              if cmax < current.cmax && blocked_cmax.map_or(true, |blocked| cmax < blocked) {
+                blocked_cmax = Some(cmax);
+              }
+            }
             return false;
           }
         }
 
-        if let Some(current_best) = maybe_best {
+        let accepted = if let Some(current_best) = maybe_best {
+          if candidate_violation != selected_violation {
+            return if candidate_violation < selected_violation {
+              selected_violation = candidate_violation;
+              selected_cmax = candidate_cmax;
+              true
+            } else {
+              false
+            };
+          }
+
           let candidate_penalty =
             penalty_factor * op_push_back_count[b] as f32 / total_push_back_count as f32;
           let current_penalty = penalty_factor
             * op_push_back_count[current_best.swap_move.1] as f32
             / total_push_back_count as f32;
-          let candidate_evaluation = candidate.cmax as f32 + candidate_penalty;
-          let current_evaluation = current_best.cmax as f32 + current_penalty;
+          let candidate_evaluation = candidate_cmax as f32 + candidate_penalty;
+          let current_evaluation = selected_cmax as f32 + current_penalty;
           candidate_evaluation < current_evaluation
+            || (candidate_evaluation == current_evaluation
+              && compare_tied_moves(current, candidate, current_best, config.secondary_objective)
+                == Ordering::Less)
         } else {
           true
+        };
+        if accepted {
+          selected_violation = candidate_violation;
+          selected_cmax = candidate_cmax;
         }
+        accepted
       },
-      n1::SearchMethod::Exhaustive,
+      config.search_method,
+      config.candidate_order,
+      &mut self.tie_breaker,
     );
+    if let Some(blocked_cmax) = blocked_cmax {
+      let selected_exact_cmax = maybe_move.as_ref().and_then(|selected| {
+        self
+          .current
+          .evaluate_swap(selected.swap_move.0, selected.swap_move.1)
+      });
+      if selected_exact_cmax.map_or(true, |cmax| cmax > blocked_cmax) {
+        self.tabu_blocked_improvements += 1;
+      }
+    }
 
-    if let Some(next_move) = maybe_move {
+    let stagnating = config
+      .max_stagnant_iterations
+      .map_or(false, |max| self.stagnant_iterations >= max);
+    if let (Some(next_move), false) = (maybe_move, stagnating) {
       let swap_move = next_move.swap_move;
       let (a, b) = swap_move;
-      let delta = next_move.cmax.saturating_sub(current.cmax);
-      max_delta = cmp::max(max_delta, delta);
+      let delta = selected_cmax.saturating_sub(self.current.cmax);
+      self.max_delta = cmp::max(self.max_delta, delta);
 
-      current = current.apply_swap(a, b);
-      op_last_swap[b] = iteration;
-      op_push_back_count[b] += 1;
-      total_push_back_count += 1;
+      self.current = match &mut self.solution_cache {
+        Some(cache) => cache.apply_swap(&self.current, a, b).clone(),
+        None => self.current.apply_swap(a, b),
+      };
+      self.op_last_swap[b] = iteration;
+      self.op_push_back_count[b] += 1;
+      self.total_push_back_count += 1;
+      self.search += self
+        .start
+        .elapsed()
+        .checked_sub(search_start)
+        .unwrap_or_default();
       trace!(
         "Found move {:?} to {} ({})",
         swap_move,
-        current.cmax,
+        self.current.cmax,
         iteration
       );
-      trace!("Current solution {:?} ({})", current, iteration);
-      #[cfg(debug_assertions)]
-      crate::solver::verify_solution(&inst, &current.to_solution()).expect("Verification failed");
+      trace!("Current solution {:?} ({})", self.current, iteration);
+      verify_step(config.verify, self.inst, || self.current.to_solution());
     } else {
-      debug!("Did not find move, resetting ({})", iteration);
-      current = generate_solution(inst, &mut rng);
-      op_last_swap.fill(i32::min_value());
-      op_push_back_count.fill(0);
-      total_push_back_count = 0;
-      max_delta = 0;
+      self.search += self
+        .start
+        .elapsed()
+        .checked_sub(search_start)
+        .unwrap_or_default();
+      if stagnating {
+        debug!(
+          "Best did not improve for {} iterations, resetting ({})",
+          self.stagnant_iterations, iteration
+        );
+      } else {
+        debug!("Did not find move, resetting ({})", iteration);
+      }
+      let inst = self.inst;
+      let rng = &mut self.rng;
+      self.current = timed(&self.start, &mut self.construction, || {
+        generate_solution(inst, rng, config.run.init_bias, &config.required_edges)
+      });
+      self.op_last_swap.fill(i32::MIN);
+      self.op_push_back_count.fill(0);
+      self.total_push_back_count = 0;
+      self.max_delta = 0;
+      self.stagnant_iterations = 0;
     }
 
-    if current.cmax() < best.cmax() {
-      best = current.clone();
-      debug!("Improved best to {} ({})", best.cmax(), iteration);
+    if is_better(
+      &self.current,
+      &self.best,
+      config.secondary_objective,
+      config.constraint,
+    ) {
+      self.best = self.current.clone();
+      self.stagnant_iterations = 0;
+      self.last_improvement = iteration;
+      publish_best(&config.run.shared_best, self.best.cmax());
+      self
+        .stall
+        .observe(iteration as u64, &self.best, config.constraint);
+      debug!("Improved best to {} ({})", self.best.cmax(), iteration);
+      if config.run.collect_improvements {
+        self
+          .improvements
+          .push((iteration as u64, self.best.cmax(), self.best.to_solution()));
+      }
+    } else {
+      self.stagnant_iterations += 1;
+    }
+    if let Some(trajectory) = &mut self.trajectory {
+      trajectory.offer(iteration as u64, self.current.cmax());
+    }
+    if let Some(visited) = &mut self.visited {
+      visited.observe(&self.current);
     }
+    self
+      .time_checkpoints
+      .observe(&self.start, &self.best, config.constraint);
+    let (previous_stats, start, construction, search) = (
+      &self.previous_stats,
+      &self.start,
+      self.construction,
+      self.search,
+    );
+    self.checkpointer.maybe_save(&self.best, || {
+      stats_at(previous_stats, start, iteration, construction, search)
+    });
 
-    iteration += 1;
+    self.iteration += 1;
   }
 
-  info!("Stopping due to timeout at {} ({})", best.cmax(), iteration);
+  fn finish(mut self) -> (IntermediateSolution, SolveStats) {
+    if self.stall.is_stalled(self.iteration as u64) {
+      info!(
+        "Stopping because the best stalled at {} ({})",
+        self.best.cmax(),
+        self.iteration
+      );
+    } else {
+      info!(
+        "Stopping due to timeout at {} ({})",
+        self.best.cmax(),
+        self.iteration
+      );
+    }
+
+    if let Some(cache) = &self.solution_cache {
+      debug!(
+        "Solution cache: {} hits, {} misses, {} of {} entries",
+        cache.hits(),
+        cache.misses(),
+        cache.len(),
+        cache.capacity()
+      );
+    }
+
+    let mut stats = self.stats();
+    stats.timeout_fraction_used = timeout_fraction(self.start.elapsed(), self.config.timeout);
+    self.checkpointer.save(&self.best, &stats);
+    stats.improvements = self.improvements;
+    stats.trajectory = finish_trajectory(self.trajectory, self.iteration as u64, self.best.cmax());
+    stats.orientation_distance_from_start = self.best.arc_distance(&self.start_key);
+    stats.checkpoint_cmax = self.time_checkpoints.finish(&self.best);
+    stats.tabu_blocked_improvements = self.tabu_blocked_improvements;
+    stats.unique_solutions_visited = self.visited.map_or(0, |visited| visited.count());
+
+    return (self.best, stats);
+  }
+}
 
-  return best;
+// Statistics including those of the resumed run
+fn stats_at(
+  previous_stats: &SolveStats,
+  start: &Stopwatch,
+  iteration: i32,
+  construction: Duration,
+  search: Duration,
+) -> SolveStats {
+  return SolveStats {
+    iterations: iteration as u64,
+    elapsed: previous_stats.elapsed + start.elapsed(),
+    construction: previous_stats.construction + construction,
+    search: previous_stats.search + search,
+    ..SolveStats::default()
+  };
 }
 
-fn generate_solution<R: Rng>(inst: &Instance, rng: &mut R) -> IntermediateSolution {
-  let orientation = get_orientation_from_schedule(inst, &generate_random_solution(inst, rng));
+fn generate_solution<R: Rng>(
+  inst: &Instance,
+  rng: &mut R,
+  bias: Option<DurationBias>,
+  required_edges: &[Edge],
+) -> IntermediateSolution {
+  let orientation = repair_orientation(
+    inst,
+    &generate_initial_solution(inst, rng, bias),
+    required_edges,
+  );
 
   return IntermediateSolution::new(inst.clone(), orientation);
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::required_order::{required_edges, RequiredOrder};
+  use crate::solver::termination::TargetCmax;
+  use crate::solver::{generate_random_solution_seeded, verify_solution};
+  use crate::test_instances::{assert_improvements, ft06, StepClock};
+  use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
+  use std::thread;
+
+  fn config(seed: u64, iterations: u64) -> Config {
+    return Config {
+      timeout: Duration::from_secs(u64::MAX),
+      seed: seed,
+      verify: false,
+      run: RunOptions {
+        max_iterations: Some(iterations),
+        ..RunOptions::default()
+      },
+      unique_solutions_cap: None,
+      termination: None,
+      max_stagnant_iterations: None,
+      neighborhood: DEFAULT_NEIGHBORHOOD,
+      skip_symmetric_swaps: false,
+      search_method: SearchMethod::Exhaustive,
+      candidate_order: CandidateOrder::Generated,
+      tie_break: TieBreak::Generated,
+      secondary_objective: None,
+      constraint: None,
+      required_edges: Vec::new(),
+      solution_cache_capacity: None,
+      penalty_coefficient: DEFAULT_PENALTY_COEFFICIENT,
+      penalty_exponent: DEFAULT_PENALTY_EXPONENT,
+      zero_timeout_rule: None,
+    };
+  }
+
+  #[test]
+  fn shared_best_can_be_polled() {
+    let shared_best = Arc::new(AtomicU32::new(u32::MAX));
+    let handle = {
+      let shared_best = shared_best.clone();
+      thread::spawn(move || {
+        let config = Config {
+          run: RunOptions {
+            shared_best: Some(shared_best),
+            max_iterations: Some(2000),
+            ..RunOptions::default()
+          },
+          ..config(0, 2000)
+        };
+        find_solution(&ft06(), &config).0.cmax()
+      })
+    };
+    let mut polled = u32::MAX;
+    while !handle.is_finished() {
+      let best = shared_best.load(AtomicOrdering::SeqCst);
+      assert!(best <= polled, "The shared best increased");
+      polled = best;
+      thread::yield_now();
+    }
+    let cmax = handle.join().unwrap();
+    assert_eq!(shared_best.load(AtomicOrdering::SeqCst), cmax);
+  }
+
+  #[test]
+  fn improvements_strictly_decrease() {
+    let inst = ft06();
+    let config = Config {
+      run: RunOptions {
+        collect_improvements: true,
+        max_iterations: Some(500),
+        ..RunOptions::default()
+      },
+      ..config(0, 500)
+    };
+    let (best, stats) = find_solution(&inst, &config);
+    assert_improvements(&inst, &stats, &best);
+  }
+
+  #[test]
+  fn stagnation_restarts_keep_the_best() {
+    let inst = ft06();
+    let config = Config {
+      max_stagnant_iterations: Some(5),
+      ..config(0, 500)
+    };
+    let mut search = Search::new(&inst, &config);
+    let mut restarts = 0;
+    let mut min_cmax = search.current.cmax();
+    while !search.is_finished() {
+      let best_cmax = search.best.cmax();
+      search.step();
+      // Only a restart resets the push back counts
+      if search.total_push_back_count == 0 {
+        restarts += 1;
+      }
+      assert!(search.best.cmax() <= best_cmax);
+      min_cmax = cmp::min(min_cmax, search.current.cmax());
+    }
+    assert!(restarts > 1, "{}", restarts);
+    assert_eq!(search.finish().0.cmax(), min_cmax);
+  }
+
+  #[test]
+  fn fixed_start_seed() {
+    let inst = ft06();
+    let fixed_start = generate_random_solution_seeded(&inst, 7);
+    for seed in 0..2 {
+      let config = Config {
+        run: RunOptions {
+          start_seed: Some(7),
+          collect_improvements: true,
+          max_iterations: Some(10),
+          ..RunOptions::default()
+        },
+        ..config(seed, 10)
+      };
+      let (_, stats) = find_solution(&inst, &config);
+      assert_eq!(stats.improvements[0].2.start_times, fixed_start.start_times);
+    }
+  }
+
+  #[test]
+  fn stalled_search_stops_before_the_timeout() {
+    let inst = ft06();
+    let config = Config {
+      timeout: Duration::from_secs(60),
+      run: RunOptions {
+        stall_window: Some(100),
+        collect_improvements: true,
+        ..RunOptions::default()
+      },
+      ..config(0, 0)
+    };
+    let (best, stats) = find_solution(&inst, &config);
+    assert!(stats.elapsed < config.timeout);
+    assert!(stats.timeout_fraction_used < 1.0);
+    // Stops as soon as the best did not improve during the whole window
+    let &(last_improvement, cmax, _) = stats.improvements.last().unwrap();
+    assert_eq!(cmax, best.cmax());
+    assert_eq!(stats.iterations, last_improvement + 100);
+  }
+
+  #[test]
+  fn timeout_fraction_used() {
+    let inst = ft06();
+    // The sps solution already reaches its cmax of 60, the start solution typically does not
+    let target_config = Config {
+      timeout: Duration::from_secs(20),
+      run: RunOptions::default(),
+      termination: Some(Arc::new(TargetCmax(60))),
+      ..config(0, 0)
+    };
+    let (best, stats) = find_solution(&inst, &target_config);
+    assert!(best.cmax() <= 60);
+    assert!(
+      stats.timeout_fraction_used < 0.5,
+      "{}",
+      stats.timeout_fraction_used
+    );
+
+    let timeout_config = Config {
+      timeout: Duration::from_millis(200),
+      run: RunOptions::default(),
+      ..config(0, 0)
+    };
+    let (_, stats) = find_solution(&inst, &timeout_config);
+    assert!(
+      (1.0..1.5).contains(&stats.timeout_fraction_used),
+      "{}",
+      stats.timeout_fraction_used
+    );
+  }
+
+  #[test]
+  fn required_orders_are_respected() {
+    let inst = ft06();
+    let (unconstrained, _) = find_solution(&inst, &config(0, 300));
+    // Reverse the first and last job on machines 0 and 3 of the unconstrained solution
+    let orders: Vec<RequiredOrder> = [0, 3]
+      .iter()
+      .map(|&machine| {
+        let mut ops: Vec<usize> = inst
+          .op_ids()
+          .into_iter()
+          .filter(|&op| inst.machines[op] == machine)
+          .collect();
+        ops.sort_by_key(|&op| unconstrained.release_times[op]);
+        RequiredOrder {
+          machine: machine,
+          before_job: inst.op_from_id(*ops.last().unwrap())[0],
+          after_job: inst.op_from_id(ops[0])[0],
+        }
+      })
+      .collect();
+    let edges = required_edges(&inst, &orders).unwrap();
+    for seed in 0..5 {
+      let (solution, stats) = find_solution(
+        &inst,
+        &Config {
+          required_edges: edges.clone(),
+          max_stagnant_iterations: Some(50),
+          run: RunOptions {
+            collect_improvements: true,
+            max_iterations: Some(300),
+            ..RunOptions::default()
+          },
+          ..config(seed, 300)
+        },
+      );
+      for (_, _, improvement) in stats
+        .improvements
+        .iter()
+        .chain(&[(0, 0, solution.to_solution())])
+      {
+        verify_solution(&inst, improvement).unwrap();
+        for &(a, b) in &edges {
+          assert!(improvement.start_times[a] + inst.durations[a] <= improvement.start_times[b]);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn iterate_steps() {
+    let inst = ft06();
+    let config = config(0, 500);
+    let cmaxs: Vec<u32> = iter(&inst, &config)
+      .take(200)
+      .map(|best| best.cmax())
+      .collect();
+    assert_eq!(cmaxs.len(), 200);
+    assert!(
+      cmaxs.windows(2).all(|pair| pair[1] <= pair[0]),
+      "{:?}",
+      cmaxs
+    );
+
+    // Running to the end yields one best solution per iteration, the last equal to the one of find_solution
+    let steps: Vec<IntermediateSolution> = iter(&inst, &config).collect();
+    assert_eq!(steps.len(), 500);
+    assert_eq!(steps[199].cmax(), cmaxs[199]);
+    let (solution, _) = find_solution(&inst, &config);
+    assert_eq!(
+      steps.last().unwrap().to_solution().start_times,
+      solution.to_solution().start_times
+    );
+  }
+
+  #[test]
+  fn solution_cache() {
+    let inst = ft06();
+    let results: Vec<(u32, u32)> = (0..3)
+      .map(|seed| {
+        let (uncached, _) = find_solution(&inst, &config(seed, 300));
+        let (cached, _) = find_solution(
+          &inst,
+          &Config {
+            solution_cache_capacity: Some(100),
+            ..config(seed, 300)
+          },
+        );
+        verify_solution(&inst, &cached.to_solution()).unwrap();
+        (uncached.cmax(), cached.cmax())
+      })
+      .collect();
+    // Evaluating the moves exactly changes the search
+    assert_eq!(results, vec![(55, 55), (55, 55), (55, 57)]);
+  }
+
+  #[test]
+  fn zero_timeout() {
+    let inst = ft06();
+    let rule_solutions: Vec<IntermediateSolution> = PriorityRule::ALL
+      .iter()
+      .map(|rule| IntermediateSolution::from_solution(&inst, &rule.find_solution(&inst)).unwrap())
+      .collect();
+    let zero_timeout = Config {
+      timeout: Duration::from_secs(0),
+      ..config(0, 300)
+    };
+    let (solution, stats) = find_solution(&inst, &zero_timeout);
+    assert_eq!(stats.iterations, 0);
+    assert_eq!(
+      solution.cmax,
+      rule_solutions
+        .iter()
+        .map(|solution| solution.cmax)
+        .min()
+        .unwrap()
+    );
+    let start =
+      IntermediateSolution::from_solution(&inst, &generate_random_solution_seeded(&inst, 0))
+        .unwrap();
+    assert!(solution.cmax < start.cmax);
+
+    // With a constraint the rule of the lowest violation wins
+    let constraint = Constraint {
+      objective: Objective::TotalFlowTime,
+      max_value: 0,
+    };
+    let (solution, _) = find_solution(
+      &inst,
+      &Config {
+        constraint: Some(constraint),
+        ..zero_timeout
+      },
+    );
+    assert_eq!(
+      constraint.violation(&solution),
+      rule_solutions
+        .iter()
+        .map(|solution| constraint.violation(solution))
+        .min()
+        .unwrap()
+    );
+  }
+
+  #[test]
+  fn orientation_distance_from_start() {
+    let inst = ft06();
+    let (_, stats) = find_solution(&inst, &config(0, 0));
+    assert_eq!(stats.orientation_distance_from_start, 0);
+    let (_, stats) = find_solution(&inst, &config(0, 300));
+    assert!(stats.orientation_distance_from_start > 0);
+  }
+
+  #[test]
+  fn time_checkpoints() {
+    let inst = ft06();
+    let checkpoints = vec![
+      Duration::from_millis(5),
+      Duration::from_millis(50),
+      Duration::from_millis(200),
+      Duration::from_secs(3600),
+    ];
+    let (best, stats) = find_solution(
+      &inst,
+      &Config {
+        run: RunOptions {
+          time_checkpoints: checkpoints.clone(),
+          clock: Some(Arc::new(StepClock::new())),
+          ..RunOptions::default()
+        },
+        timeout: Duration::from_millis(500),
+        ..config(0, u64::MAX)
+      },
+    );
+    let recorded: Vec<Duration> = stats.checkpoint_cmax.iter().map(|&(c, _)| c).collect();
+    assert_eq!(recorded, checkpoints);
+    for (&(_, cmax), &(_, next_cmax)) in stats
+      .checkpoint_cmax
+      .iter()
+      .zip(&stats.checkpoint_cmax[1..])
+    {
+      assert!(next_cmax <= cmax);
+    }
+    assert!(stats.checkpoint_cmax[0].1 > best.cmax);
+    assert_eq!(stats.checkpoint_cmax.last().unwrap().1, best.cmax);
+  }
+
+  #[test]
+  fn zero_penalty_coefficient() {
+    let inst = ft06();
+    let improvements = |coefficient: f32, exponent: f32| {
+      let (_, stats) = find_solution(
+        &inst,
+        &Config {
+          penalty_coefficient: coefficient,
+          penalty_exponent: exponent,
+          run: RunOptions {
+            collect_improvements: true,
+            max_iterations: Some(500),
+            ..RunOptions::default()
+          },
+          ..config(0, 500)
+        },
+      );
+      stats
+        .improvements
+        .iter()
+        .map(|&(iteration, cmax, _)| (iteration, cmax))
+        .collect::<Vec<_>>()
+    };
+    // Without the penalty term the exponent has no effect, every move is chosen by cmax alone
+    let unpenalized = improvements(0.0, DEFAULT_PENALTY_EXPONENT);
+    assert_eq!(improvements(0.0, 3.0), unpenalized);
+    assert_ne!(
+      improvements(DEFAULT_PENALTY_COEFFICIENT, DEFAULT_PENALTY_EXPONENT),
+      unpenalized
+    );
+  }
+
+  #[test]
+  fn tabu_blocked_improvements() {
+    let inst = ft06();
+    let config = config(3, 500);
+    let mut search = Search::new(&inst, &config);
+    let mut blocked_iterations = 0;
+    while !search.is_finished() {
+      let current = search.current.clone();
+      let (iteration, best_cmax) = (search.iteration, search.best.cmax);
+      // Lowest exact cmax of the improving tabu moves without aspiration
+      let blocked = search
+        .neighborhood
+        .generate_moves(&current)
+        .iter()
+        .filter(|m| {
+          iteration < search.op_last_swap[m.swap_move.0] + search.tabu_duration
+            && m.cmax >= best_cmax
+        })
+        .filter_map(|m| current.evaluate_swap(m.swap_move.0, m.swap_move.1))
+        .filter(|&cmax| cmax < current.cmax)
+        .min();
+      let count = search.tabu_blocked_improvements;
+      search.step();
+      // A restart follows if there was no admissible move
+      let selected_cmax = if search.total_push_back_count == 0 {
+        None
+      } else {
+        Some(search.current.cmax)
+      };
+      let expected = match (blocked, selected_cmax) {
+        (Some(blocked), Some(selected)) => selected > blocked,
+        (Some(_), None) => true,
+        (None, _) => false,
+      };
+      assert_eq!(
+        search.tabu_blocked_improvements - count,
+        expected as u64,
+        "{}",
+        iteration
+      );
+      blocked_iterations += expected as u64;
+    }
+    assert!(blocked_iterations > 0);
+    assert_eq!(
+      search.finish().1.tabu_blocked_improvements,
+      blocked_iterations
+    );
+  }
+
+  #[test]
+  fn unique_solutions_visited() {
+    let inst = ft06();
+    let iterations = 300;
+    let (_, stats) = find_solution(&inst, &config(0, iterations));
+    assert_eq!(stats.unique_solutions_visited, 0);
+
+    let (_, stats) = find_solution(
+      &inst,
+      &Config {
+        unique_solutions_cap: Some(usize::MAX),
+        ..config(0, iterations)
+      },
+    );
+    assert!(stats.unique_solutions_visited > 0);
+    assert!(stats.unique_solutions_visited as u64 <= iterations);
+
+    let (_, capped) = find_solution(
+      &inst,
+      &Config {
+        unique_solutions_cap: Some(10),
+        ..config(0, iterations)
+      },
+    );
+    assert_eq!(
+      capped.unique_solutions_visited,
+      cmp::min(10, stats.unique_solutions_visited)
+    );
+  }
+}
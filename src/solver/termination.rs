@@ -0,0 +1,139 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+// Progress of a search as seen by the termination criteria
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchState {
+  // Elapsed time of this run (excluding resumed runs)
+  pub elapsed: Duration,
+  // Iterations in total, including resumed ones
+  pub iteration: u64,
+  // Cmax of the best solution satisfying the constraint (if any), None while there is none
+  pub best_cmax: Option<u32>,
+  // Iterations since the best solution last improved (or the search started)
+  pub iterations_since_improvement: u64,
+}
+
+// Stopping rule of a search, consulted before every iteration
+pub trait TerminationCriterion: Send + Sync {
+  fn should_stop(&self, state: &SearchState) -> bool;
+}
+
+// Shared criteria, e.g. of a config, can be combined as well
+impl<T: TerminationCriterion + ?Sized> TerminationCriterion for Arc<T> {
+  fn should_stop(&self, state: &SearchState) -> bool {
+    return (**self).should_stop(state);
+  }
+}
+
+// Stops once the elapsed time of this run reaches the timeout
+pub struct Timeout(pub Duration);
+
+impl TerminationCriterion for Timeout {
+  fn should_stop(&self, state: &SearchState) -> bool {
+    return state.elapsed >= self.0;
+  }
+}
+
+// Stops after this many iterations in total
+pub struct MaxIterations(pub u64);
+
+impl TerminationCriterion for MaxIterations {
+  fn should_stop(&self, state: &SearchState) -> bool {
+    return state.iteration >= self.0;
+  }
+}
+
+// Stops once the best cmax is at most the target, e.g. a known lower bound
+pub struct TargetCmax(pub u32);
+
+impl TerminationCriterion for TargetCmax {
+  fn should_stop(&self, state: &SearchState) -> bool {
+    return state.best_cmax.map_or(false, |cmax| cmax <= self.0);
+  }
+}
+
+// Stops once the best solution did not improve for this many iterations (unlike the stall window of the configs without
+// a tolerance, every improvement counts)
+pub struct Stall(pub u64);
+
+impl TerminationCriterion for Stall {
+  fn should_stop(&self, state: &SearchState) -> bool {
+    return state.iterations_since_improvement >= self.0;
+  }
+}
+
+// Stops once any of the criteria is met, never without criteria
+pub struct AnyOf(pub Vec<Box<dyn TerminationCriterion>>);
+
+impl TerminationCriterion for AnyOf {
+  fn should_stop(&self, state: &SearchState) -> bool {
+    return self.0.iter().any(|criterion| criterion.should_stop(state));
+  }
+}
+
+// Stops once all of the criteria are met, immediately without criteria
+pub struct AllOf(pub Vec<Box<dyn TerminationCriterion>>);
+
+impl TerminationCriterion for AllOf {
+  fn should_stop(&self, state: &SearchState) -> bool {
+    return self.0.iter().all(|criterion| criterion.should_stop(state));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn state(
+    elapsed_ms: u64,
+    iteration: u64,
+    best_cmax: Option<u32>,
+    since_improvement: u64,
+  ) -> SearchState {
+    return SearchState {
+      elapsed: Duration::from_millis(elapsed_ms),
+      iteration: iteration,
+      best_cmax: best_cmax,
+      iterations_since_improvement: since_improvement,
+    };
+  }
+
+  #[test]
+  fn concrete_criteria() {
+    let timeout = Timeout(Duration::from_millis(100));
+    assert!(!timeout.should_stop(&state(99, 0, None, 0)));
+    assert!(timeout.should_stop(&state(100, 0, None, 0)));
+
+    let max_iterations = MaxIterations(10);
+    assert!(!max_iterations.should_stop(&state(0, 9, None, 0)));
+    assert!(max_iterations.should_stop(&state(0, 10, None, 0)));
+
+    let target = TargetCmax(55);
+    assert!(!target.should_stop(&state(0, 0, None, 0)));
+    assert!(!target.should_stop(&state(0, 0, Some(56), 0)));
+    assert!(target.should_stop(&state(0, 0, Some(55), 0)));
+
+    let stall = Stall(5);
+    assert!(!stall.should_stop(&state(0, 100, Some(60), 4)));
+    assert!(stall.should_stop(&state(0, 100, Some(60), 5)));
+
+    let shared: Arc<dyn TerminationCriterion> = Arc::new(MaxIterations(1));
+    assert!(shared.should_stop(&state(0, 1, None, 0)));
+  }
+
+  #[test]
+  fn combinators() {
+    let any = AnyOf(vec![Box::new(MaxIterations(10)), Box::new(TargetCmax(55))]);
+    assert!(!any.should_stop(&state(0, 5, Some(60), 0)));
+    assert!(any.should_stop(&state(0, 10, Some(60), 0)));
+    assert!(any.should_stop(&state(0, 5, Some(55), 0)));
+    assert!(!AnyOf(Vec::new()).should_stop(&state(0, 5, Some(55), 0)));
+
+    let all = AllOf(vec![Box::new(MaxIterations(10)), Box::new(TargetCmax(55))]);
+    assert!(!all.should_stop(&state(0, 10, Some(60), 0)));
+    assert!(!all.should_stop(&state(0, 5, Some(55), 0)));
+    assert!(all.should_stop(&state(0, 10, Some(55), 0)));
+    assert!(AllOf(Vec::new()).should_stop(&state(0, 0, None, 0)));
+  }
+}
@@ -0,0 +1,62 @@
+use crate::solver::clock::Stopwatch;
+use crate::solver::objective::{violation, Constraint};
+use crate::solver::IntermediateSolution;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// Records the best cmax of the feasible solutions seen so far whenever the elapsed time of a search passes one of the
+// checkpoints, e.g. for convergence tables
+pub(crate) struct TimeCheckpoints {
+  // Sorted, without the recorded ones
+  pending: VecDeque<Duration>,
+  best: Option<u32>,
+  recorded: Vec<(Duration, u32)>,
+}
+
+impl TimeCheckpoints {
+  pub(crate) fn new(checkpoints: &[Duration]) -> Self {
+    let mut pending = checkpoints.to_vec();
+    pending.sort_unstable();
+    pending.dedup();
+    return TimeCheckpoints {
+      pending: pending.into(),
+      best: None,
+      recorded: Vec::new(),
+    };
+  }
+
+  // Infeasible solutions are ignored, checkpoints passed before the first feasible solution are recorded with it
+  pub(crate) fn observe(
+    &mut self,
+    start: &Stopwatch,
+    solution: &IntermediateSolution,
+    constraint: Option<Constraint>,
+  ) {
+    if self.pending.is_empty() {
+      return;
+    }
+    if violation(solution, constraint) == 0 && self.best.map_or(true, |best| solution.cmax() < best)
+    {
+      self.best = Some(solution.cmax());
+    }
+    if let Some(best) = self.best {
+      let elapsed = start.elapsed();
+      while self
+        .pending
+        .front()
+        .map_or(false, |&checkpoint| checkpoint <= elapsed)
+      {
+        let checkpoint = self.pending.pop_front().unwrap();
+        self.recorded.push((checkpoint, best));
+      }
+    }
+  }
+
+  // Checkpoints after the end of the search get the cmax of the returned solution
+  pub(crate) fn finish(mut self, best: &IntermediateSolution) -> Vec<(Duration, u32)> {
+    for checkpoint in self.pending.drain(..) {
+      self.recorded.push((checkpoint, best.cmax()));
+    }
+    return self.recorded;
+  }
+}
@@ -0,0 +1,34 @@
+use crate::solver::IntermediateSolution;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+// Distinct orientations seen by a search, deduplicated by the hash of their orientation key (so colliding orientations
+// count once), e.g. to tell whether a search cycles among few states
+// Stops inserting at the cap, so the count is exact below the cap and a lower bound at it
+pub(crate) struct VisitedSolutions {
+  cap: usize,
+  hashes: HashSet<u64>,
+}
+
+impl VisitedSolutions {
+  pub(crate) fn new(cap: usize) -> Self {
+    return VisitedSolutions {
+      cap: cap,
+      hashes: HashSet::new(),
+    };
+  }
+
+  pub(crate) fn observe(&mut self, solution: &IntermediateSolution) {
+    if self.hashes.len() >= self.cap {
+      return;
+    }
+    let mut hasher = DefaultHasher::new();
+    solution.orientation_key().hash(&mut hasher);
+    self.hashes.insert(hasher.finish());
+  }
+
+  pub(crate) fn count(&self) -> usize {
+    return self.hashes.len();
+  }
+}
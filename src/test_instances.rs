@@ -0,0 +1,44 @@
+use crate::data::Instance;
+use crate::parser::parse_instance;
+use crate::solver::clock::Clock;
+use crate::solver::{calculate_cmax, IntermediateSolution, SolveStats};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// Fisher and Thompson's 6x6 instance with the optimal makespan 55
+pub(crate) fn ft06() -> Instance {
+  return parse_instance(include_str!("../tests/data/ft06.txt")).unwrap();
+}
+
+// Checks the collected improvements of a run that returned best: several, with strictly decreasing cmax and matching
+// solutions, ending with the best
+pub(crate) fn assert_improvements(
+  inst: &Instance,
+  stats: &SolveStats,
+  best: &IntermediateSolution,
+) {
+  assert!(stats.improvements.len() > 1);
+  for ((iteration, cmax, solution), (next_iteration, next_cmax, _)) in
+    stats.improvements.iter().zip(&stats.improvements[1..])
+  {
+    assert!(next_cmax < cmax);
+    assert!(next_iteration >= iteration);
+    assert_eq!(calculate_cmax(inst, solution), *cmax);
+  }
+  assert_eq!(stats.improvements.last().unwrap().1, best.cmax());
+}
+
+// Advances by a millisecond on every reading
+pub(crate) struct StepClock(AtomicU64);
+
+impl StepClock {
+  pub(crate) fn new() -> Self {
+    return StepClock(AtomicU64::new(0));
+  }
+}
+
+impl Clock for StepClock {
+  fn now(&self) -> Duration {
+    return Duration::from_millis(self.0.fetch_add(1, Ordering::SeqCst));
+  }
+}
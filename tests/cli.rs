@@ -0,0 +1,412 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{self, Command, Output, Stdio};
+
+fn heuristics(args: &[&str]) -> Output {
+  return Command::new(env!("CARGO_BIN_EXE_heuristics"))
+    .args(args)
+    .output()
+    .expect("Failed to run heuristics");
+}
+
+fn heuristics_with_stdin(args: &[&str], stdin: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_heuristics"))
+    .args(args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .expect("Failed to run heuristics");
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(stdin.as_bytes())
+    .expect("Failed to write stdin");
+  return child.wait_with_output().expect("Failed to run heuristics");
+}
+
+fn stdout(output: &Output) -> String {
+  return String::from_utf8_lossy(&output.stdout).into_owned();
+}
+
+fn stderr(output: &Output) -> String {
+  return String::from_utf8_lossy(&output.stderr).into_owned();
+}
+
+const FT06: &str = "tests/data/ft06.txt";
+const MALFORMED: &str = "tests/data/malformed.txt";
+// Optimal with job 1 first on machine 0 (cmax 8), job 0 first gives 9
+const TINY: &str = "tests/data/tiny.txt";
+// Optimum 11, reached by priority-sps but not by priority-mwrm (12)
+const SMALL: &str = "tests/data/3x3.txt";
+
+#[test]
+fn dry_run() {
+  let output = heuristics(&["--instance", FT06, "--dry-run"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let stdout = stdout(&output);
+  assert!(stdout.contains("n_jobs: 6\n"), "{}", stdout);
+  assert!(stdout.contains("n_machines: 6\n"), "{}", stdout);
+  assert!(stdout.contains("n_ops: 36\n"), "{}", stdout);
+
+  let output = heuristics(&["--instance", MALFORMED, "--dry-run"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("line 2"), "{}", stderr(&output));
+}
+
+#[test]
+fn resume_requires_the_same_solver() {
+  let path = env::temp_dir().join(format!("heuristics-cli-{}.checkpoint", process::id()));
+  let path = path.to_str().unwrap();
+  let solve = |solver: &str, checkpoint_arg: &str| {
+    heuristics(&[
+      "--instance",
+      FT06,
+      "--solver",
+      solver,
+      "--timeout",
+      "1",
+      "--seed",
+      "0",
+      "--no-verify",
+      checkpoint_arg,
+      path,
+    ])
+  };
+  let output = solve("tabu-search", "--checkpoint");
+  assert!(output.status.success(), "{}", stderr(&output));
+  let output = solve("tabu-search", "--resume");
+  assert!(output.status.success(), "{}", stderr(&output));
+  let output = solve("simulated-annealing", "--resume");
+  assert!(!output.status.success());
+  assert!(
+    stderr(&output).contains("saved by tabu-search"),
+    "{}",
+    stderr(&output)
+  );
+  fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn assert_cmax_leq() {
+  let solve = |threshold: &str| {
+    heuristics(&[
+      "--instance",
+      FT06,
+      "--solver",
+      "priority-sps",
+      "--timeout",
+      "0",
+      "--assert-cmax-leq",
+      threshold,
+    ])
+  };
+  // priority-sps finds a makespan of 60 on ft06
+  let output = solve("60");
+  assert!(output.status.success(), "{}", stderr(&output));
+  let output = solve("1");
+  assert!(!output.status.success());
+  assert!(
+    stderr(&output).contains("cmax 60 exceeds threshold 1"),
+    "{}",
+    stderr(&output)
+  );
+}
+
+#[test]
+fn rule_optimality() {
+  let output = heuristics(&["--instance", TINY, "--rule-optimality"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let stdout = stdout(&output);
+  assert!(stdout.starts_with("optimum: 8\n"), "{}", stdout);
+  assert!(stdout.contains("mwrm: 8\n"), "{}", stdout);
+  assert!(stdout.contains("spt: 11\n"), "{}", stdout);
+  assert!(stdout.ends_with("matching: lps mwrm\n"), "{}", stdout);
+}
+
+#[test]
+fn invalid_input_fails_cleanly() {
+  let solve = |instance: &str, seed: &str| {
+    heuristics(&[
+      "--instance",
+      instance,
+      "--solver",
+      "tabu-search",
+      "--seed",
+      seed,
+      "--timeout",
+      "1",
+    ])
+  };
+  let cases = [
+    (
+      "tests/data/nonexistent.txt",
+      "0",
+      "Error reading instance file",
+    ),
+    (MALFORMED, "0", "Invalid instance: line 2, column 7"),
+    (FT06, "x", "Invalid value \"x\" for --seed"),
+  ];
+  for &(instance, seed, message) in &cases {
+    let output = solve(instance, seed);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(stdout(&output).is_empty());
+    let stderr = stderr(&output);
+    assert!(stderr.contains(message), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+  }
+
+  // The job count of the prelude is not trusted for allocations
+  let output = heuristics_with_stdin(
+    &[
+      "--instance",
+      "-",
+      "--solver",
+      "tabu-search",
+      "--seed",
+      "0",
+      "--timeout",
+      "1",
+    ],
+    "99999999999999999 6\n0 1\n",
+  );
+  assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+  assert!(
+    stderr(&output).contains("Invalid instance: line 3, column 1: Job 1 missing"),
+    "{}",
+    stderr(&output)
+  );
+}
+
+#[test]
+fn deterministic_solvers_without_seed() {
+  for solver in &[
+    "steepest-descent",
+    "hill-climber",
+    "priority-spt",
+    "sequential",
+  ] {
+    let solve = || heuristics(&["--instance", FT06, "--solver", solver, "--timeout", "1"]);
+    let output = solve();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), stdout(&solve()), "{}", solver);
+  }
+  let output = heuristics(&[
+    "--instance",
+    FT06,
+    "--solver",
+    "tabu-search",
+    "--timeout",
+    "1",
+  ]);
+  assert!(!output.status.success());
+}
+
+#[test]
+fn check_optimal() {
+  for &(solver, cmax, optimal) in &[("priority-sps", 11, "yes"), ("priority-mwrm", 12, "no")] {
+    let output = heuristics(&[
+      "--instance",
+      SMALL,
+      "--solver",
+      solver,
+      "--timeout",
+      "1",
+      "--check-optimal",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with(&format!("{}\n", cmax)), "{}", stdout);
+    assert!(
+      stdout.ends_with(&format!("optimum: 11\noptimal: {}\n", optimal)),
+      "{}",
+      stdout
+    );
+  }
+}
+
+#[test]
+fn stream_jsonl() {
+  let args = [
+    "--stream-jsonl",
+    "--solver",
+    "tabu-search",
+    "--seed",
+    "0",
+    "--timeout",
+    "1",
+  ];
+  let input = concat!(
+    "{\"n_machines\": 2, \"jobs\": [[[0, 3], [1, 2]], [[0, 2], [1, 4]]]}\n",
+    "\n",
+    "{\"n_machines\": 1, \"jobs\": [[[0, 3]], [[0, 4]]]}\n",
+  );
+  let output = heuristics_with_stdin(&args, input);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(
+    stdout(&output),
+    "{\"cmax\":8,\"ok\":true}\n{\"cmax\":7,\"ok\":true}\n"
+  );
+
+  // A malformed line results in an error line, the following lines are still solved
+  let input = concat!(
+    "{\"n_machines\": 1, \"jobs\": [[[0]]]}\n",
+    "{\"n_machines\": 1, \"jobs\": [[[1, 3]]]}\n",
+    "{\"n_machines\": 1, \"jobs\": [[[0, 3]], [[0, 4]]]}\n",
+  );
+  let output = heuristics_with_stdin(&args, input);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let stdout = stdout(&output);
+  let lines: Vec<&str> = stdout.lines().collect();
+  assert_eq!(lines.len(), 3, "{}", stdout);
+  assert!(
+    lines[0].starts_with("{\"ok\":false,\"error\":\"Invalid instance: Operation [0, 0]"),
+    "{}",
+    stdout
+  );
+  assert!(
+    lines[1].starts_with("{\"ok\":false,\"error\":\"Invalid instance: "),
+    "{}",
+    stdout
+  );
+  assert_eq!(lines[2], "{\"cmax\":7,\"ok\":true}");
+
+  // Options of a single solution are rejected instead of ignored
+  let mut args = args.to_vec();
+  args.extend(&["--output", "dispatch"]);
+  let output = heuristics_with_stdin(&args, "");
+  assert!(!output.status.success());
+  assert!(
+    stderr(&output).contains("cannot be used with"),
+    "{}",
+    stderr(&output)
+  );
+}
+
+#[test]
+fn invalid_checkpoints() {
+  for checkpoints in &["1,1e30", "-1", "1,x"] {
+    let checkpoints = format!("--checkpoints={}", checkpoints);
+    let output = heuristics(&[
+      "--instance",
+      FT06,
+      "--solver",
+      "tabu-search",
+      "--seed",
+      "0",
+      "--timeout",
+      "0",
+      "--benchmark",
+      &checkpoints,
+    ]);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    let stderr = stderr(&output);
+    assert!(stderr.contains("Invalid checkpoint"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+  }
+}
+
+#[test]
+fn compare() {
+  let output = heuristics(&[
+    "--instance",
+    SMALL,
+    "--solver",
+    "compare",
+    "--compare",
+    "priority-sps,priority-mwrm",
+    "--timeout",
+    "0",
+  ]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let stdout = stdout(&output);
+  let rows: Vec<Vec<&str>> = stdout
+    .lines()
+    .skip(1)
+    .take(2)
+    .map(|line| line.split_whitespace().collect())
+    .collect();
+  // Only the better rule is marked
+  assert_eq!(rows[0][..2], ["priority-sps", "11"]);
+  assert_eq!(rows[0].last(), Some(&"*"));
+  assert_eq!(rows[1][..2], ["priority-mwrm", "12"]);
+  assert_ne!(rows[1].last(), Some(&"*"));
+
+  // Symmetric pairwise distances, zero on the diagonal
+  let distances: Vec<Vec<&str>> = stdout
+    .lines()
+    .skip_while(|&line| line != "orientation_distance:")
+    .skip(2)
+    .map(|line| line.split_whitespace().collect())
+    .collect();
+  assert_eq!(distances.len(), 2);
+  assert_eq!(distances[0][1..], ["0", distances[1][1]]);
+  assert_eq!(distances[1][2], "0");
+  assert_ne!(distances[1][1], "0");
+}
+
+#[test]
+fn simulated_annealing_defaults() {
+  let output = heuristics(&[
+    "--instance",
+    FT06,
+    "--solver",
+    "simulated-annealing",
+    "--seed",
+    "0",
+    "--timeout",
+    "1",
+    "--benchmark",
+    "--verify",
+  ]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let stdout = stdout(&output);
+  assert!(
+    stdout.contains("sa_start_acceptance_ratio: 0.9\n"),
+    "{}",
+    stdout
+  );
+  // 10 / n_ops for the 36 operations of ft06
+  assert!(
+    stdout.contains(&format!("sa_delta: {}\n", 10.0 / 36.0)),
+    "{}",
+    stdout
+  );
+  let cmax: u32 = stdout
+    .lines()
+    .find(|line| line.starts_with("cmax: "))
+    .map(|line| &line["cmax: ".len()..])
+    .unwrap()
+    .parse()
+    .unwrap();
+  assert!(cmax >= 55);
+}
+
+#[test]
+fn solver_options_checked_when_streaming() {
+  let output = heuristics_with_stdin(
+    &[
+      "--stream-jsonl",
+      "--solver",
+      "tabu-search",
+      "--seed",
+      "0",
+      "--timeout",
+      "1",
+      "--hc-random-walk-probability",
+      "0.5",
+    ],
+    "{\"n_machines\": 1, \"jobs\": [[[0, 3]], [[0, 4]]]}\n",
+  );
+  assert!(!output.status.success());
+  assert!(
+    stderr(&output).contains("--hc-random-walk-probability is only supported by hill-climber"),
+    "{}",
+    stderr(&output)
+  );
+  assert_eq!(stdout(&output), "");
+}
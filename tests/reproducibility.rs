@@ -0,0 +1,93 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use heuristics::gantt::{to_svg, GanttColoring, GanttLayout};
+use heuristics::json::stream_line;
+use heuristics::parser::parse_instance;
+use heuristics::solver::hill_climber::HcConfig;
+use heuristics::solver::neighborhood::NeighborhoodKind;
+use heuristics::solver::spec::{solve_with_budget, Budget, PriorityRule, SolverSpec};
+use heuristics::solver::{calculate_cmax, format_solution};
+use std::fs;
+
+// Every solver, the metaheuristics with a fixed seed
+fn all_solvers() -> Vec<SolverSpec> {
+  let hill_climber = HcConfig {
+    allow_plateau: true,
+    max_plateau_steps: 10,
+    ..HcConfig::default()
+  };
+  let mut specs = vec![
+    SolverSpec::HillClimber(hill_climber.clone()),
+    // Stopped by the iteration budget
+    SolverSpec::HillClimber(HcConfig {
+      random_walk_probability: 0.2,
+      seed: 3,
+      ..hill_climber.clone()
+    }),
+    SolverSpec::SteepestDescent { verify: false },
+    SolverSpec::RandomRestartHillClimber {
+      seed: 3,
+      hill_climber: hill_climber,
+    },
+    SolverSpec::TabuSearch {
+      seed: 3,
+      neighborhood: NeighborhoodKind::N1,
+      skip_symmetric_swaps: false,
+      secondary_objective: None,
+      constraint: None,
+      verify: false,
+    },
+    SolverSpec::SimulatedAnnealing {
+      seed: 3,
+      start_acceptance_ratio: None,
+      delta: None,
+      neighborhood: NeighborhoodKind::N1Full,
+      skip_symmetric_swaps: false,
+      secondary_objective: None,
+      constraint: None,
+      verify: false,
+    },
+    SolverSpec::BestConstruct {
+      seed: 3,
+      random_constructions: 20,
+    },
+    SolverSpec::Sequential,
+  ];
+  specs.extend(
+    PriorityRule::ALL
+      .iter()
+      .map(|&rule| SolverSpec::Priority(rule)),
+  );
+  return specs;
+}
+
+// Everything printed for a solution, the matrix, the --stream-jsonl line and both Gantt charts
+fn outputs(spec: &SolverSpec) -> Vec<String> {
+  let inst = parse_instance(&fs::read_to_string("tests/data/ft06.txt").unwrap()).unwrap();
+  let (solution, _) = solve_with_budget(&inst, spec, Budget::Iterations(200)).unwrap();
+  let mut outputs = vec![
+    format_solution(&inst, &solution),
+    stream_line(&Ok(calculate_cmax(&inst, &solution))),
+  ];
+  for &layout in &[GanttLayout::ByMachine, GanttLayout::ByJob] {
+    outputs.push(
+      to_svg(
+        &inst,
+        &solution,
+        layout,
+        &GanttColoring::default_for(layout),
+      )
+      .unwrap(),
+    );
+  }
+  return outputs;
+}
+
+#[test]
+fn identical_runs_give_identical_outputs() {
+  for spec in all_solvers() {
+    let first = outputs(&spec);
+    assert!(first.iter().all(|output| !output.is_empty()), "{:?}", spec);
+    assert_eq!(first, outputs(&spec), "{:?}", spec);
+  }
+}